@@ -12,7 +12,7 @@ fn main() {
     let color = Color::new(1.0, 1.0, 1.0);
     for i in 0..12 {
         let rotated = Matrix::rotation(Axis::Y, i as f32 * (PI / 6f32));
-        let point = &rotated * &twelve;
+        let point = rotated * twelve;
         let final_point = &(point.multiply(150.0)) + &middle;
         let x = final_point.x.round() as usize;
         let y = final_point.z.round() as usize;
@@ -0,0 +1,51 @@
+use std::f32::consts::PI;
+
+use ray_tracer::{tuple::Tuple, color::Color, matrix::Matrix, transform::Transform, helper::Axis, sphere::Sphere,
+                material::Material, light::PointLight, world::World, camera::Camera, shape::Shape};
+
+fn main() {
+    let wall_material = Material { color: Color::new(1.0, 0.9, 0.9), specular: 0.0, ..Material::default() };
+
+    let mut floor = Sphere::new();
+    floor.set_transform(Matrix::scaling(10.0, 0.01, 10.0));
+    floor.set_material(wall_material.clone());
+
+    let mut left_wall = Sphere::new();
+    left_wall.set_transform(Transform::identity().scale(10.0, 0.01, 10.0).rotate(Axis::X, PI / 2.0)
+                            .rotate(Axis::Y, -PI / 4.0).translate(0.0, 0.0, 5.0).into());
+    left_wall.set_material(wall_material.clone());
+
+    let mut right_wall = Sphere::new();
+    right_wall.set_transform(Transform::identity().scale(10.0, 0.01, 10.0).rotate(Axis::X, PI / 2.0)
+                             .rotate(Axis::Y, PI / 4.0).translate(0.0, 0.0, 5.0).into());
+    right_wall.set_material(wall_material);
+
+    let mut middle = Sphere::new();
+    middle.set_transform(Matrix::translation(-0.5, 1.0, 0.5));
+    middle.set_material(Material { color: Color::new(0.1, 1.0, 0.5), diffuse: 0.7, specular: 0.3, ..Material::default() });
+
+    let mut right = Sphere::new();
+    right.set_transform(Transform::identity().scale(0.5, 0.5, 0.5).translate(1.5, 0.5, -0.5).into());
+    right.set_material(Material { color: Color::new(0.5, 1.0, 0.1), diffuse: 0.7, specular: 0.3, ..Material::default() });
+
+    let mut left = Sphere::new();
+    left.set_transform(Transform::identity().scale(0.33, 0.33, 0.33).translate(-1.5, 0.33, -0.75).into());
+    left.set_material(Material { color: Color::new(1.0, 0.8, 0.1), diffuse: 0.7, specular: 0.3, ..Material::default() });
+
+    let mut world = World::new();
+    world.add_light(PointLight::new(Tuple::new_point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+    world.add_object(Box::new(floor));
+    world.add_object(Box::new(left_wall));
+    world.add_object(Box::new(right_wall));
+    world.add_object(Box::new(middle));
+    world.add_object(Box::new(right));
+    world.add_object(Box::new(left));
+
+    let mut camera = Camera::new(400, 200, PI / 3.0);
+    camera.set_transform(Matrix::view_transform(&Tuple::new_point(0.0, 1.5, -5.0),
+                                                &Tuple::new_point(0.0, 1.0, 0.0),
+                                                &Tuple::new_vector(0.0, 1.0, 0.0)));
+
+    let canvas = camera.render(&world);
+    canvas.write_to_file("images/chapter_07.ppm");
+}
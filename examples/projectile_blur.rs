@@ -0,0 +1,44 @@
+use std::f32::consts::PI;
+
+use ray_tracer::{tuple::{Tuple, Environment}, projectile::Projectile, color::Color, matrix::Matrix,
+                transform::Transform, sphere::Sphere, plane::Plane, material::Material, light::PointLight,
+                world::World, camera::{Camera, RenderOptions}, shape::Shape};
+
+fn main() {
+    let gravity = Tuple::new_vector(0.0, -0.1, 0.0);
+    let wind = Tuple::new_vector(-0.01, 0.0, 0.0);
+    let environment = Environment::new(gravity, wind);
+    let mut projectile = Projectile::new(Tuple::new_point(0.0, 1.0, 0.0),
+                                         Tuple::new_vector(1.0, 1.8, 0.0).normalize().multiply(2.0));
+
+    let mut world = World::new();
+    world.add_light(PointLight::new(Tuple::new_point(-10.0, 20.0, -20.0), Color::new(1.0, 1.0, 1.0)));
+    let mut floor = Plane::new();
+    floor.set_material(Material { color: Color::new(0.8, 0.8, 0.7), specular: 0.0, ..Material::default() });
+    world.add_object(Box::new(floor));
+
+    // every few ticks a ball travels from where the projectile is to where the next tick takes it
+    let ball = Material { color: Color::new(1.0, 0.2, 0.1), diffuse: 0.7, specular: 0.3, ..Material::default() };
+    let mut tick = 0;
+    while projectile.point.y > 0.0 {
+        let start = projectile.point;
+        projectile = projectile.tick(&environment);
+        if tick % 3 == 0 {
+            let end = projectile.point;
+            let mut sphere = Sphere::new();
+            sphere.set_motion(Transform::identity().scale(0.25, 0.25, 0.25).translate(start.x, start.y, start.z).into(),
+                              Transform::identity().scale(0.25, 0.25, 0.25).translate(end.x, end.y, end.z).into());
+            sphere.set_material(ball.clone());
+            world.add_object(Box::new(sphere));
+        }
+        tick += 1;
+    }
+
+    let mut camera = Camera::new(600, 300, PI / 3.0);
+    camera.set_transform(Matrix::view_transform(&Tuple::new_point(14.0, 8.0, -36.0),
+                                                &Tuple::new_point(14.0, 8.0, 0.0),
+                                                &Tuple::new_vector(0.0, 1.0, 0.0)));
+    let options = RenderOptions { samples_per_pixel: 16, threads: 4, ..RenderOptions::default() };
+    let canvas = camera.render_with(&world, &options, &mut |_, _| {});
+    canvas.save("images/projectile_blur.png").expect("cannot write images/projectile_blur.png");
+}
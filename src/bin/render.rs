@@ -0,0 +1,294 @@
+use std::{env, io::{self, Write}, path::PathBuf, process, thread,
+          time::{Duration, Instant}};
+
+use ray_tracer::{scene::{load_scene_file, SceneError}, camera::RenderOptions, canvas::ImageFormat,
+                 sampling::{Sampler, Filter}};
+
+const USAGE: &str = "\
+Renders a YAML or JSON scene file to an image.
+
+Usage: render <scene> [options]
+
+Options:
+  -o, --output <path>    image to write, defaults to the scene name with the format's extension
+  -w, --width <pixels>   overrides the width of the scene's camera
+  -H, --height <pixels>  overrides the height of the scene's camera
+  -s, --samples <count>  rays per pixel (default 1)
+      --sampler <name>   how the rays spread over a pixel: grid, jittered, stratified
+                         or rotated-grid (default grid)
+      --filter <name>    how the rays are weighed: box, tent, gaussian or mitchell (default box)
+  -t, --threads <count>  render threads (default: available cores)
+      --tile-size <pixels>
+                         side of the square tiles the threads take turns on (default 16)
+  -f, --format <name>    output format: png, ppm (binary P6), p3 (ASCII PPM) or pfm (floating point)
+                         (default: from the output extension, png without an output)
+  -q, --quiet            no progress bar
+  -h, --help             shows this message";
+
+#[derive(Debug)]
+struct Arguments {
+    scene: PathBuf,
+    output: Option<PathBuf>,
+    width: Option<usize>,
+    height: Option<usize>,
+    samples: usize,
+    sampler: Sampler,
+    filter: Filter,
+    threads: usize,
+    tile_size: usize,
+    format: Option<ImageFormat>,
+    quiet: bool,
+    /// Only print the usage, the other arguments are not checked
+    help: bool
+}
+
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut scene = None;
+    let mut arguments = Arguments {
+        scene: PathBuf::new(),
+        output: None,
+        width: None,
+        height: None,
+        samples: 1,
+        sampler: Sampler::default(),
+        filter: Filter::default(),
+        threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        tile_size: RenderOptions::default().tile_size,
+        format: None,
+        quiet: false,
+        help: false
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                arguments.help = true;
+                return Ok(arguments);
+            }
+            "-o" | "--output" => arguments.output = Some(PathBuf::from(value(&arg)?)),
+            "-w" | "--width" => arguments.width = Some(positive(&arg, &value(&arg)?)?),
+            "-H" | "--height" => arguments.height = Some(positive(&arg, &value(&arg)?)?),
+            "-s" | "--samples" => arguments.samples = positive(&arg, &value(&arg)?)?,
+            "--sampler" => {
+                let name = value(&arg)?;
+                arguments.sampler = Sampler::from_name(&name).ok_or_else(|| format!("unknown sampler '{}'", name))?;
+            }
+            "--filter" => {
+                let name = value(&arg)?;
+                arguments.filter = Filter::from_name(&name).ok_or_else(|| format!("unknown filter '{}'", name))?;
+            }
+            "-t" | "--threads" => arguments.threads = positive(&arg, &value(&arg)?)?,
+            "--tile-size" => arguments.tile_size = positive(&arg, &value(&arg)?)?,
+            "-f" | "--format" => {
+                let name = value(&arg)?;
+                arguments.format = Some(ImageFormat::from_name(&name).ok_or_else(|| format!("unsupported format '{}'", name))?);
+            }
+            "-q" | "--quiet" => arguments.quiet = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument '{}'", arg))
+        }
+    }
+
+    arguments.scene = scene.ok_or("missing the scene file")?;
+    Ok(arguments)
+}
+
+fn positive(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{} expects a positive whole number, got '{}'", name, value))
+    }
+}
+
+/// Picks the format from the option, then from the output extension, then falls back to PNG
+fn output_format(arguments: &Arguments) -> Result<(PathBuf, ImageFormat), String> {
+    match (&arguments.output, arguments.format) {
+        (Some(output), Some(format)) => Ok((output.clone(), format)),
+        (Some(output), None) => match ImageFormat::from_path(output) {
+            Some(format) => Ok((output.clone(), format)),
+            None => Err(format!("cannot tell the format of {}, use --format", output.display()))
+        },
+        (None, format) => {
+            let format = format.unwrap_or(ImageFormat::Png);
+            Ok((arguments.scene.with_extension(format.extension()), format))
+        }
+    }
+}
+
+/// A single line bar on stderr, redrawn only when the percentage changes
+struct ProgressBar {
+    start: Instant,
+    last_percent: Option<usize>
+}
+
+impl ProgressBar {
+    const WIDTH: usize = 40;
+
+    fn new() -> ProgressBar {
+        ProgressBar { start: Instant::now(), last_percent: None }
+    }
+
+    fn update(&mut self, done: usize, total: usize) {
+        let percent = done * 100 / total.max(1);
+        if self.last_percent == Some(percent) {
+            return;
+        }
+        self.last_percent = Some(percent);
+
+        let filled = done * Self::WIDTH / total.max(1);
+        let elapsed = self.start.elapsed();
+        let remaining = if done > 0 {
+            elapsed.mul_f64((total - done) as f64 / done as f64)
+        } else {
+            Duration::ZERO
+        };
+        eprint!("\r[{}{}] {:3}% {}/{} tiles, {} left ",
+                "#".repeat(filled), " ".repeat(Self::WIDTH - filled), percent, done, total,
+                format_duration(remaining));
+        let _ = io::stderr().flush();
+    }
+
+    fn finish(&self) {
+        eprintln!();
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 60.0 {
+        format!("{:.2}s", seconds)
+    } else {
+        format!("{}m{:02}s", (seconds / 60.0) as u64, (seconds % 60.0) as u64)
+    }
+}
+
+fn run(arguments: Arguments) -> Result<(), String> {
+    let (output, format) = output_format(&arguments)?;
+
+    let start = Instant::now();
+    let mut scene = load_scene_file(&arguments.scene).map_err(|error| match error {
+        SceneError::Io { .. } => error.to_string(),
+        SceneError::Invalid { .. } => format!("{}: {}", arguments.scene.display(), error)
+    })?;
+    let load_time = start.elapsed();
+
+    if arguments.width.is_some() || arguments.height.is_some() {
+        let width = arguments.width.unwrap_or(scene.camera.hsize);
+        let height = arguments.height.unwrap_or(scene.camera.vsize);
+        scene.camera = scene.camera.with_size(width, height);
+    }
+
+    let options = RenderOptions {
+        samples_per_pixel: arguments.samples,
+        sampler: arguments.sampler,
+        filter: arguments.filter,
+        threads: arguments.threads,
+        tile_size: arguments.tile_size
+    };
+    let start = Instant::now();
+    let canvas = if arguments.quiet {
+        scene.camera.render_with(&scene.world, &options, &mut |_, _| {})
+    } else {
+        let mut progress = ProgressBar::new();
+        let canvas = scene.camera.render_with(&scene.world, &options, &mut |done, total| progress.update(done, total));
+        progress.finish();
+        canvas
+    };
+    let render_time = start.elapsed();
+
+    let start = Instant::now();
+    canvas.save_as(&output, format).map_err(|error| format!("{}: {}", output.display(), error))?;
+    let write_time = start.elapsed();
+
+    let pixels = scene.camera.hsize * scene.camera.vsize;
+    eprintln!("scene   {} ({} objects, {} lights) loaded in {}", arguments.scene.display(),
+              scene.world.objects.len(), scene.world.lights.len(), format_duration(load_time));
+    eprintln!("render  {}x{}, {} {} samples per pixel with a {} filter on {} threads in {} ({:.0} pixels/s)",
+              scene.camera.hsize, scene.camera.vsize, options.samples_per_pixel, options.sampler.name(),
+              options.filter.name(), options.threads,
+              format_duration(render_time), pixels as f64 / render_time.as_secs_f64().max(1e-9));
+    eprintln!("output  {} written in {}", output.display(), format_duration(write_time));
+    Ok(())
+}
+
+fn main() {
+    let result = parse_arguments(env::args().skip(1)).and_then(|arguments| {
+        if arguments.help {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        run(arguments)
+    });
+    if let Err(message) = result {
+        eprintln!("error: {}", message);
+        eprintln!("run with --help for usage");
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Arguments, String> {
+        parse_arguments(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parsing_the_options() {
+        let arguments = parse(&["scene.yml", "-w", "320", "-H", "200", "-s", "6", "--sampler", "stratified",
+                                "--filter", "mitchell", "-t", "2", "-f", "p3", "-o", "out.ppm", "-q"]).unwrap();
+        assert_eq!(arguments.scene, PathBuf::from("scene.yml"));
+        assert_eq!((arguments.width, arguments.height), (Some(320), Some(200)));
+        assert_eq!(arguments.samples, 6);
+        assert_eq!(arguments.sampler, Sampler::Stratified);
+        assert_eq!(arguments.filter, Filter::Mitchell);
+        assert_eq!(arguments.threads, 2);
+        assert_eq!(arguments.format, Some(ImageFormat::PpmAscii));
+        assert_eq!(arguments.output, Some(PathBuf::from("out.ppm")));
+        assert!(arguments.quiet);
+        assert!(!arguments.help);
+    }
+
+    #[test]
+    fn asking_for_help_needs_no_scene() {
+        assert!(parse(&["-h"]).unwrap().help);
+        assert!(parse(&["scene.yml", "--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn bad_numbers_are_errors() {
+        assert_eq!(parse(&["scene.yml", "-w", "wide"]).unwrap_err(), "-w expects a positive whole number, got 'wide'");
+        assert_eq!(parse(&["scene.yml", "--samples", "0"]).unwrap_err(), "--samples expects a positive whole number, got '0'");
+        assert!(parse(&["scene.yml", "-H", "-5"]).is_err());
+        assert!(parse(&["scene.yml", "-t", "1.5"]).is_err());
+        assert_eq!(parse(&["scene.yml", "--tile-size"]).unwrap_err(), "--tile-size needs a value");
+    }
+
+    #[test]
+    fn a_missing_scene_is_an_error() {
+        assert_eq!(parse(&[]).unwrap_err(), "missing the scene file");
+        assert_eq!(parse(&["-w", "10"]).unwrap_err(), "missing the scene file");
+        assert_eq!(parse(&["a.yml", "b.yml"]).unwrap_err(), "unexpected argument 'b.yml'");
+    }
+
+    #[test]
+    fn unknown_names_are_errors() {
+        assert_eq!(parse(&["scene.yml", "-f", "gif"]).unwrap_err(), "unsupported format 'gif'");
+        assert_eq!(parse(&["scene.yml", "--sampler", "halton"]).unwrap_err(), "unknown sampler 'halton'");
+        assert_eq!(parse(&["scene.yml", "--filter", "lanczos"]).unwrap_err(), "unknown filter 'lanczos'");
+        assert_eq!(parse(&["scene.yml", "--height", "10", "-x"]).unwrap_err(), "unknown option '-x'");
+    }
+
+    #[test]
+    fn the_format_comes_from_the_option_then_the_extension() {
+        let arguments = parse(&["scenes/cover.yml"]).unwrap();
+        assert_eq!(output_format(&arguments).unwrap(), (PathBuf::from("scenes/cover.png"), ImageFormat::Png));
+        let arguments = parse(&["scene.yml", "-o", "image.pfm"]).unwrap();
+        assert_eq!(output_format(&arguments).unwrap(), (PathBuf::from("image.pfm"), ImageFormat::Pfm));
+        let arguments = parse(&["scene.yml", "-o", "image.out"]).unwrap();
+        assert!(output_format(&arguments).is_err());
+    }
+}
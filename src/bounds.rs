@@ -0,0 +1,236 @@
+use crate::{tuple::Tuple, ray::Ray, matrix::Matrix, helper::EPSILON};
+
+/// An axis aligned box. Unbounded shapes such as planes use infinite
+/// coordinates, an empty box has its minimum above its maximum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox::new_empty()
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// A box that contains nothing, adding a point to it gives a box around that point
+    pub fn new_empty() -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+                         Tuple::new_point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY))
+    }
+
+    pub fn new_infinite() -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+                         Tuple::new_point(f32::INFINITY, f32::INFINITY, f32::INFINITY))
+    }
+
+    /// The smallest box around the given points
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Tuple>) -> BoundingBox {
+        let mut bounds = BoundingBox::new_empty();
+        for point in points {
+            bounds.add_point(point);
+        }
+        bounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z].iter().all(|value| value.is_finite())
+    }
+
+    pub fn add_point(&mut self, point: &Tuple) {
+        self.min = Tuple::new_point(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z));
+        self.max = Tuple::new_point(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z));
+    }
+
+    /// Returns the smallest box containing both boxes
+    pub fn merge(&self, other: &BoundingBox) -> BoundingBox {
+        if other.is_empty() {
+            return *self;
+        }
+        let mut bounds = *self;
+        bounds.add_point(&other.min);
+        bounds.add_point(&other.max);
+        bounds
+    }
+
+    pub fn contains_point(&self, point: &Tuple) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        Tuple::new_point((self.min.x + self.max.x) / 2.0, (self.min.y + self.max.y) / 2.0, (self.min.z + self.max.z) / 2.0)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = &self.max - &self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// Returns the box around this box once transformed by the matrix.
+    /// Each coordinate is accumulated separately (Arvo's method) so that
+    /// zero matrix entries never multiply an infinite extent.
+    pub fn transform(&self, matrix: &Matrix) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+        let mut new_min = [0.0; 3];
+        let mut new_max = [0.0; 3];
+        for row in 0..3 {
+            new_min[row] = matrix[(row, 3)];
+            new_max[row] = new_min[row];
+            for column in 0..3 {
+                let factor = matrix[(row, column)];
+                if factor == 0.0 {
+                    continue;
+                }
+                let (a, b) = (factor * min[column], factor * max[column]);
+                new_min[row] += a.min(b);
+                new_max[row] += a.max(b);
+            }
+        }
+        BoundingBox::new(Tuple::new_point(new_min[0], new_min[1], new_min[2]),
+                         Tuple::new_point(new_max[0], new_max[1], new_max[2]))
+    }
+
+    /// Slab test along the whole line of the ray, hits behind the origin count too
+    /// since shapes report those intersections as well
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+        let axes = [(ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+                    (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+                    (ray.origin.z, ray.direction.z, self.min.z, self.max.z)];
+        for (origin, direction, min, max) in axes {
+            if direction.abs() < EPSILON {
+                if origin < min - EPSILON || origin > max + EPSILON {
+                    return false;
+                }
+                continue;
+            }
+            let (t1, t2) = ((min - origin) / direction, (max - origin) / direction);
+            tmin = tmin.max(t1.min(t2));
+            tmax = tmax.min(t1.max(t2));
+        }
+        tmin <= tmax + EPSILON
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{PI, SQRT_2};
+
+    use crate::helper::Axis;
+
+    use super::*;
+
+    fn point(x: f32, y: f32, z: f32) -> Tuple {
+        Tuple::new_point(x, y, z)
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = BoundingBox::new_empty();
+        assert!(b.is_empty());
+        b.add_point(&point(-5.0, 2.0, 0.0));
+        b.add_point(&point(7.0, 0.0, -3.0));
+        assert_eq!(b.min, point(-5.0, 0.0, -3.0));
+        assert_eq!(b.max, point(7.0, 2.0, 0.0));
+        assert!(!b.is_empty());
+    }
+
+    #[test]
+    fn merging_two_bounding_boxes() {
+        let b1 = BoundingBox::new(point(-5.0, -2.0, 0.0), point(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(point(8.0, -7.0, -2.0), point(14.0, 2.0, 8.0));
+        let b = b1.merge(&b2);
+        assert_eq!(b.min, point(-5.0, -7.0, -2.0));
+        assert_eq!(b.max, point(14.0, 4.0, 8.0));
+        assert_eq!(BoundingBox::new_empty().merge(&b1), b1);
+        assert_eq!(b1.merge(&BoundingBox::new_empty()), b1);
+    }
+
+    #[test]
+    fn checking_if_a_box_contains_points_and_boxes() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        assert!(b.contains_point(&point(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(&point(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(&point(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(&point(8.0, 1.0, 8.0)));
+        assert!(b.contains_box(&BoundingBox::new(point(6.0, -1.0, 1.0), point(10.0, 3.0, 6.0))));
+        assert!(!b.contains_box(&BoundingBox::new(point(4.0, -3.0, -1.0), point(10.0, 3.0, 6.0))));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let matrix = Matrix::rotation(Axis::X, PI / 4.0) * Matrix::rotation(Axis::Y, PI / 4.0);
+        let t = b.transform(&matrix);
+        assert_eq!(t.min, point(-SQRT_2, -1.70711, -1.70711));
+        assert_eq!(t.max, point(SQRT_2, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn transforming_an_unbounded_box_keeps_the_bounded_axes() {
+        let plane = BoundingBox::new(point(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY), point(f32::INFINITY, 0.0, f32::INFINITY));
+        let t = plane.transform(&Matrix::translation(1.0, 2.0, 3.0));
+        assert_eq!((t.min.y, t.max.y), (2.0, 2.0));
+        assert_eq!((t.min.x, t.max.z), (f32::NEG_INFINITY, f32::INFINITY));
+        assert!(!t.is_finite());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let cases = [
+            (point(15.0, 1.0, 2.0), Tuple::new_vector(-1.0, 0.0, 0.0), true),
+            (point(-5.0, -1.0, 4.0), Tuple::new_vector(1.0, 0.0, 0.0), true),
+            (point(7.0, 6.0, 5.0), Tuple::new_vector(0.0, -1.0, 0.0), true),
+            (point(9.0, -5.0, 6.0), Tuple::new_vector(0.0, 1.0, 0.0), true),
+            (point(8.0, 2.0, 12.0), Tuple::new_vector(0.0, 0.0, -1.0), true),
+            (point(6.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), true),
+            (point(8.0, 1.0, 3.5), Tuple::new_vector(0.0, 0.0, 1.0), true),
+            (point(9.0, -1.0, -8.0), Tuple::new_vector(2.0, 4.0, 6.0), false),
+            (point(8.0, 3.0, -4.0), Tuple::new_vector(6.0, 2.0, 4.0), false),
+            (point(9.0, -1.0, -2.0), Tuple::new_vector(4.0, 6.0, 2.0), false),
+            (point(4.0, 0.0, 9.0), Tuple::new_vector(0.0, 0.0, -1.0), false),
+            (point(8.0, 6.0, -1.0), Tuple::new_vector(0.0, -1.0, 0.0), false),
+            (point(12.0, 5.0, 4.0), Tuple::new_vector(-1.0, 0.0, 0.0), false)
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected, "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn a_flat_box_is_hit_head_on() {
+        let b = BoundingBox::new(point(-1.0, -1.0, 0.0), point(1.0, 1.0, 0.0));
+        assert!(b.intersects(&Ray::new(point(0.5, 0.5, -3.0), Tuple::new_vector(0.0, 0.0, 1.0))));
+        assert!(BoundingBox::new_infinite().intersects(&Ray::new(point(0.0, 0.0, 0.0), Tuple::new_vector(1.0, 0.0, 0.0))));
+        assert!(!BoundingBox::new_empty().intersects(&Ray::new(point(0.0, 0.0, 0.0), Tuple::new_vector(1.0, 1.0, 0.0))));
+    }
+}
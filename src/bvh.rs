@@ -0,0 +1,263 @@
+use crate::{bounds::BoundingBox, ray::Ray, tuple::Tuple};
+
+/// Bounding volume hierarchy over a list of boxes, such as the children of a group.
+/// It stores indices into that list, so the shapes themselves stay where they are.
+/// Unbounded boxes, like those of planes, cannot be split and are always visited.
+#[derive(Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+    bounds: BoundingBox
+}
+
+#[derive(Debug)]
+struct Node {
+    bounds: BoundingBox,
+    content: Content
+}
+
+#[derive(Debug)]
+enum Content {
+    /// Range of `Bvh::indices`
+    Leaf { start: usize, count: usize },
+    /// Positions of the two children in `Bvh::nodes`
+    Branch { left: usize, right: usize }
+}
+
+/// A box to sort into the tree, along with its position in the original list
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: Tuple
+}
+
+impl Bvh {
+    /// Leaves are never split below this size
+    pub const MAX_LEAF_SIZE: usize = 4;
+    /// Number of buckets the surface area heuristic evaluates per split
+    const BINS: usize = 12;
+
+    /// Builds the tree with the surface area heuristic, falling back to a median
+    /// split when the heuristic cannot separate the boxes
+    pub fn build(boxes: &[BoundingBox]) -> Bvh {
+        let mut bvh = Bvh::default();
+        let mut items = Vec::with_capacity(boxes.len());
+        for (index, bounds) in boxes.iter().enumerate() {
+            bvh.bounds = bvh.bounds.merge(bounds);
+            if bounds.is_empty() {
+                continue;
+            }
+            if bounds.is_finite() {
+                items.push(Item { index, bounds: *bounds, centroid: bounds.centroid() });
+            } else {
+                bvh.unbounded.push(index);
+            }
+        }
+        if !items.is_empty() {
+            bvh.build_node(&mut items);
+        }
+        bvh
+    }
+
+    /// Returns the box around everything the tree was built from
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    /// Number of nodes on the longest path from the root to a leaf, 0 for an empty tree
+    pub fn depth(&self) -> usize {
+        fn depth_of(bvh: &Bvh, node: usize) -> usize {
+            match bvh.nodes[node].content {
+                Content::Leaf { .. } => 1,
+                Content::Branch { left, right } => 1 + depth_of(bvh, left).max(depth_of(bvh, right))
+            }
+        }
+        if self.nodes.is_empty() { 0 } else { depth_of(self, 0) }
+    }
+
+    /// Calls `visit` with the index of every box in a leaf the ray passes through,
+    /// and of every unbounded box
+    pub fn visit(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for &index in &self.unbounded {
+            visit(index);
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+            match node.content {
+                Content::Leaf { start, count } => {
+                    for &index in &self.indices[start..start + count] {
+                        visit(index);
+                    }
+                }
+                Content::Branch { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+    }
+
+    /// Appends the node for the items and its subtree, returning its position
+    fn build_node(&mut self, items: &mut [Item]) -> usize {
+        let bounds = items.iter().fold(BoundingBox::new_empty(), |bounds, item| bounds.merge(&item.bounds));
+        let position = self.nodes.len();
+        self.nodes.push(Node { bounds, content: Content::Leaf { start: 0, count: 0 } });
+
+        let content = match Bvh::split(items, &bounds) {
+            Some(middle) => {
+                let (left_items, right_items) = items.split_at_mut(middle);
+                let left = self.build_node(left_items);
+                let right = self.build_node(right_items);
+                Content::Branch { left, right }
+            }
+            None => {
+                let start = self.indices.len();
+                self.indices.extend(items.iter().map(|item| item.index));
+                Content::Leaf { start, count: items.len() }
+            }
+        };
+        self.nodes[position].content = content;
+        position
+    }
+
+    /// Reorders the items into two halves and returns where the second one starts,
+    /// or `None` when they are better off in a single leaf
+    fn split(items: &mut [Item], bounds: &BoundingBox) -> Option<usize> {
+        if items.len() <= Bvh::MAX_LEAF_SIZE {
+            return None;
+        }
+
+        let centroids = BoundingBox::from_points(items.iter().map(|item| &item.centroid));
+        let extent = &centroids.max - &centroids.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+        let coordinate = move |point: &Tuple| [point.x, point.y, point.z][axis];
+        let (low, size) = (coordinate(&centroids.min), coordinate(&extent));
+
+        let median = |items: &mut [Item]| {
+            let middle = items.len() / 2;
+            items.select_nth_unstable_by(middle, |a, b| coordinate(&a.centroid).total_cmp(&coordinate(&b.centroid)));
+            Some(middle)
+        };
+        if size <= 0.0 {
+            return median(items);
+        }
+
+        let bin_of = |item: &Item| (((coordinate(&item.centroid) - low) / size * Bvh::BINS as f32) as usize).min(Bvh::BINS - 1);
+        let mut bins = [(BoundingBox::new_empty(), 0usize); Bvh::BINS];
+        for item in items.iter() {
+            let bin = &mut bins[bin_of(item)];
+            *bin = (bin.0.merge(&item.bounds), bin.1 + 1);
+        }
+
+        // cost of splitting after each bin, relative to the cost of testing every item
+        let mut best: Option<(f32, usize)> = None;
+        for split in 1..Bvh::BINS {
+            let (left, right) = bins.split_at(split);
+            let side = |bins: &[(BoundingBox, usize)]| bins.iter().fold((BoundingBox::new_empty(), 0), |(bounds, count), bin| {
+                (bounds.merge(&bin.0), count + bin.1)
+            });
+            let ((left_bounds, left_count), (right_bounds, right_count)) = (side(left), side(right));
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+            let cost = left_bounds.surface_area() * left_count as f32 + right_bounds.surface_area() * right_count as f32;
+            if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                best = Some((cost, split));
+            }
+        }
+
+        let leaf_cost = bounds.surface_area() * items.len() as f32;
+        match best {
+            Some((cost, _)) if cost >= leaf_cost && items.len() <= 4 * Bvh::MAX_LEAF_SIZE => None,
+            Some((_, split)) => {
+                let mut middle = 0;
+                for i in 0..items.len() {
+                    if bin_of(&items[i]) < split {
+                        items.swap(i, middle);
+                        middle += 1;
+                    }
+                }
+                Some(middle)
+            }
+            None => median(items)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box_at(x: f32, y: f32, z: f32) -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(x - 0.5, y - 0.5, z - 0.5), Tuple::new_point(x + 0.5, y + 0.5, z + 0.5))
+    }
+
+    fn visited(bvh: &Bvh, ray: &Ray) -> Vec<usize> {
+        let mut indices = vec![];
+        bvh.visit(ray, |index| indices.push(index));
+        indices.sort();
+        indices
+    }
+
+    #[test]
+    fn a_small_list_stays_in_a_single_leaf() {
+        let boxes: Vec<BoundingBox> = (0..3).map(|i| unit_box_at(i as f32 * 3.0, 0.0, 0.0)).collect();
+        let bvh = Bvh::build(&boxes);
+        assert_eq!(bvh.depth(), 1);
+        assert_eq!(bvh.bounds(), boxes[0].merge(&boxes[2]));
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_eq!(visited(&bvh, &r), vec![0, 1, 2]);
+        let r = Ray::new(Tuple::new_point(0.0, 5.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(visited(&bvh, &r).is_empty());
+    }
+
+    #[test]
+    fn traversal_only_reaches_the_boxes_along_the_ray() {
+        let mut boxes = vec![];
+        for x in 0..20 {
+            for y in 0..20 {
+                boxes.push(unit_box_at(x as f32 * 2.0, y as f32 * 2.0, 0.0));
+            }
+        }
+        let bvh = Bvh::build(&boxes);
+        assert!(bvh.depth() > 1 && bvh.depth() < 20, "depth {}", bvh.depth());
+
+        // whole leaves are visited, so neighbours sharing the leaf come along
+        let r = Ray::new(Tuple::new_point(6.0, 10.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let indices = visited(&bvh, &r);
+        assert!(indices.contains(&(3 * 20 + 5)));
+        assert!(indices.len() <= Bvh::MAX_LEAF_SIZE, "{:?}", indices);
+        let r = Ray::new(Tuple::new_point(-5.0, 4.0, 0.0), Tuple::new_vector(1.0, 0.0, 0.0));
+        let indices = visited(&bvh, &r);
+        assert!((0..20).all(|x| indices.contains(&(x * 20 + 2))));
+        assert!(indices.len() <= 20 * Bvh::MAX_LEAF_SIZE, "{:?}", indices);
+    }
+
+    #[test]
+    fn identical_boxes_are_split_by_count() {
+        let boxes = vec![unit_box_at(1.0, 1.0, 1.0); 40];
+        let bvh = Bvh::build(&boxes);
+        assert!(bvh.depth() > 1);
+        let r = Ray::new(Tuple::new_point(1.0, 1.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_eq!(visited(&bvh, &r), (0..40).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unbounded_and_empty_boxes() {
+        let boxes = [unit_box_at(0.0, 0.0, 0.0), BoundingBox::new_infinite(), BoundingBox::new_empty()];
+        let bvh = Bvh::build(&boxes);
+        assert!(!bvh.bounds().is_finite());
+        let r = Ray::new(Tuple::new_point(0.0, 5.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_eq!(visited(&bvh, &r), vec![1]);
+        assert_eq!(Bvh::build(&[]).depth(), 0);
+    }
+}
@@ -0,0 +1,605 @@
+use std::{f32::consts::PI, sync::{atomic::{AtomicUsize, Ordering}, mpsc}, thread};
+
+use crate::{matrix::Matrix, shape::CachedTransform, ray::Ray, tuple::Tuple,
+            world::World, canvas::Canvas, color::Color, sampling::{Sampler, Filter, grid_size}};
+
+/// How `Camera::render_with` samples the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// Rays per pixel, laid out on the grid of `sampling::grid_size`
+    pub samples_per_pixel: usize,
+    pub sampler: Sampler,
+    /// Weighs the rays of a pixel into its color
+    pub filter: Filter,
+    pub threads: usize,
+    /// Width and height in pixels of the square tiles handed to the threads
+    pub tile_size: usize
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { samples_per_pixel: 1, sampler: Sampler::Grid, filter: Filter::Box, threads: 1, tile_size: 16 }
+    }
+}
+
+/// A rectangle of the image rendered by one thread in one go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize
+}
+
+/// Cuts the image into tiles row by row, the ones on the right and bottom edges may be smaller
+pub fn tiles(hsize: usize, vsize: usize, tile_size: usize) -> Vec<Tile> {
+    let size = tile_size.max(1);
+    (0..vsize).step_by(size)
+        .flat_map(|y| (0..hsize).step_by(size).map(move |x| Tile {
+            x,
+            y,
+            width: size.min(hsize - x),
+            height: size.min(vsize - y)
+        }))
+        .collect()
+}
+
+/// A thin lens in front of the eye. Rays leave from points all over the lens and meet again
+/// on the focal plane, so only what lies on that plane is sharp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lens {
+    /// Radius of the aperture, zero makes a pinhole that keeps everything sharp
+    pub aperture: f32,
+    /// Distance from the eye along the view direction of the plane in focus
+    pub focal_distance: f32,
+    /// Number of straight blades closing the aperture, which shapes out of focus highlights.
+    /// Fewer than three give a round aperture.
+    pub blades: usize,
+    /// Turns the blades around the view direction, in radians
+    pub blade_rotation: f32
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Lens { aperture: 0.0, focal_distance: 1.0, blades: 0, blade_rotation: 0.0 }
+    }
+}
+
+impl Lens {
+    pub fn is_pinhole(&self) -> bool {
+        self.aperture == 0.0
+    }
+
+    /// Maps a point of the unit square evenly onto the aperture, centered on the eye
+    pub fn point_on_aperture(&self, u: f32, v: f32) -> (f32, f32) {
+        if self.blades < 3 {
+            let (radius, angle) = (v.sqrt() * self.aperture, u * 2.0 * PI);
+            return (radius * angle.cos(), radius * angle.sin());
+        }
+        // u picks one of the triangles between the center and two neighbouring blade corners
+        let blades = self.blades as f32;
+        let blade = (u * blades).floor().min(blades - 1.0);
+        let along = u * blades - blade;
+        let corner = |index: f32| {
+            let angle = self.blade_rotation + index * 2.0 * PI / blades;
+            (angle.cos(), angle.sin())
+        };
+        let (a, b) = (corner(blade), corner(blade + 1.0));
+        let scale = v.sqrt() * self.aperture;
+        (scale * (a.0 + (b.0 - a.0) * along), scale * (a.1 + (b.1 - a.1) * along))
+    }
+}
+
+/// Maps the canvas one unit in front of the eye and shoots rays through its pixels
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub hsize: usize,
+    pub vsize: usize,
+    pub field_of_view: f32,
+    lens: Lens,
+    shutter: (f32, f32),
+    transformation: CachedTransform,
+    pixel_size: f32,
+    half_width: f32,
+    half_height: f32
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f32) -> Camera {
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f32 / vsize as f32;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            lens: Lens::default(),
+            shutter: (0.0, 1.0),
+            transformation: CachedTransform::default(),
+            pixel_size: half_width * 2.0 / hsize as f32,
+            half_width,
+            half_height
+        }
+    }
+
+    pub fn transform(&self) -> &Matrix {
+        &self.transformation.matrix
+    }
+
+    /// Sets the view transformation, usually built by `Matrix::view_transform`
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    pub fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    /// Replaces the pinhole with a lens, which needs more than one sample per pixel to look smooth
+    pub fn set_lens(&mut self, lens: Lens) {
+        self.lens = lens;
+    }
+
+    /// Times at which the shutter opens and closes, moving shapes are at the start of their
+    /// motion at 0 and at its end at 1
+    pub fn shutter(&self) -> (f32, f32) {
+        self.shutter
+    }
+
+    /// Sets the times the rays are spread over, equal times freeze the motion at that moment
+    pub fn set_shutter(&mut self, open: f32, close: f32) {
+        self.shutter = (open, close);
+    }
+
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    /// Returns a copy of the camera with another resolution, keeping the field of view, the lens,
+    /// the shutter and the transform
+    pub fn with_size(&self, hsize: usize, vsize: usize) -> Camera {
+        Camera { lens: self.lens, shutter: self.shutter, transformation: self.transformation.clone(), ..Camera::new(hsize, vsize, self.field_of_view) }
+    }
+
+    /// Returns the world space ray that passes through the center of the given pixel
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    /// Returns the world space ray through a point of the pixel,
+    /// the offsets go from 0 to 1 across the pixel
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, offset_x: f32, offset_y: f32) -> Ray {
+        let xoffset = (px as f32 + offset_x) * self.pixel_size;
+        let yoffset = (py as f32 + offset_y) * self.pixel_size;
+
+        // the camera looks toward -z, so +x is to the left
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let inverse = &self.transformation.inverse;
+        let pixel = inverse * &Tuple::new_point(world_x, world_y, -1.0);
+        let origin = inverse * &Tuple::new_point(0.0, 0.0, 0.0);
+        let direction = (&pixel - &origin).normalize();
+        Ray::new(origin, direction)
+    }
+
+    /// Returns the world space ray from a point of the lens through the spot of the focal plane
+    /// seen through a point of the pixel. `lens_u` and `lens_v` go from 0 to 1 and are spread
+    /// over the aperture, a pinhole ignores them.
+    pub fn ray_through_lens(&self, px: usize, py: usize, offset_x: f32, offset_y: f32, lens_u: f32, lens_v: f32) -> Ray {
+        if self.lens.is_pinhole() {
+            return self.ray_for_subpixel(px, py, offset_x, offset_y);
+        }
+        let xoffset = (px as f32 + offset_x) * self.pixel_size;
+        let yoffset = (py as f32 + offset_y) * self.pixel_size;
+        let focal_distance = self.lens.focal_distance;
+        let focus = Tuple::new_point((self.half_width - xoffset) * focal_distance,
+                                     (self.half_height - yoffset) * focal_distance,
+                                     -focal_distance);
+        let (lens_x, lens_y) = self.lens.point_on_aperture(lens_u, lens_v);
+
+        let inverse = &self.transformation.inverse;
+        let focus = inverse * &focus;
+        let origin = inverse * &Tuple::new_point(lens_x, lens_y, 0.0);
+        let direction = (&focus - &origin).normalize();
+        Ray::new(origin, direction)
+    }
+
+    /// Renders the world into a canvas, one ray per pixel
+    pub fn render(&self, world: &World) -> Canvas {
+        self.render_with(world, &RenderOptions::default(), &mut |_, _| {})
+    }
+
+    /// Renders the world, handing out tiles to `options.threads` threads as they become free.
+    /// Finished tiles are copied into the canvas by the calling thread, which also calls
+    /// `progress` with the number of finished tiles and the total after each one.
+    /// Every pixel is computed independently, so the image is the same whatever the thread count.
+    pub fn render_with(&self, world: &World, options: &RenderOptions, progress: &mut dyn FnMut(usize, usize)) -> Canvas {
+        // fails early when there are no samples to lay out
+        grid_size(options.samples_per_pixel);
+
+        let tiles = tiles(self.hsize, self.vsize, options.tile_size);
+        let next_tile = AtomicUsize::new(0);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel::<(Tile, Vec<Color>)>();
+            for _ in 0..options.threads.max(1) {
+                let sender = sender.clone();
+                let (tiles, next_tile) = (&tiles, &next_tile);
+                scope.spawn(move || {
+                    while let Some(&tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed)) {
+                        let colors = (tile.y..tile.y + tile.height)
+                            .flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| (x, y)))
+                            .map(|(x, y)| self.pixel_color(world, x, y, options))
+                            .collect();
+                        if sender.send((tile, colors)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            for (finished, (tile, colors)) in receiver.iter().enumerate() {
+                for (row, colors) in colors.chunks(tile.width).enumerate() {
+                    let start = (tile.y + row) * self.hsize + tile.x;
+                    image.pixels[start..start + tile.width].copy_from_slice(colors);
+                }
+                progress(finished + 1, tiles.len());
+            }
+        });
+        image
+    }
+
+    /// Shoots the rays of the sampler over the footprint of the filter around the pixel, each
+    /// at its own time while the shutter is open, and returns their colors weighted by the filter.
+    /// A single grid sample through a pinhole is taken halfway through the shutter.
+    ///
+    /// Every ray stands for its cell of the sample grid, so it is weighted by the filter
+    /// integrated over that cell rather than at its own point. The weights of a pixel then
+    /// always add up to the integral of the filter over its footprint, and the negative lobes
+    /// of a filter like Mitchell cannot cancel them out.
+    fn pixel_color(&self, world: &World, x: usize, y: usize, options: &RenderOptions) -> Color {
+        let (open, close) = self.shutter;
+        if options.samples_per_pixel == 1 && options.sampler == Sampler::Grid && self.lens.is_pinhole() {
+            return world.color_at(&Ray { time: (open + close) / 2.0, ..self.ray_for_pixel(x, y) });
+        }
+
+        let count = options.samples_per_pixel;
+        // the seeds differ from the one of the pixel samples so the patterns are not aligned
+        let lens_points = if self.lens.is_pinhole() {
+            vec![(0.5, 0.5); count]
+        } else {
+            Sampler::Stratified.points(count, [x as u32, !(y as u32)])
+        };
+        // a stratified pattern puts every x in its own slice of the unit interval
+        let times: Vec<f32> = Sampler::Stratified.points(count, [!(x as u32), y as u32]).into_iter()
+            .map(|(u, _)| open + (close - open) * u)
+            .collect();
+
+        let radius = options.filter.radius();
+        let (columns, rows) = grid_size(count);
+        let cell_weights = |cells: usize| -> Vec<f32> {
+            let size = 2.0 * radius / cells as f32;
+            (0..cells).map(|cell| options.filter.integral(cell as f32 * size - radius, (cell + 1) as f32 * size - radius)).collect()
+        };
+        let (column_weights, row_weights) = (cell_weights(columns), cell_weights(rows));
+        let total_weight = column_weights.iter().sum::<f32>() * row_weights.iter().sum::<f32>();
+
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        let samples = options.sampler.points(count, [x as u32, y as u32]).into_iter().zip(lens_points).zip(times);
+        for (((u, v), (lens_u, lens_v)), time) in samples {
+            let column = ((u * columns as f32) as usize).min(columns - 1);
+            let row = ((v * rows as f32) as usize).min(rows - 1);
+            let weight = column_weights[column] * row_weights[row];
+            if weight == 0.0 {
+                continue;
+            }
+            let (dx, dy) = ((u - 0.5) * 2.0 * radius, (v - 0.5) * 2.0 * radius);
+            let ray = Ray { time, ..self.ray_through_lens(x, y, 0.5 + dx, 0.5 + dy, lens_u, lens_v) };
+            sum = sum + world.color_at(&ray) * weight;
+        }
+        let color = sum * (1.0 / total_weight);
+        // the negative lobes can still ring below black next to bright edges
+        Color::new(color.red().max(0.0), color.green().max(0.0), color.blue().max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{helper::{Axis, equal, EPSILON}, color::Color, world::tests::{default_world, assert_color_near},
+                sphere::Sphere, light::PointLight, shape::Shape, plane::Plane, material::Material};
+
+    use super::*;
+
+    #[test]
+    fn constructing_a_camera() {
+        let c = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(c.hsize, 160);
+        assert_eq!(c.vsize, 120);
+        assert_eq!(c.field_of_view, PI / 2.0);
+        assert_eq!(c.transform(), &Matrix::new_identity_matrix());
+    }
+
+    #[test]
+    fn pixel_size_for_a_horizontal_canvas() {
+        let c = Camera::new(200, 125, PI / 2.0);
+        assert!(equal(c.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn pixel_size_for_a_vertical_canvas() {
+        let c = Camera::new(125, 200, PI / 2.0);
+        assert!(equal(c.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_the_center_of_the_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction, Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_a_corner_of_the_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin, Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction, Tuple::new_vector(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn constructing_a_ray_when_the_camera_is_transformed() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(Matrix::rotation(Axis::Y, PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0));
+        let r = c.ray_for_pixel(100, 50);
+        let value = 2f32.sqrt() / 2.0;
+        assert_eq!(r.origin, Tuple::new_point(0.0, 2.0, -5.0));
+        assert_eq!(r.direction, Tuple::new_vector(value, 0.0, -value));
+    }
+
+    #[test]
+    fn rendering_a_world_with_a_camera() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Matrix::view_transform(&from, &to, &up));
+        let image = c.render(&w);
+        assert_color_near(*image.pixel_at(5, 5).unwrap(), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    fn default_camera(hsize: usize, vsize: usize) -> Camera {
+        let mut c = Camera::new(hsize, vsize, PI / 2.0);
+        c.set_transform(Matrix::view_transform(&Tuple::new_point(0.0, 0.0, -5.0), &Tuple::new_point(0.0, 0.0, 0.0),
+                                               &Tuple::new_vector(0.0, 1.0, 0.0)));
+        c
+    }
+
+    #[test]
+    fn resizing_a_camera_keeps_its_view() {
+        let c = default_camera(11, 11).with_size(22, 33);
+        assert_eq!(c.hsize, 22);
+        assert_eq!(c.vsize, 33);
+        assert_eq!(c.field_of_view, PI / 2.0);
+        assert_eq!(c.transform(), default_camera(11, 11).transform());
+    }
+
+    #[test]
+    fn ray_for_a_subpixel_offset() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+        assert_eq!(r.origin, Tuple::new_point(0.0, 0.0, 0.0));
+        let half_width = c.pixel_size() * 201.0 / 2.0;
+        let half_height = c.pixel_size() * 101.0 / 2.0;
+        assert_eq!(r.direction, Tuple::new_vector(half_width, half_height, -1.0).normalize());
+    }
+
+    #[test]
+    fn rendering_with_threads_gives_the_same_image() {
+        let w = default_world();
+        let c = default_camera(16, 12);
+        let single = c.render(&w);
+        let channels = |canvas: &Canvas| -> Vec<[u32; 3]> {
+            canvas.pixels.iter().map(|c| [c.red().to_bits(), c.green().to_bits(), c.blue().to_bits()]).collect()
+        };
+        for (threads, tile_size) in [(1, 5), (2, 16), (3, 1), (7, 4)] {
+            let mut reports = Vec::new();
+            let options = RenderOptions { threads, tile_size, ..RenderOptions::default() };
+            let threaded = c.render_with(&w, &options, &mut |finished, total| reports.push((finished, total)));
+            let total = tiles(16, 12, tile_size).len();
+            assert_eq!(reports, (1..=total).map(|finished| (finished, total)).collect::<Vec<_>>());
+            assert_eq!(channels(&threaded), channels(&single), "{} threads, tiles of {}", threads, tile_size);
+        }
+    }
+
+    #[test]
+    fn a_tent_filter_stretches_the_samples_over_its_footprint() {
+        let w = default_world();
+        let c = default_camera(11, 11);
+        let options = RenderOptions { samples_per_pixel: 4, filter: Filter::Tent, ..RenderOptions::default() };
+        let image = c.render_with(&w, &options, &mut |_, _| {});
+        // the grid lands halfway to the edge of the footprint, on the corners of the pixel
+        let mut expected = Color::new(0.0, 0.0, 0.0);
+        for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            expected = expected + w.color_at(&c.ray_for_subpixel(5, 5, dx, dy));
+        }
+        assert_color_near(*image.pixel_at(5, 5).unwrap(), expected * 0.25);
+    }
+
+    #[test]
+    fn random_samplers_render_the_same_image_on_any_number_of_threads() {
+        let w = default_world();
+        let c = default_camera(12, 10);
+        for (sampler, filter) in [(Sampler::Jittered, Filter::Gaussian), (Sampler::Stratified, Filter::Mitchell),
+                                  (Sampler::RotatedGrid, Filter::Box)] {
+            let options = RenderOptions { samples_per_pixel: 4, sampler, filter, ..RenderOptions::default() };
+            let single = c.render_with(&w, &options, &mut |_, _| {});
+            let threaded = c.render_with(&w, &RenderOptions { threads: 3, tile_size: 5, ..options }, &mut |_, _| {});
+            assert_eq!(single.pixels, threaded.pixels, "{:?}", options);
+            assert_ne!(single.pixels, c.render(&w).pixels, "{:?}", options);
+        }
+    }
+
+    #[test]
+    fn filter_weights_are_normalized_for_every_pixel() {
+        // a wall filling the view, lit the same everywhere
+        let mut w = World::new();
+        let mut wall = Plane::new();
+        wall.set_transform(Matrix::translation(0.0, 0.0, 5.0) * Matrix::rotation(Axis::X, PI / 2.0));
+        wall.set_material(Material { color: Color::new(0.5, 0.5, 0.5), ambient: 1.0, diffuse: 0.0, specular: 0.0, ..Material::default() });
+        w.objects.push(Box::new(wall));
+        w.add_light(PointLight::new(Tuple::new_point(0.0, 0.0, -5.0), Color::new(1.0, 1.0, 1.0)));
+        let c = default_camera(9, 9);
+        for filter in [Filter::Tent, Filter::Gaussian, Filter::Mitchell] {
+            for samples_per_pixel in [2, 5, 9] {
+                let options = RenderOptions { samples_per_pixel, sampler: Sampler::Jittered, filter, ..RenderOptions::default() };
+                for color in c.render_with(&w, &options, &mut |_, _| {}).pixels {
+                    assert_color_near(color, Color::new(0.5, 0.5, 0.5));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn negative_filter_lobes_keep_the_pixels_in_the_range_of_the_scene() {
+        let w = default_world();
+        let c = default_camera(21, 21);
+        for sampler in [Sampler::Jittered, Sampler::Stratified] {
+            for samples_per_pixel in [2, 4, 6, 9, 16] {
+                let options = RenderOptions { samples_per_pixel, sampler, filter: Filter::Mitchell, ..RenderOptions::default() };
+                let image = c.render_with(&w, &options, &mut |_, _| {});
+                // ambient, diffuse and specular of the default world add up to at most 1.9
+                for color in &image.pixels {
+                    for channel in [color.red(), color.green(), color.blue()] {
+                        assert!((0.0..=1.9 + EPSILON).contains(&channel), "{:?} {} {:?}", sampler, samples_per_pixel, color);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rays_from_anywhere_on_the_lens_meet_on_the_focal_plane() {
+        let mut c = default_camera(11, 11);
+        c.set_lens(Lens { aperture: 0.5, focal_distance: 4.0, ..Lens::default() });
+        let focus = c.ray_for_subpixel(3, 7, 0.2, 0.9);
+        // the camera at z = -5 looks along +z, so the focal plane is at z = -1
+        let focus = focus.position(4.0 / focus.direction.z);
+        assert!(equal(focus.z, -1.0));
+        for (u, v) in [(0.5, 0.5), (0.0, 1.0), (0.3, 0.7), (0.9, 0.1)] {
+            let r = c.ray_through_lens(3, 7, 0.2, 0.9, u, v);
+            assert!(equal(r.origin.z, -5.0));
+            let t = (&focus - &r.origin).magnitude();
+            assert_eq!(r.position(t), focus, "lens point {} {}", u, v);
+        }
+        assert_ne!(c.ray_through_lens(3, 7, 0.2, 0.9, 0.3, 0.7).origin, c.ray_through_lens(3, 7, 0.2, 0.9, 0.8, 0.7).origin);
+    }
+
+    #[test]
+    fn a_pinhole_ignores_the_lens_coordinates() {
+        let c = default_camera(11, 11);
+        assert!(c.lens().is_pinhole());
+        let (through_lens, through_pixel) = (c.ray_through_lens(2, 4, 0.5, 0.5, 0.1, 0.9), c.ray_for_pixel(2, 4));
+        assert_eq!(through_lens.origin, through_pixel.origin);
+        assert_eq!(through_lens.direction, through_pixel.direction);
+        assert_eq!(c.with_size(5, 5).lens(), c.lens());
+    }
+
+    #[test]
+    fn points_on_a_bladed_aperture_stay_inside_its_polygon() {
+        for blades in [0, 3, 6] {
+            let lens = Lens { aperture: 2.0, blades, blade_rotation: 0.3, ..Lens::default() };
+            for i in 0..40 {
+                let (x, y) = lens.point_on_aperture(i as f32 / 40.0, ((i * 7) % 40) as f32 / 39.0);
+                assert!((x * x + y * y).sqrt() <= 2.0 + EPSILON, "{} blades", blades);
+                if blades >= 3 {
+                    // every side of the polygon is this far from the center
+                    let apothem = 2.0 * (PI / blades as f32).cos();
+                    for side in 0..blades {
+                        let angle = 0.3 + (side as f32 + 0.5) * 2.0 * PI / blades as f32;
+                        assert!(x * angle.cos() + y * angle.sin() <= apothem + EPSILON, "{} blades", blades);
+                    }
+                }
+            }
+        }
+        let hexagon = Lens { aperture: 1.0, blades: 6, ..Lens::default() };
+        let (x, y) = hexagon.point_on_aperture(0.0, 1.0);
+        assert!(equal(x, 1.0) && equal(y, 0.0));
+    }
+
+    #[test]
+    fn depth_of_field_renders_the_same_image_on_any_number_of_threads() {
+        let w = default_world();
+        let mut c = default_camera(12, 10);
+        c.set_lens(Lens { aperture: 0.3, focal_distance: 2.0, blades: 5, ..Lens::default() });
+        let options = RenderOptions { samples_per_pixel: 4, ..RenderOptions::default() };
+        let single = c.render_with(&w, &options, &mut |_, _| {});
+        let threaded = c.render_with(&w, &RenderOptions { threads: 4, tile_size: 3, ..options }, &mut |_, _| {});
+        assert_eq!(single.pixels, threaded.pixels);
+        assert_ne!(single.pixels, default_camera(12, 10).render_with(&w, &options, &mut |_, _| {}).pixels);
+    }
+
+    #[test]
+    fn a_moving_sphere_is_blurred_over_the_shutter() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Tuple::new_point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        let mut sphere = Sphere::new();
+        sphere.set_motion(Matrix::translation(-1.0, 0.0, 0.0), Matrix::translation(1.0, 0.0, 0.0));
+        w.add_object(Box::new(sphere));
+        let mut c = default_camera(21, 11);
+        let options = RenderOptions { samples_per_pixel: 16, ..RenderOptions::default() };
+
+        // the edges of the path are only covered for part of the shutter
+        let streak = c.render_with(&w, &options, &mut |_, _| {});
+        let threaded = c.render_with(&w, &RenderOptions { threads: 3, tile_size: 4, ..options }, &mut |_, _| {});
+        assert_eq!(streak.pixels, threaded.pixels);
+        let (center, edge) = (streak.pixel_at(10, 5).unwrap().red(), streak.pixel_at(13, 5).unwrap().red());
+        assert!(edge > 0.0 && edge < center, "center {}, edge {}", center, edge);
+        c.set_shutter(0.5, 0.5);
+        assert_eq!(c.render_with(&w, &options, &mut |_, _| {}).pixel_at(13, 5).unwrap().red(), 0.0);
+
+        // a shutter open for an instant sees the sphere where it is then
+        c.set_shutter(1.0, 1.0);
+        let mut still = Sphere::new();
+        still.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        let mut moved = World::new();
+        moved.add_light(PointLight::new(Tuple::new_point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        moved.add_object(Box::new(still));
+        assert_eq!(c.render_with(&w, &options, &mut |_, _| {}).pixels, c.render_with(&moved, &options, &mut |_, _| {}).pixels);
+        assert_eq!(c.with_size(3, 3).shutter(), (1.0, 1.0));
+    }
+
+    #[test]
+    fn cutting_an_image_into_tiles() {
+        let t = tiles(10, 7, 4);
+        assert_eq!(t.len(), 6);
+        assert_eq!(t[0], Tile { x: 0, y: 0, width: 4, height: 4 });
+        assert_eq!(t[2], Tile { x: 8, y: 0, width: 2, height: 4 });
+        assert_eq!(t[5], Tile { x: 8, y: 4, width: 2, height: 3 });
+        assert_eq!(t.iter().map(|tile| tile.width * tile.height).sum::<usize>(), 70);
+        assert!(tiles(0, 5, 4).is_empty());
+    }
+
+    #[test]
+    fn supersampling_averages_a_grid_of_rays() {
+        let w = default_world();
+        let c = default_camera(11, 11);
+        let image = c.render_with(&w, &RenderOptions { samples_per_pixel: 4, ..RenderOptions::default() }, &mut |_, _| {});
+        let mut expected = Color::new(0.0, 0.0, 0.0);
+        for (dx, dy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+            expected = expected + w.color_at(&c.ray_for_subpixel(5, 5, dx, dy));
+        }
+        assert_color_near(*image.pixel_at(5, 5).unwrap(), expected * 0.25);
+    }
+}
@@ -1,7 +1,72 @@
-use std::{path::Path, fs::{OpenOptions}, io::Write};
+use std::{path::Path, fs::{self, OpenOptions}, io::{self, Write}, fmt};
 
-use crate::color::Color;
+use crate::{color::Color, png, ppm, zlib::Compression};
 
+/// Why an image could not be read
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// The data is not a well formed image, or uses a feature that is not supported
+    Decode(String)
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "{}", error),
+            ImageError::Decode(message) => write!(f, "invalid image: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        ImageError::Io(error)
+    }
+}
+
+/// File formats a canvas can be saved as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// ASCII PPM (P3), readable but large
+    PpmAscii,
+    /// Binary PPM (P6)
+    PpmBinary,
+    Png,
+    /// Portable float map, keeps the unclamped colors for compositing
+    Pfm
+}
+
+impl ImageFormat {
+    /// Guesses the format from the extension of the path, `.ppm` is written as binary P6
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        ImageFormat::from_name(extension)
+    }
+
+    /// Parses a format name or extension, `p3` and `p6` pick the PPM variant explicitly
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "p3" => Some(ImageFormat::PpmAscii),
+            "ppm" | "p6" => Some(ImageFormat::PpmBinary),
+            "png" => Some(ImageFormat::Png),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::PpmAscii | ImageFormat::PpmBinary => "ppm",
+            ImageFormat::Png => "png",
+            ImageFormat::Pfm => "pfm"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -36,29 +101,104 @@ impl Canvas {
     }
 
     /// Returns ppm formatted string
-    pub fn to_ppm_format(self) -> String {
+    pub fn to_ppm_format(&self) -> String {
         let mut result = String::with_capacity(self.width * self.height * 7);
         let header = format!("P3\n{} {}\n255\n", self.width, self.height);
         result.push_str(&header);
         
         let mut line_length = 0;
-        #[allow(unused_variables)]
-        let mut item_count = 0;
         for color in &self.pixels {
             let ppm_colors = color.to_ppm_format();
             if line_length + ppm_colors.len() > 70 {
                 line_length = 0;
-                item_count = 0;
             }
-            result.push_str(&*format!("{} ", ppm_colors.as_str()));
-            item_count += 1;
+            result.push_str(&format!("{} ", ppm_colors.as_str()));
             line_length += ppm_colors.len() + 1;      
         }
-        result.push_str("\n");
+        result.push('\n');
         
         result
     }
 
+    /// Returns the canvas as binary PPM (P6), three bytes per pixel
+    pub fn to_ppm_binary(&self) -> Vec<u8> {
+        let mut result = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        result.reserve(self.pixels.len() * 3);
+        for color in &self.pixels {
+            result.extend_from_slice(&color.to_rgb8());
+        }
+        result
+    }
+
+    /// Returns the canvas as a little endian portable float map.
+    /// Rows are stored bottom to top, as the format requires.
+    pub fn to_pfm(&self) -> Vec<u8> {
+        let mut result = format!("PF\n{} {}\n-1.0\n", self.width, self.height).into_bytes();
+        result.reserve(self.pixels.len() * 12);
+        for row in self.pixels.chunks(self.width.max(1)).rev() {
+            for color in row {
+                for channel in [color.red(), color.green(), color.blue()] {
+                    result.extend_from_slice(&channel.to_le_bytes());
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the canvas as a compressed PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self, Compression::Fixed)
+    }
+
+    /// Returns the file content of the canvas in the given format
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::PpmAscii => self.to_ppm_format().into_bytes(),
+            ImageFormat::PpmBinary => self.to_ppm_binary(),
+            ImageFormat::Png => self.to_png(),
+            ImageFormat::Pfm => self.to_pfm()
+        }
+    }
+
+    /// Saves the canvas in the format matching the extension of the path
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        match ImageFormat::from_path(path) {
+            Some(format) => self.save_as(path, format),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                       format!("unknown image format for {}", path.display())))
+        }
+    }
+
+    pub fn save_as<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> io::Result<()> {
+        fs::write(path, self.encode(format))
+    }
+
+    /// Reads an ASCII (P3) or binary (P6) PPM image
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, ImageError> {
+        ppm::decode(data)
+    }
+
+    /// Reads a PNG image, the alpha channel is dropped
+    pub fn from_png(data: &[u8]) -> Result<Canvas, ImageError> {
+        png::decode(data)
+    }
+
+    /// Reads a PPM or PNG image, recognized by its first bytes rather than its extension
+    pub fn decode(data: &[u8]) -> Result<Canvas, ImageError> {
+        if data.starts_with(&png::SIGNATURE) {
+            Canvas::from_png(data)
+        } else if data.starts_with(b"P3") || data.starts_with(b"P6") {
+            Canvas::from_ppm(data)
+        } else {
+            Err(ImageError::Decode("unknown image format, expected PPM or PNG".to_string()))
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Canvas, ImageError> {
+        Canvas::decode(&fs::read(path)?)
+    }
+
     pub fn write_to_file(self, path: &str) {
         let ppm_formatted_text = self.to_ppm_format();
         let path = Path::new(path);
@@ -77,4 +217,81 @@ impl Canvas {
         }
     
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_by_two() -> Canvas {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        canvas.write_pixel(1, 0, Color::new(0.0, 0.5, 0.0));
+        canvas.write_pixel(0, 1, Color::new(-0.5, 0.0, 1.0));
+        canvas.write_pixel(1, 1, Color::new(0.25, 0.25, 0.25));
+        canvas
+    }
+
+    #[test]
+    fn choosing_the_format_from_the_extension() {
+        assert_eq!(ImageFormat::from_path("out/image.PNG"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_path("image.ppm"), Some(ImageFormat::PpmBinary));
+        assert_eq!(ImageFormat::from_path("image.pfm"), Some(ImageFormat::Pfm));
+        assert_eq!(ImageFormat::from_path("image.jpg"), None);
+        assert_eq!(ImageFormat::from_path("image"), None);
+        assert_eq!(ImageFormat::from_name("p3"), Some(ImageFormat::PpmAscii));
+    }
+
+    #[test]
+    fn binary_ppm_has_a_header_and_three_bytes_per_pixel() {
+        let ppm = two_by_two().to_ppm_binary();
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(ppm[..header.len()], header[..]);
+        assert_eq!(ppm[header.len()..], [255, 0, 0, 0, 127, 0, 0, 0, 255, 63, 63, 63]);
+    }
+
+    #[test]
+    fn pfm_keeps_unclamped_values_bottom_row_first() {
+        let pfm = two_by_two().to_pfm();
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(pfm[..header.len()], header[..]);
+        let values: Vec<f32> = pfm[header.len()..].chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        assert_eq!(values, vec![-0.5, 0.0, 1.0, 0.25, 0.25, 0.25, 1.5, 0.0, 0.0, 0.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn encoding_dispatches_on_the_format() {
+        let canvas = two_by_two();
+        assert!(canvas.encode(ImageFormat::PpmAscii).starts_with(b"P3\n"));
+        assert!(canvas.encode(ImageFormat::PpmBinary).starts_with(b"P6\n"));
+        assert!(canvas.encode(ImageFormat::Png).starts_with(&png::SIGNATURE));
+        assert!(canvas.encode(ImageFormat::Pfm).starts_with(b"PF\n"));
+    }
+
+    #[test]
+    fn decoding_recognizes_the_format_from_the_content() {
+        let canvas = two_by_two();
+        for format in [ImageFormat::PpmAscii, ImageFormat::PpmBinary, ImageFormat::Png] {
+            let decoded = Canvas::decode(&canvas.encode(format)).unwrap();
+            assert_eq!(decoded.width, 2);
+            assert_eq!(decoded.height, 2);
+            let expected: Vec<[u8; 3]> = canvas.pixels.iter().map(Color::to_rgb8).collect();
+            let actual: Vec<[u8; 3]> = decoded.pixels.iter().map(Color::to_rgb8).collect();
+            assert_eq!(actual, expected, "{:?}", format);
+        }
+        assert!(matches!(Canvas::decode(b"GIF89a"), Err(ImageError::Decode(_))));
+    }
+
+    #[test]
+    fn loading_a_missing_file_is_an_io_error() {
+        assert!(matches!(Canvas::load("does/not/exist.png"), Err(ImageError::Io(_))));
+    }
+
+    #[test]
+    fn saving_with_an_unknown_extension_fails() {
+        let error = two_by_two().save("image.unknown").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -22,6 +22,18 @@ impl Color {
         Color { red, green, blue }
     }
 
+    pub fn red(&self) -> f32 {
+        self.red
+    }
+
+    pub fn green(&self) -> f32 {
+        self.green
+    }
+
+    pub fn blue(&self) -> f32 {
+        self.blue
+    }
+
     pub fn hadamard_product(&self, other: &Color) -> Color {
         Color { red: self.red * other.red, 
                 green: self.green * other.green, 
@@ -34,11 +46,16 @@ impl Color {
                             Color::scale_the_color(self.blue))
     }
 
+    /// Returns the color as 8 bit red, green and blue, clamped like `to_ppm_format`
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        [Color::scale_the_color(self.red), Color::scale_the_color(self.green), Color::scale_the_color(self.blue)]
+    }
+
     fn scale_the_color(input: f32) -> u8 {
         if input >= 1.0 {
-            return 255;
+            255
         } else if input <= 0.0 {
-            return 0;
+            0
         } else {
             (255.0 * input) as u8
         }
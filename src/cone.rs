@@ -0,0 +1,213 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::{Intersection, intersections}, helper::EPSILON,
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, cylinder::check_cap,
+            bounds::BoundingBox};
+
+/// A double-napped cone around the y axis of its object space, with its apex at the origin,
+/// optionally truncated at `minimum` and `maximum` and capped at both ends
+#[derive(Debug)]
+pub struct Cone {
+    transformation: CachedTransform,
+    material: MaterialSlot,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Cone::new()
+    }
+}
+
+impl Cone {
+    /// Returns an infinite, open cone
+    pub fn new() -> Cone {
+        Cone::new_truncated(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+
+    pub fn new_truncated(minimum: f32, maximum: f32, closed: bool) -> Cone {
+        Cone {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default(),
+            minimum,
+            maximum,
+            closed
+        }
+    }
+
+    /// Adds the intersections with the end caps, if the cone is closed.
+    /// The radius of a cap equals the absolute y value it sits on.
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        let (origin, direction) = (&ray.origin, &ray.direction);
+        let a = direction.x.powi(2) - direction.y.powi(2) + direction.z.powi(2);
+        let b = 2.0 * origin.x * direction.x - 2.0 * origin.y * direction.y + 2.0 * origin.z * direction.z;
+        let c = origin.x.powi(2) - origin.y.powi(2) + origin.z.powi(2);
+
+        let mut ts = vec![];
+        if a.abs() < EPSILON {
+            // the ray is parallel to one of the halves, so it hits the other one once
+            if b.abs() >= EPSILON {
+                ts.push(-c / (2.0 * b));
+            }
+        } else {
+            // grazing rays round to a slightly negative discriminant in f32
+            let discriminant = match b.powi(2) - 4.0 * a * c {
+                d if d < 0.0 && d > -EPSILON => 0.0,
+                d => d
+            };
+            if discriminant >= 0.0 {
+                let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+                let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+                ts.push(t0.min(t1));
+                ts.push(t0.max(t1));
+            }
+        }
+
+        for t in ts {
+            let y = origin.y + t * direction.y;
+            if self.minimum < y && y < self.maximum {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        intersections(xs)
+    }
+
+    fn local_normal_at(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+        let distance = point.x.powi(2) + point.z.powi(2);
+
+        if distance < point.y.powi(2) && point.y >= self.maximum - EPSILON {
+            Tuple::new_vector(0.0, 1.0, 0.0)
+        } else if distance < point.y.powi(2) && point.y <= self.minimum + EPSILON {
+            Tuple::new_vector(0.0, -1.0, 0.0)
+        } else {
+            let y = if point.y > 0.0 { -distance.sqrt() } else { distance.sqrt() };
+            Tuple::new_vector(point.x, y, point.z)
+        }
+    }
+
+    /// The radius at any height is the distance to the apex, so the widest end sets x and z
+    fn local_bounds(&self) -> BoundingBox {
+        let radius = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(Tuple::new_point(-radius, self.minimum, -radius), Tuple::new_point(radius, self.maximum, radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helper::equal;
+
+    use super::*;
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let shape = Cone::new();
+        let cases = [
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(1.0, 1.0, 1.0), 8.66025, 8.66025),
+            (Tuple::new_point(1.0, 1.0, -5.0), Tuple::new_vector(-0.5, -1.0, 1.0), 4.55006, 49.44994)
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = shape.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            // f32 loses precision fast for grazing rays, so compare loosely
+            assert!((xs[0].t - t0).abs() < 0.001);
+            assert!((xs[1].t - t1).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray_parallel_to_one_of_its_halves() {
+        let shape = Cone::new();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -1.0), Tuple::new_vector(0.0, 1.0, 1.0).normalize());
+        let xs = shape.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!((xs[0].t - 0.35355).abs() < 0.0001);
+    }
+
+    #[test]
+    fn intersecting_a_cones_end_caps() {
+        let shape = Cone::new_truncated(-0.5, 0.5, true);
+        let cases = [
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 1.0, 0.0), 0),
+            (Tuple::new_point(0.0, 0.0, -0.25), Tuple::new_vector(0.0, 1.0, 1.0), 2),
+            (Tuple::new_point(0.0, 0.0, -0.25), Tuple::new_vector(0.0, 1.0, 0.0), 4)
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(shape.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn computing_the_normal_vector_on_a_cone() {
+        let shape = Cone::new();
+        let i = Intersection::new(0.0, &shape);
+        let cases = [
+            (Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 0.0)),
+            (Tuple::new_point(1.0, 1.0, 1.0), Tuple::new_vector(1.0, -(2f32.sqrt()), 1.0)),
+            (Tuple::new_point(-1.0, -1.0, 0.0), Tuple::new_vector(-1.0, 1.0, 0.0))
+        ];
+        for (point, normal) in cases {
+            assert_eq!(shape.local_normal_at(&point, &i), normal);
+        }
+    }
+
+    #[test]
+    fn intersections_through_the_side_and_a_cap_are_sorted() {
+        let shape = Cone::new_truncated(-1.0, 1.0, true);
+        let r = Ray::new(Tuple::new_point(0.0, 2.0, -0.5), Tuple::new_vector(0.0, -1.0, 0.75));
+        let ts: Vec<f32> = shape.local_intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts[0], 1.0);
+        assert!(equal(ts[1], 10.0 / 7.0), "{:?}", ts);
+    }
+
+    #[test]
+    fn a_cone_has_a_bounding_box() {
+        let b = Cone::new().local_bounds();
+        assert_eq!((b.min.x, b.min.y, b.min.z), (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY));
+        assert_eq!((b.max.x, b.max.y, b.max.z), (f32::INFINITY, f32::INFINITY, f32::INFINITY));
+        let b = Cone::new_truncated(-5.0, 3.0, true).local_bounds();
+        assert_eq!(b.min, Tuple::new_point(-5.0, -5.0, -5.0));
+        assert_eq!(b.max, Tuple::new_point(5.0, 3.0, 5.0));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::{Intersection, intersections},
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, group::reparent, bounds::BoundingBox};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference
+}
+
+impl CsgOperation {
+    /// Returns if an intersection is part of the combined surface.
+    /// `lhit` is true when the left shape was hit, `inl` and `inr` tell
+    /// if the hit lies inside the left and right shapes respectively.
+    pub fn intersection_allowed(&self, lhit: bool, inl: bool, inr: bool) -> bool {
+        match self {
+            CsgOperation::Union => (lhit && !inr) || (!lhit && !inl),
+            CsgOperation::Intersection => (lhit && inr) || (!lhit && inl),
+            CsgOperation::Difference => (lhit && !inr) || (!lhit && inl)
+        }
+    }
+}
+
+/// Constructive solid geometry, combines two shapes with a boolean operation.
+/// Like a group, the transform is composed into both operands.
+#[derive(Debug)]
+pub struct Csg {
+    transformation: CachedTransform,
+    material: MaterialSlot,
+    pub operation: CsgOperation,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Csg {
+        Csg {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default(),
+            operation,
+            left,
+            right
+        }
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Keeps only the intersections, sorted by t, that lie on the combined surface
+    pub fn filter_intersections<'a>(&self, xs: Vec<Intersection<'a>>) -> Vec<Intersection<'a>> {
+        // both flags start outside and flip every time the ray crosses an operand's surface
+        let mut inl = false;
+        let mut inr = false;
+        let mut result = vec![];
+
+        for i in xs {
+            let lhit = self.left.includes(i.object);
+            if self.operation.intersection_allowed(lhit, inl, inr) {
+                result.push(i);
+            }
+
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+}
+
+impl Shape for Csg {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        reparent(self.left.as_mut(), &self.transformation, &transformation);
+        reparent(self.right.as_mut(), &self.transformation, &transformation);
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    /// Assigns the material to the csg and to the operands that do not have one of their own
+    fn set_material(&mut self, material: Material) {
+        self.left.inherit_material(material.clone());
+        self.right.inherit_material(material.clone());
+        self.material.set(material);
+    }
+
+    /// A csg with a material of its own keeps it, and its operands with it
+    fn inherit_material(&mut self, material: Material) {
+        if self.material.is_own() {
+            return;
+        }
+        self.left.inherit_material(material.clone());
+        self.right.inherit_material(material.clone());
+        self.material.inherit(material);
+    }
+
+    /// Both operands are already in world space, so the ray is passed down untransformed
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = self.left.intersect(ray);
+        xs.extend(self.right.intersect(ray));
+        self.filter_intersections(intersections(xs))
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        self.intersect(&ray.transform(self.transform()))
+    }
+
+    fn local_normal_at(&self, _point: &Tuple, _hit: &Intersection) -> Tuple {
+        panic!("a csg has no surface of its own, normals are computed on its operands")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds().transform(&self.transformation.inverse)
+    }
+
+    /// Both operands are already in world space, so their boxes are merged as they are
+    fn bounds(&self) -> BoundingBox {
+        self.left.bounds().merge(&self.right.bounds())
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other) || self.left.includes(other) || self.right.includes(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{sphere::Sphere, cube::Cube, matrix::Matrix, helper::Axis};
+
+    use super::*;
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let c = Csg::new(CsgOperation::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+        assert_eq!(c.operation, CsgOperation::Union);
+        assert!(c.includes(c.left()));
+        assert!(c.includes(c.right()));
+        assert!(!c.left().includes(c.right()));
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        let cases = [
+            (CsgOperation::Union, [false, true, false, true, false, false, true, true]),
+            (CsgOperation::Intersection, [true, false, true, false, true, true, false, false]),
+            (CsgOperation::Difference, [false, true, false, true, true, true, false, false])
+        ];
+        for (operation, expected) in cases {
+            let mut i = 0;
+            for lhit in [true, false] {
+                for inl in [true, false] {
+                    for inr in [true, false] {
+                        assert_eq!(operation.intersection_allowed(lhit, inl, inr), expected[i],
+                                    "{:?} {} {} {}", operation, lhit, inl, inr);
+                        i += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1)
+        ];
+        for (operation, x0, x1) in cases {
+            let c = Csg::new(operation, Box::new(Sphere::new()), Box::new(Cube::new()));
+            let xs = intersections(vec![Intersection::new(1.0, c.left()), Intersection::new(2.0, c.right()),
+                                        Intersection::new(3.0, c.left()), Intersection::new(4.0, c.right())]);
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0], xs[x0]);
+            assert_eq!(result[1], xs[x1]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_csg_object() {
+        let c = Csg::new(CsgOperation::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+        let r = Ray::new(Tuple::new_point(0.0, 2.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(c.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_hits_a_csg_object() {
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0.0, 0.0, 0.5));
+        let c = Csg::new(CsgOperation::Union, Box::new(Sphere::new()), Box::new(s2));
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = c.local_intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert!(std::ptr::addr_eq(xs[0].object, c.left()));
+        assert_eq!(xs[1].t, 6.5);
+        assert!(std::ptr::addr_eq(xs[1].object, c.right()));
+    }
+
+    #[test]
+    fn a_transformed_csg_moves_both_operands() {
+        let mut hole = Sphere::new();
+        hole.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        let mut c = Csg::new(CsgOperation::Difference, Box::new(Cube::new()), Box::new(hole));
+        c.set_transform(Matrix::translation(10.0, 0.0, 0.0));
+        let r = Ray::new(Tuple::new_point(10.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+        assert!(std::ptr::addr_eq(xs[0].object, c.left()));
+        assert!(std::ptr::addr_eq(xs[1].object, c.right()));
+    }
+
+    #[test]
+    fn operands_of_a_spinning_csg_follow_the_arc() {
+        let mut left = Sphere::new();
+        left.set_transform(Matrix::translation(2.0, 0.0, 0.0) * Matrix::scaling(0.2, 0.2, 0.2));
+        let mut right = Sphere::new();
+        right.set_transform(Matrix::translation(-2.0, 0.0, 0.0) * Matrix::scaling(0.2, 0.2, 0.2));
+        let mut c = Csg::new(CsgOperation::Union, Box::new(left), Box::new(right));
+        c.set_motion(Matrix::new_identity_matrix(), Matrix::rotation(Axis::Z, PI / 2.0));
+        let diagonal = 2f32.sqrt();
+        for (x, y) in [(diagonal, diagonal), (-diagonal, -diagonal)] {
+            let r = Ray::new_with_time(Tuple::new_point(x, y, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0.5);
+            assert_eq!(c.intersect(&r).len(), 2);
+        }
+        let r = Ray::new_with_time(Tuple::new_point(1.0, 1.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0.5);
+        assert!(c.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn a_csg_shape_has_a_bounding_box_that_contains_its_children() {
+        let mut right = Sphere::new();
+        right.set_transform(Matrix::translation(2.0, 3.0, 4.0));
+        let c = Csg::new(CsgOperation::Difference, Box::new(Sphere::new()), Box::new(right));
+        assert_eq!(c.bounds().min, Tuple::new_point(-1.0, -1.0, -1.0));
+        assert_eq!(c.bounds().max, Tuple::new_point(3.0, 4.0, 5.0));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::Intersection, helper::EPSILON,
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox};
+
+/// An axis-aligned cube spanning -1 to 1 on every axis in its object space
+#[derive(Debug, Default)]
+pub struct Cube {
+    transformation: CachedTransform,
+    material: MaterialSlot
+}
+
+impl Cube {
+    pub fn new() -> Cube {
+        Cube {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default()
+        }
+    }
+}
+
+/// Returns where the ray enters and leaves the slab between -1 and 1 on one axis
+fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (tmin_numerator * f32::INFINITY, tmax_numerator * f32::INFINITY)
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return vec![];
+        }
+        vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+    }
+
+    fn local_normal_at(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+        let maxc = point.x.abs().max(point.y.abs()).max(point.z.abs());
+
+        if maxc == point.x.abs() {
+            Tuple::new_vector(point.x, 0.0, 0.0)
+        } else if maxc == point.y.abs() {
+            Tuple::new_vector(0.0, point.y, 0.0)
+        } else {
+            Tuple::new_vector(0.0, 0.0, point.z)
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_point(1.0, 1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_intersects_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            (Tuple::new_point(5.0, 0.5, 0.0), Tuple::new_vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+            (Tuple::new_point(-5.0, 0.5, 0.0), Tuple::new_vector(1.0, 0.0, 0.0), 4.0, 6.0),
+            (Tuple::new_point(0.5, 5.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0), 4.0, 6.0),
+            (Tuple::new_point(0.5, -5.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0), 4.0, 6.0),
+            (Tuple::new_point(0.5, 0.0, 5.0), Tuple::new_vector(0.0, 0.0, -1.0), 4.0, 6.0),
+            (Tuple::new_point(0.5, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Tuple::new_point(0.0, 0.5, 0.0), Tuple::new_vector(0.0, 0.0, 1.0), -1.0, 1.0)
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let xs = c.local_intersect(&Ray::new(origin, direction));
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs[0].t, t1);
+            assert_eq!(xs[1].t, t2);
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            (Tuple::new_point(-2.0, 0.0, 0.0), Tuple::new_vector(0.2673, 0.5345, 0.8018)),
+            (Tuple::new_point(0.0, -2.0, 0.0), Tuple::new_vector(0.8018, 0.2673, 0.5345)),
+            (Tuple::new_point(0.0, 0.0, -2.0), Tuple::new_vector(0.5345, 0.8018, 0.2673)),
+            (Tuple::new_point(2.0, 0.0, 2.0), Tuple::new_vector(0.0, 0.0, -1.0)),
+            (Tuple::new_point(0.0, 2.0, 2.0), Tuple::new_vector(0.0, -1.0, 0.0)),
+            (Tuple::new_point(2.0, 2.0, 0.0), Tuple::new_vector(-1.0, 0.0, 0.0))
+        ];
+        for (origin, direction) in cases {
+            assert!(c.local_intersect(&Ray::new(origin, direction)).is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_the_surface_of_a_cube() {
+        let c = Cube::new();
+        let i = Intersection::new(0.0, &c);
+        let cases = [
+            (Tuple::new_point(1.0, 0.5, -0.8), Tuple::new_vector(1.0, 0.0, 0.0)),
+            (Tuple::new_point(-1.0, -0.2, 0.9), Tuple::new_vector(-1.0, 0.0, 0.0)),
+            (Tuple::new_point(-0.4, 1.0, -0.1), Tuple::new_vector(0.0, 1.0, 0.0)),
+            (Tuple::new_point(0.3, -1.0, -0.7), Tuple::new_vector(0.0, -1.0, 0.0)),
+            (Tuple::new_point(-0.6, 0.3, 1.0), Tuple::new_vector(0.0, 0.0, 1.0)),
+            (Tuple::new_point(0.4, 0.4, -1.0), Tuple::new_vector(0.0, 0.0, -1.0)),
+            (Tuple::new_point(1.0, 1.0, 1.0), Tuple::new_vector(1.0, 0.0, 0.0)),
+            (Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_vector(-1.0, 0.0, 0.0))
+        ];
+        for (point, normal) in cases {
+            assert_eq!(c.local_normal_at(&point, &i), normal);
+        }
+    }
+
+    #[test]
+    fn a_cube_has_a_bounding_box() {
+        let b = Cube::new().local_bounds();
+        assert_eq!(b.min, Tuple::new_point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, Tuple::new_point(1.0, 1.0, 1.0));
+    }
+}
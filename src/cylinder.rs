@@ -0,0 +1,249 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::{Intersection, intersections}, helper::EPSILON,
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox};
+
+/// A cylinder of radius 1 around the y axis of its object space,
+/// optionally truncated at `minimum` and `maximum` and capped at both ends
+#[derive(Debug)]
+pub struct Cylinder {
+    transformation: CachedTransform,
+    material: MaterialSlot,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Cylinder::new()
+    }
+}
+
+impl Cylinder {
+    /// Returns an infinite, open cylinder
+    pub fn new() -> Cylinder {
+        Cylinder::new_truncated(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+
+    pub fn new_truncated(minimum: f32, maximum: f32, closed: bool) -> Cylinder {
+        Cylinder {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default(),
+            minimum,
+            maximum,
+            closed
+        }
+    }
+
+    /// Adds the intersections with the end caps, if the cylinder is closed
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.0) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+/// Returns if the intersection at t is within the given radius of the y axis
+pub(crate) fn check_cap(ray: &Ray, t: f32, radius: f32) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+    x.powi(2) + z.powi(2) <= radius.powi(2) + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
+
+        // a ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x.powi(2) + ray.origin.z.powi(2) - 1.0;
+            let discriminant = b.powi(2) - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return xs;
+            }
+
+            let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+            for t in [t0.min(t1), t0.max(t1)] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        intersections(xs)
+    }
+
+    fn local_normal_at(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+        let distance = point.x.powi(2) + point.z.powi(2);
+
+        if distance < 1.0 && point.y >= self.maximum - EPSILON {
+            Tuple::new_vector(0.0, 1.0, 0.0)
+        } else if distance < 1.0 && point.y <= self.minimum + EPSILON {
+            Tuple::new_vector(0.0, -1.0, 0.0)
+        } else {
+            Tuple::new_vector(point.x, 0.0, point.z)
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(-1.0, self.minimum, -1.0), Tuple::new_point(1.0, self.maximum, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_misses_a_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::new_point(1.0, 0.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0)),
+            (Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0)),
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(1.0, 1.0, 1.0))
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert!(cyl.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Tuple::new_point(1.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Tuple::new_point(0.5, 0.0, -5.0), Tuple::new_vector(0.1, 1.0, 1.0), 6.80798, 7.08872)
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cyl.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!((xs[0].t - t0).abs() < 0.0001);
+            assert!((xs[1].t - t1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinder() {
+        let cyl = Cylinder::new();
+        let i = Intersection::new(0.0, &cyl);
+        let cases = [
+            (Tuple::new_point(1.0, 0.0, 0.0), Tuple::new_vector(1.0, 0.0, 0.0)),
+            (Tuple::new_point(0.0, 5.0, -1.0), Tuple::new_vector(0.0, 0.0, -1.0)),
+            (Tuple::new_point(0.0, -2.0, 1.0), Tuple::new_vector(0.0, 0.0, 1.0)),
+            (Tuple::new_point(-1.0, 1.0, 0.0), Tuple::new_vector(-1.0, 0.0, 0.0))
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(&point, &i), normal);
+        }
+    }
+
+    #[test]
+    fn the_default_minimum_and_maximum_for_a_cylinder() {
+        let cyl = Cylinder::new();
+        assert_eq!(cyl.minimum, f32::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f32::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn intersecting_a_constrained_cylinder() {
+        let cyl = Cylinder::new_truncated(1.0, 2.0, false);
+        let cases = [
+            (Tuple::new_point(0.0, 1.5, 0.0), Tuple::new_vector(0.1, 1.0, 0.0), 0),
+            (Tuple::new_point(0.0, 3.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0),
+            (Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0),
+            (Tuple::new_point(0.0, 2.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0),
+            (Tuple::new_point(0.0, 1.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0),
+            (Tuple::new_point(0.0, 1.5, -2.0), Tuple::new_vector(0.0, 0.0, 1.0), 2)
+        ];
+        for (point, direction, count) in cases {
+            let r = Ray::new(point, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cylinder() {
+        let cyl = Cylinder::new_truncated(1.0, 2.0, true);
+        let cases = [
+            (Tuple::new_point(0.0, 3.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0), 2),
+            (Tuple::new_point(0.0, 3.0, -2.0), Tuple::new_vector(0.0, -1.0, 2.0), 2),
+            (Tuple::new_point(0.0, 4.0, -2.0), Tuple::new_vector(0.0, -1.0, 1.0), 2),
+            (Tuple::new_point(0.0, 0.0, -2.0), Tuple::new_vector(0.0, 1.0, 2.0), 2),
+            (Tuple::new_point(0.0, -1.0, -2.0), Tuple::new_vector(0.0, 1.0, 1.0), 2)
+        ];
+        for (point, direction, count) in cases {
+            let r = Ray::new(point, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn intersections_through_the_side_and_a_cap_are_sorted() {
+        let cyl = Cylinder::new_truncated(1.0, 2.0, true);
+        let r = Ray::new(Tuple::new_point(0.0, 3.0, -0.5), Tuple::new_vector(0.0, -1.0, 1.0));
+        let ts: Vec<f32> = cyl.local_intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![1.0, 1.5]);
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinders_end_caps() {
+        let cyl = Cylinder::new_truncated(1.0, 2.0, true);
+        let i = Intersection::new(0.0, &cyl);
+        let cases = [
+            (Tuple::new_point(0.0, 1.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0)),
+            (Tuple::new_point(0.5, 1.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0)),
+            (Tuple::new_point(0.0, 1.0, 0.5), Tuple::new_vector(0.0, -1.0, 0.0)),
+            (Tuple::new_point(0.0, 2.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0)),
+            (Tuple::new_point(0.5, 2.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0)),
+            (Tuple::new_point(0.0, 2.0, 0.5), Tuple::new_vector(0.0, 1.0, 0.0))
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(&point, &i), normal);
+        }
+    }
+
+    #[test]
+    fn a_cylinder_has_a_bounding_box() {
+        let b = Cylinder::new().local_bounds();
+        assert_eq!((b.min.x, b.min.y, b.min.z), (-1.0, f32::NEG_INFINITY, -1.0));
+        assert_eq!((b.max.x, b.max.y, b.max.z), (1.0, f32::INFINITY, 1.0));
+        let b = Cylinder::new_truncated(-5.0, 3.0, true).local_bounds();
+        assert_eq!(b.min, Tuple::new_point(-1.0, -5.0, -1.0));
+        assert_eq!(b.max, Tuple::new_point(1.0, 3.0, 1.0));
+    }
+}
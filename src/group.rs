@@ -0,0 +1,459 @@
+use std::sync::OnceLock;
+
+use crate::{tuple::Tuple, ray::Ray, intersection::{Intersection, intersections},
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox, bvh::Bvh};
+
+/// A collection of shapes that are transformed as a single unit.
+///
+/// Instead of walking up a parent chain for every ray, the group composes its
+/// transform into its children: a child's transform always holds the full
+/// object to world conversion, so `world_to_object` and `normal_to_world`
+/// of a child already pass through every group above it.
+///
+/// Rays are only tested against the children whose bounds they cross, found
+/// through a bounding volume hierarchy built on the first intersection.
+#[derive(Debug, Default)]
+pub struct Group {
+    transformation: CachedTransform,
+    material: MaterialSlot,
+    children: Vec<Box<dyn Shape>>,
+    hierarchy: OnceLock<Bvh>
+}
+
+impl Group {
+    pub fn new() -> Group {
+        Group {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default(),
+            children: vec![],
+            hierarchy: OnceLock::new()
+        }
+    }
+
+    /// Adds the shape to the group, placing it relative to the group's transform.
+    /// A shape without a material of its own takes the group's.
+    pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+        reparent(child.as_mut(), &CachedTransform::default(), &self.transformation);
+        child.inherit_material(self.material.get().clone());
+        self.children.push(child);
+        self.hierarchy = OnceLock::new();
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the hierarchy over the children's bounds, building it if the group changed since
+    pub fn hierarchy(&self) -> &Bvh {
+        self.hierarchy.get_or_init(|| {
+            let bounds: Vec<BoundingBox> = self.children.iter().map(|child| child.bounds()).collect();
+            Bvh::build(&bounds)
+        })
+    }
+}
+
+/// Moves a child from under its parent's old transform to under the new one. A moving parent
+/// stays a layer of the child's motion, so the child turns around the parent's pivot with it.
+pub(crate) fn reparent(child: &mut dyn Shape, old_parent: &CachedTransform, new_parent: &CachedTransform) {
+    let transformation = child.transformation();
+    let moved = if old_parent.motion.is_none() && new_parent.motion.is_none() && transformation.motion.is_none() {
+        CachedTransform::new(new_parent.matrix * old_parent.inverse * transformation.matrix)
+    } else {
+        new_parent.then(&transformation.relative_to(old_parent))
+    };
+    child.set_transformation(moved);
+}
+
+impl Shape for Group {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    /// Replaces the group's transform, moving the children along with it
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        for child in self.children.iter_mut() {
+            reparent(child.as_mut(), &self.transformation, &transformation);
+        }
+        self.transformation = transformation;
+        self.hierarchy = OnceLock::new();
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    /// Assigns the material to the group and to the children that do not have one of their own
+    fn set_material(&mut self, material: Material) {
+        for child in self.children.iter_mut() {
+            child.inherit_material(material.clone());
+        }
+        self.material.set(material);
+    }
+
+    /// A group with a material of its own keeps it, and its children with it
+    fn inherit_material(&mut self, material: Material) {
+        if self.material.is_own() {
+            return;
+        }
+        for child in self.children.iter_mut() {
+            child.inherit_material(material.clone());
+        }
+        self.material.inherit(material);
+    }
+
+    /// Children are already in world space, so the ray is passed down untransformed
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        self.hierarchy().visit(ray, |index| xs.extend(self.children[index].intersect(ray)));
+        intersections(xs)
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        self.intersect(&ray.transform(self.transform()))
+    }
+
+    fn local_normal_at(&self, _point: &Tuple, _hit: &Intersection) -> Tuple {
+        panic!("a group has no surface, normals are computed on its children")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds().transform(&self.transformation.inverse)
+    }
+
+    /// The children are already in world space, so their boxes are merged as they are
+    fn bounds(&self) -> BoundingBox {
+        self.hierarchy().bounds()
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other) || self.children.iter().any(|child| child.includes(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{sphere::Sphere, helper::Axis, matrix::Matrix, color::Color};
+
+    use super::*;
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = Group::new();
+        assert_eq!(g.transform(), &Matrix::new_identity_matrix());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        assert!(!g.is_empty());
+        assert!(g.includes(g.children()[0].as_ref()));
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0.0, 0.0, -3.0));
+        let mut s3 = Sphere::new();
+        s3.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(Box::new(s1));
+        g.add_child(Box::new(s2));
+        g.add_child(Box::new(s3));
+
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = g.local_intersect(&r);
+        assert_eq!(xs.len(), 4);
+        assert!(std::ptr::addr_eq(xs[0].object, g.children()[1].as_ref()));
+        assert!(std::ptr::addr_eq(xs[1].object, g.children()[1].as_ref()));
+        assert!(std::ptr::addr_eq(xs[2].object, g.children()[0].as_ref()));
+        assert!(std::ptr::addr_eq(xs[3].object, g.children()[0].as_ref()));
+    }
+
+    #[test]
+    fn children_move_along_with_their_group() {
+        let mut g = Group::new();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(0.0, 2.0, 0.0));
+        g.add_child(Box::new(s));
+        g.set_motion(Matrix::new_identity_matrix(), Matrix::translation(4.0, 0.0, 0.0));
+        let child = g.children()[0].transformation();
+        assert_eq!(child.matrix, Matrix::translation(0.0, 2.0, 0.0));
+        assert_eq!(child.close(), Matrix::translation(4.0, 2.0, 0.0));
+
+        // a child added later joins the motion, and moving the group keeps it going
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        g.add_child(Box::new(s));
+        g.set_transform(Matrix::translation(0.0, 0.0, 1.0));
+        let child = g.children()[1].transformation();
+        assert_eq!(child.matrix, Matrix::translation(0.0, 0.0, 1.0) * Matrix::scaling(0.5, 0.5, 0.5));
+        assert_eq!(child.close(), child.matrix);
+        assert!(child.motion.is_none());
+
+        let mut outer = Group::new();
+        let mut moving = Sphere::new();
+        moving.set_motion(Matrix::new_identity_matrix(), Matrix::translation(1.0, 0.0, 0.0));
+        outer.add_child(Box::new(moving));
+        outer.set_transform(Matrix::translation(0.0, 3.0, 0.0));
+        let child = outer.children()[0].transformation();
+        assert_eq!(child.matrix, Matrix::translation(0.0, 3.0, 0.0));
+        assert_eq!(child.close(), Matrix::translation(1.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn children_without_a_material_take_the_group_one() {
+        let red = Material { color: Color::new(1.0, 0.0, 0.0), ..Material::default() };
+        let blue = Material { color: Color::new(0.0, 0.0, 1.0), ..Material::default() };
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        let mut own = Sphere::new();
+        own.set_material(blue.clone());
+        g.add_child(Box::new(own));
+        g.set_material(red.clone());
+        g.add_child(Box::new(Sphere::new()));
+        let colors = |g: &Group| g.children().iter().map(|child| child.material().color).collect::<Vec<_>>();
+        assert_eq!(colors(&g), vec![red.color, blue.color, red.color]);
+
+        // changing the group material again only changes what the children inherited
+        let green = Material { color: Color::new(0.0, 1.0, 0.0), ..Material::default() };
+        g.set_material(green.clone());
+        assert_eq!(colors(&g), vec![green.color, blue.color, green.color]);
+    }
+
+    #[test]
+    fn a_child_explicitly_given_the_default_material_keeps_it() {
+        let red = Material { color: Color::new(1.0, 0.0, 0.0), ..Material::default() };
+        let mut g = Group::new();
+        let mut plain = Sphere::new();
+        plain.set_material(Material::default());
+        g.add_child(Box::new(plain));
+        let mut same = Sphere::new();
+        same.set_material(red.clone());
+        g.add_child(Box::new(same));
+        g.set_material(red.clone());
+
+        let blue = Material { color: Color::new(0.0, 0.0, 1.0), ..Material::default() };
+        g.set_material(blue);
+        assert_eq!(g.children()[0].material(), &Material::default());
+        assert_eq!(g.children()[1].material(), &red);
+    }
+
+    #[test]
+    fn nested_groups_pass_the_material_down_unless_they_have_their_own() {
+        let red = Material { color: Color::new(1.0, 0.0, 0.0), ..Material::default() };
+        let blue = Material { color: Color::new(0.0, 0.0, 1.0), ..Material::default() };
+        let mut inner = Group::new();
+        inner.add_child(Box::new(Sphere::new()));
+        let mut own = Group::new();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        own.add_child(Box::new(s));
+        own.set_material(blue.clone());
+        let mut outer = Group::new();
+        outer.add_child(Box::new(inner));
+        outer.add_child(Box::new(own));
+        outer.set_material(red.clone());
+
+        let material_at = |x: f32| {
+            let r = Ray::new(Tuple::new_point(x, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+            outer.intersect(&r)[0].object.material().clone()
+        };
+        assert_eq!(material_at(0.0), red);
+        assert_eq!(material_at(5.0), blue);
+    }
+
+    #[test]
+    fn a_child_off_the_pivot_of_a_spinning_group_follows_the_arc() {
+        let mut g = Group::new();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(2.0, 0.0, 0.0) * Matrix::scaling(0.2, 0.2, 0.2));
+        g.add_child(Box::new(s));
+        g.set_motion(Matrix::new_identity_matrix(), Matrix::rotation(Axis::Z, PI / 2.0));
+        let hits = |g: &Group, x: f32, y: f32| {
+            let r = Ray::new_with_time(Tuple::new_point(x, y, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0.5);
+            !g.intersect(&r).is_empty()
+        };
+        let diagonal = 2f32.sqrt();
+        assert!(hits(&g, diagonal, diagonal));
+        // halfway between the ends is only where a straight line would be
+        assert!(!hits(&g, 1.0, 1.0));
+        assert!(g.bounds().contains_point(&Tuple::new_point(diagonal + 0.2, diagonal + 0.2, 0.0)));
+
+        // a new motion replaces the old one instead of adding to it
+        g.set_motion(Matrix::rotation(Axis::Z, PI / 2.0), Matrix::rotation(Axis::Z, PI));
+        assert!(hits(&g, -diagonal, diagonal));
+
+        // inside a group that moves on its own, the spin goes along
+        let mut outer = Group::new();
+        outer.add_child(Box::new(g));
+        outer.set_motion(Matrix::new_identity_matrix(), Matrix::translation(0.0, 0.0, 2.0));
+        let r = Ray::new_with_time(Tuple::new_point(-diagonal, diagonal, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0.5);
+        let xs = outer.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!((xs[0].t - 5.8).abs() < 0.001);
+        outer.set_transform(Matrix::new_identity_matrix());
+        let inner = outer.children()[0].transformation();
+        assert_eq!(inner.matrix, Matrix::rotation(Axis::Z, PI / 2.0));
+    }
+
+    #[test]
+    fn a_moving_group_is_hit_where_it_is_at_the_time_of_the_ray() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        g.set_motion(Matrix::new_identity_matrix(), Matrix::translation(0.0, 4.0, 0.0));
+        assert_eq!(g.bounds().max, Tuple::new_point(1.0, 5.0, 1.0));
+        let r = Ray::new_with_time(Tuple::new_point(0.0, 3.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 0.75);
+        let xs = g.intersect(&r);
+        assert_eq!(xs.iter().map(|i| i.t).collect::<Vec<_>>(), vec![4.0, 6.0]);
+        assert_eq!(xs[0].time, 0.75);
+        assert_eq!(xs[0].object.normal_at(&r.position(4.0), &xs[0]), Tuple::new_vector(0.0, 0.0, -1.0));
+        assert!(g.intersect(&Ray { time: 0.0, ..r }).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(Box::new(s));
+        let r = Ray::new(Tuple::new_point(10.0, 0.0, -10.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn transforming_a_group_moves_its_existing_children() {
+        let mut g = Group::new();
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(Box::new(s));
+        g.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        g.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let expected = Matrix::scaling(2.0, 2.0, 2.0) * Matrix::translation(5.0, 0.0, 0.0);
+        assert_eq!(g.children()[0].transform(), &expected);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix::rotation(Axis::Y, PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -20.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let p = xs[0].object.world_to_object(&Tuple::new_point(-2.0, 0.0, -10.0), 0.0);
+        assert_eq!(p, Tuple::new_point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix::rotation(Axis::Y, PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix::scaling(1.0, 2.0, 3.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -20.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let value = 3f32.sqrt() / 3.0;
+        let n = xs[0].object.normal_to_world(&Tuple::new_vector(value, value, value), 0.0);
+        assert_eq!(n, Tuple::new_vector(0.28571, 0.42857, -0.85714));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix::rotation(Axis::Y, PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix::scaling(1.0, 2.0, 3.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g2.add_child(Box::new(s));
+        g1.add_child(Box::new(g2));
+
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -20.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let n = xs[0].object.normal_at(&Tuple::new_point(1.7321, 1.1547, -5.5774), &xs[0]);
+        assert!((n.x - 0.2857).abs() < 0.0001);
+        assert!((n.y - 0.4286).abs() < 0.0001);
+        assert!((n.z + 0.8571).abs() < 0.0001);
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(2.0, 5.0, -3.0) * Matrix::scaling(2.0, 2.0, 2.0));
+        let mut c = crate::cylinder::Cylinder::new_truncated(-2.0, 2.0, false);
+        c.set_transform(Matrix::translation(-4.0, -1.0, 4.0) * Matrix::scaling(0.5, 1.0, 0.5));
+        let mut g = Group::new();
+        g.add_child(Box::new(s));
+        g.add_child(Box::new(c));
+        assert_eq!(g.bounds().min, Tuple::new_point(-4.5, -3.0, -5.0));
+        assert_eq!(g.bounds().max, Tuple::new_point(4.0, 7.0, 4.5));
+
+        g.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        assert_eq!(g.bounds().min, Tuple::new_point(-3.5, -3.0, -5.0));
+        assert_eq!(g.local_bounds().min, Tuple::new_point(-4.5, -3.0, -5.0));
+    }
+
+    #[test]
+    fn intersecting_a_large_group_through_its_hierarchy() {
+        let mut g = Group::new();
+        for x in 0..10 {
+            for y in 0..10 {
+                let mut s = Sphere::new();
+                s.set_transform(Matrix::translation(x as f32 * 3.0, y as f32 * 3.0, 0.0) * Matrix::scaling(0.5, 0.5, 0.5));
+                g.add_child(Box::new(s));
+            }
+        }
+        g.add_child(Box::new(crate::plane::Plane::new()));
+        assert!(g.hierarchy().depth() > 1);
+
+        let r = Ray::new(Tuple::new_point(12.0, 21.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[0].object, g.children()[4 * 10 + 7].as_ref()));
+        assert_eq!(xs[0].t, 4.5);
+
+        let r = Ray::new(Tuple::new_point(100.0, 5.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(std::ptr::addr_eq(xs[0].object, g.children()[100].as_ref()));
+    }
+
+    #[test]
+    fn adding_a_child_rebuilds_the_hierarchy() {
+        let mut g = Group::new();
+        g.add_child(Box::new(Sphere::new()));
+        let r = Ray::new(Tuple::new_point(5.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(g.intersect(&r).is_empty());
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(Box::new(s));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+}
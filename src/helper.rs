@@ -1,4 +1,8 @@
-const EPSILON: f32 = 0.00001;
+pub const EPSILON: f32 = 0.00001;
+
+/// Distance a hit point is nudged along its normal before casting secondary rays,
+/// so f32 rounding does not make a surface shadow itself (shadow acne)
+pub const SURFACE_EPSILON: f32 = EPSILON * 100.0;
 
 pub enum Axis {
     X,
@@ -9,3 +13,18 @@ pub enum Axis {
 pub fn equal(a: f32, b: f32) -> bool{
     f32::abs(a - b) < EPSILON
 }
+
+/// Hashes the bit patterns of the values into a number in `[0, 1)`. Renders use it
+/// for noise that does not depend on which thread computes a pixel or in what order.
+pub fn hash_to_unit(values: &[u32]) -> f32 {
+    let mut hash: u32 = 0x9e37_79b9;
+    for value in values {
+        hash ^= value;
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x7feb_352d);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x846c_a68b);
+        hash ^= hash >> 16;
+    }
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
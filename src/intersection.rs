@@ -0,0 +1,327 @@
+use crate::{shape::Shape, tuple::Tuple, ray::Ray, helper::SURFACE_EPSILON};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Intersection<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    /// Barycentric coordinates of the hit, only meaningful for triangles
+    pub u: f32,
+    pub v: f32,
+    /// Time of the ray that found the hit, which places a moving object
+    pub time: f32
+}
+
+/// Precomputed values of an intersection that are needed for shading
+#[derive(Debug)]
+pub struct Computations<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    pub point: Tuple,
+    /// The hit point nudged slightly above the surface, used to cast secondary rays
+    pub over_point: Tuple,
+    /// The hit point nudged slightly below the surface, where refracted rays start
+    pub under_point: Tuple,
+    pub eyev: Tuple,
+    pub normalv: Tuple,
+    pub reflectv: Tuple,
+    pub inside: bool,
+    /// Refractive index of the material the ray is leaving
+    pub n1: f32,
+    /// Refractive index of the material the ray is entering
+    pub n2: f32,
+    /// Time of the ray, secondary rays are cast at the same moment
+    pub time: f32
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Intersection<'a> {
+        Intersection { t, object, u: 0.0, v: 0.0, time: 0.0 }
+    }
+
+    pub fn new_with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Intersection<'a> {
+        Intersection { t, object, u, v, time: 0.0 }
+    }
+
+    /// Precomputes the shading values of this hit. `xs` are all the intersections
+    /// along the ray, they are needed to find out which materials the ray passes between.
+    pub fn prepare_computations(&self, ray: &Ray, xs: &[Intersection<'a>]) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = ray.direction.negate();
+        let mut normalv = self.object.normal_at(&point, self);
+        // the eye is inside the object when the normal points away from it
+        let inside = normalv.dot(&eyev) < 0.0;
+        if inside {
+            normalv = normalv.negate();
+        }
+        let over_point = &point + &normalv.multiply(SURFACE_EPSILON);
+        let under_point = &point - &normalv.multiply(SURFACE_EPSILON);
+        let reflectv = ray.direction.reflect(&normalv);
+        let (n1, n2) = self.refractive_indices(xs);
+
+        Computations {
+            t: self.t,
+            object: self.object,
+            point,
+            over_point,
+            under_point,
+            eyev,
+            normalv,
+            reflectv,
+            inside,
+            n1,
+            n2,
+            time: ray.time
+        }
+    }
+
+    /// Walks the intersections keeping track of the objects the ray is inside,
+    /// returns the refractive indices on both sides of this hit
+    fn refractive_indices(&self, xs: &[Intersection<'a>]) -> (f32, f32) {
+        let mut containers: Vec<&dyn Shape> = vec![];
+        let mut n1 = 1.0;
+
+        for i in xs {
+            if i == self {
+                n1 = containers.last().map_or(1.0, |object| object.material().refractive_index);
+            }
+
+            match containers.iter().position(|object| std::ptr::addr_eq(*object, i.object)) {
+                Some(index) => { containers.remove(index); },
+                None => containers.push(i.object)
+            }
+
+            if i == self {
+                let n2 = containers.last().map_or(1.0, |object| object.material().refractive_index);
+                return (n1, n2);
+            }
+        }
+        (n1, 1.0)
+    }
+}
+
+impl Computations<'_> {
+    /// Schlick's approximation of the Fresnel equations,
+    /// returns the fraction of the light that is reflected
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.eyev.dot(&self.normalv);
+
+        // total internal reflection can only happen when going to a lower index
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+impl PartialEq for Intersection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.t == other.t && std::ptr::addr_eq(self.object, other.object)
+    }
+}
+
+/// Sorts the given intersections by their t values
+pub fn intersections(mut xs: Vec<Intersection>) -> Vec<Intersection> {
+    xs.sort_by(|a, b| a.t.total_cmp(&b.t));
+    xs
+}
+
+/// Returns the visible intersection, which is the one with the lowest non-negative t
+pub fn hit<'a, 'b>(xs: &'b [Intersection<'a>]) -> Option<&'b Intersection<'a>> {
+    xs.iter()
+        .filter(|i| i.t >= 0.0)
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sphere::Sphere, plane::Plane, matrix::Matrix, material::Material, helper::{EPSILON, equal}};
+
+    use super::*;
+
+    #[test]
+    fn an_intersection_encapsulates_t_and_object() {
+        let s = Sphere::new();
+        let i = Intersection::new(3.5, &s);
+        assert_eq!(i.t, 3.5);
+        assert!(std::ptr::addr_eq(i.object, &s));
+    }
+
+    #[test]
+    fn aggregating_intersections() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(2.0, &s);
+        let i2 = Intersection::new(1.0, &s);
+        let xs = intersections(vec![i1, i2]);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 1.0);
+        assert_eq!(xs[1].t, 2.0);
+    }
+
+    #[test]
+    fn hit_when_all_intersections_have_positive_t() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
+        let xs = intersections(vec![i2, i1]);
+        assert_eq!(hit(&xs), Some(&i1));
+    }
+
+    #[test]
+    fn hit_when_some_intersections_have_negative_t() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(1.0, &s);
+        let xs = intersections(vec![i2, i1]);
+        assert_eq!(hit(&xs), Some(&i2));
+    }
+
+    #[test]
+    fn hit_when_all_intersections_have_negative_t() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-2.0, &s);
+        let i2 = Intersection::new(-1.0, &s);
+        let xs = intersections(vec![i2, i1]);
+        assert_eq!(hit(&xs), None);
+    }
+
+    #[test]
+    fn hit_is_always_the_lowest_nonnegative_intersection() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
+        let xs = vec![i1, i2, i3, i4];
+        assert_eq!(hit(&xs), Some(&i4));
+    }
+
+    #[test]
+    fn precomputing_the_state_of_an_intersection() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.t, i.t);
+        assert!(std::ptr::addr_eq(comps.object, &shape));
+        assert_eq!(comps.point, Tuple::new_point(0.0, 0.0, -1.0));
+        assert_eq!(comps.eyev, Tuple::new_vector(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn hit_when_an_intersection_occurs_on_the_outside() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn hit_when_an_intersection_occurs_on_the_inside() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(1.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.point, Tuple::new_point(0.0, 0.0, 1.0));
+        assert_eq!(comps.eyev, Tuple::new_vector(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+        // normal would have been (0, 0, 1), but is inverted
+        assert_eq!(comps.normalv, Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn the_hit_should_offset_the_point() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut shape = Sphere::new();
+        shape.set_transform(Matrix::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    fn glass_sphere() -> Sphere {
+        let mut s = Sphere::new();
+        s.set_material(Material { transparency: 1.0, refractive_index: 1.5, ..Material::default() });
+        s
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let shape = Plane::new();
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 1.0, -1.0), Tuple::new_vector(0.0, -value, value));
+        let i = Intersection::new(2f32.sqrt(), &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.reflectv, Tuple::new_vector(0.0, value, value));
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let mut a = glass_sphere();
+        a.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut b = glass_sphere();
+        b.set_transform(Matrix::translation(0.0, 0.0, -0.25));
+        b.set_material(Material { refractive_index: 2.0, ..b.material().clone() });
+        let mut c = glass_sphere();
+        c.set_transform(Matrix::translation(0.0, 0.0, 0.25));
+        c.set_material(Material { refractive_index: 2.5, ..c.material().clone() });
+
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -4.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(2.0, &a), Intersection::new(2.75, &b), Intersection::new(3.25, &c),
+                    Intersection::new(4.75, &b), Intersection::new(5.25, &c), Intersection::new(6.0, &a)];
+        let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+        for (index, (n1, n2)) in expected.iter().enumerate() {
+            let comps = xs[index].prepare_computations(&r, &xs);
+            assert_eq!(comps.n1, *n1);
+            assert_eq!(comps.n2, *n2);
+        }
+    }
+
+    #[test]
+    fn the_under_point_is_offset_below_the_surface() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut shape = glass_sphere();
+        shape.set_transform(Matrix::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+
+    #[test]
+    fn schlick_approximation_under_total_internal_reflection() {
+        let shape = glass_sphere();
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, value), Tuple::new_vector(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-value, &shape), Intersection::new(value, &shape)];
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-1.0, &shape), Intersection::new(1.0, &shape)];
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert!(equal(comps.schlick(), 0.04));
+    }
+
+    #[test]
+    fn schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere();
+        let r = Ray::new(Tuple::new_point(0.0, 0.99, -2.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(1.8589, &shape)];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert!((comps.schlick() - 0.48873).abs() < 0.0001);
+    }
+}
@@ -1,6 +1,35 @@
 pub mod helper;
 pub mod tuple;
+pub mod vector;
+pub mod point;
 pub mod projectile;
 pub mod color;
 pub mod canvas;
-pub mod matrix;
\ No newline at end of file
+pub mod zlib;
+pub mod png;
+pub mod ppm;
+pub mod matrix;
+pub mod transform;
+pub mod motion;
+pub mod ray;
+pub mod bounds;
+pub mod bvh;
+pub mod sphere;
+pub mod plane;
+pub mod cube;
+pub mod cylinder;
+pub mod cone;
+pub mod triangle;
+pub mod group;
+pub mod csg;
+pub mod intersection;
+pub mod shape;
+pub mod material;
+pub mod pattern;
+pub mod light;
+pub mod world;
+pub mod sampling;
+pub mod camera;
+pub mod obj_file;
+pub mod yaml;
+pub mod scene;
\ No newline at end of file
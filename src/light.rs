@@ -0,0 +1,536 @@
+use std::{f32::consts::PI, fmt::Debug};
+
+use crate::{tuple::Tuple, color::Color, material::Material, intersection::Intersection, helper::hash_to_unit};
+
+/// Light arriving at a point from one position on a light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+    /// Unit vector from the point towards the light
+    pub direction: Tuple,
+    /// How far a shadow ray has to go, infinite for a directional light
+    pub distance: f32,
+    /// Intensity after any falloff
+    pub intensity: Color
+}
+
+/// Common contract of every light. Shading and shadows only see lights through
+/// the samples they return for a point.
+pub trait Light: Debug + Send + Sync {
+    /// Intensity before any falloff, also used for the ambient term
+    fn intensity(&self) -> Color;
+
+    /// Unit vector from `point` towards the light
+    fn direction_from(&self, point: &Tuple) -> Tuple;
+
+    /// Distance from `point` to the light, infinite when it has no position
+    fn distance_from(&self, point: &Tuple) -> f32;
+
+    /// Intensity that reaches `point`, for lights that fade out in some directions
+    fn intensity_towards(&self, _point: &Tuple) -> Color {
+        self.intensity()
+    }
+
+    /// The light as seen from `point`, a single sample unless the light has a surface
+    fn samples(&self, point: &Tuple) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: self.direction_from(point),
+            distance: self.distance_from(point),
+            intensity: self.intensity_towards(point)
+        }]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Tuple,
+    pub intensity: Color
+}
+
+impl PointLight {
+    pub fn new(position: Tuple, intensity: Color) -> PointLight {
+        PointLight {
+            position: Tuple { w: 1.0, ..position },
+            intensity
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: &Tuple) -> Tuple {
+        (&self.position - point).normalize()
+    }
+
+    fn distance_from(&self, point: &Tuple) -> f32 {
+        (&self.position - point).magnitude()
+    }
+}
+
+/// A point light that only shines inside a cone. Between the inner and the outer
+/// angle, both measured from `direction`, the intensity fades out smoothly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: Tuple,
+    pub direction: Tuple,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub intensity: Color
+}
+
+impl SpotLight {
+    /// The angles are in radians, the outer one is raised to the inner one if it is smaller
+    pub fn new(position: Tuple, direction: Tuple, inner_angle: f32, outer_angle: f32, intensity: Color) -> SpotLight {
+        SpotLight {
+            position: Tuple { w: 1.0, ..position },
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle: outer_angle.max(inner_angle),
+            intensity
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: &Tuple) -> Tuple {
+        (&self.position - point).normalize()
+    }
+
+    fn distance_from(&self, point: &Tuple) -> f32 {
+        (&self.position - point).magnitude()
+    }
+
+    fn intensity_towards(&self, point: &Tuple) -> Color {
+        let cos = self.direction_from(point).negate().dot(&self.direction);
+        let (cos_inner, cos_outer) = (self.inner_angle.cos(), self.outer_angle.cos());
+        let falloff = if cos >= cos_inner {
+            1.0
+        } else if cos <= cos_outer {
+            0.0
+        } else {
+            // smoothstep, so the edge of the cone has no visible ring
+            let t = (cos - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        };
+        self.intensity * falloff
+    }
+}
+
+/// Light from so far away that it arrives everywhere from the same direction, like sunlight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, so pointing away from the sun
+    pub direction: Tuple,
+    pub intensity: Color
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Tuple, intensity: Color) -> DirectionalLight {
+        DirectionalLight { direction: direction.normalize(), intensity }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, _point: &Tuple) -> Tuple {
+        self.direction.negate()
+    }
+
+    fn distance_from(&self, _point: &Tuple) -> f32 {
+        f32::INFINITY
+    }
+}
+
+/// Surface an `AreaLight` emits from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emitter {
+    /// Parallelogram spanned by two edges starting at a corner
+    Rectangle { corner: Tuple, uvec: Tuple, vvec: Tuple },
+    /// Disk around `center`, facing along `normal`
+    Disk { center: Tuple, normal: Tuple, radius: f32 }
+}
+
+/// A light with a surface, sampled once in each of `usteps` by `vsteps` cells.
+/// Shadows get soft edges where only some of the samples are blocked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    pub emitter: Emitter,
+    pub usteps: usize,
+    pub vsteps: usize,
+    pub intensity: Color,
+    /// Moves each sample to a random spot in its cell instead of its center,
+    /// which turns the banding of regular samples into noise
+    pub jitter: bool
+}
+
+impl AreaLight {
+    /// A parallelogram light whose `uvec` edge is cut in `usteps` cells and `vvec` edge in `vsteps`
+    pub fn new_rectangle(corner: Tuple, uvec: Tuple, usteps: usize, vvec: Tuple, vsteps: usize, intensity: Color) -> AreaLight {
+        let emitter = Emitter::Rectangle { corner: Tuple { w: 1.0, ..corner }, uvec, vvec };
+        AreaLight { emitter, usteps: usteps.max(1), vsteps: vsteps.max(1), intensity, jitter: true }
+    }
+
+    /// A disk light cut in `rings` of equal area and `sectors` around its center
+    pub fn new_disk(center: Tuple, normal: Tuple, radius: f32, rings: usize, sectors: usize, intensity: Color) -> AreaLight {
+        let emitter = Emitter::Disk { center: Tuple { w: 1.0, ..center }, normal: normal.normalize(), radius };
+        AreaLight { emitter, usteps: rings.max(1), vsteps: sectors.max(1), intensity, jitter: true }
+    }
+
+    /// Maps `u` and `v` between 0 and 1 to a point on the surface. On a disk `u` goes
+    /// outwards, through the square root so that equal steps cover equal areas.
+    pub fn point_on_light(&self, u: f32, v: f32) -> Tuple {
+        match self.emitter {
+            Emitter::Rectangle { corner, uvec, vvec } => &(&corner + &uvec.multiply(u)) + &vvec.multiply(v),
+            Emitter::Disk { center, normal, radius } => {
+                // any axis that is not along the normal gives two directions in the disk
+                let other = if normal.x.abs() < 0.9 { Tuple::new_vector(1.0, 0.0, 0.0) } else { Tuple::new_vector(0.0, 1.0, 0.0) };
+                let a = normal.cross(&other).normalize();
+                let b = normal.cross(&a);
+                let (r, angle) = (radius * u.sqrt(), 2.0 * PI * v);
+                &(&center + &a.multiply(r * angle.cos())) + &b.multiply(r * angle.sin())
+            }
+        }
+    }
+
+    /// One position in every cell. Jittered positions are derived from `point`, so the
+    /// same point always sees the same positions whichever thread shades it.
+    pub fn positions(&self, point: &Tuple) -> Vec<Tuple> {
+        let seed = [point.x.to_bits(), point.y.to_bits(), point.z.to_bits()];
+        let mut positions = Vec::with_capacity(self.usteps * self.vsteps);
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let (du, dv) = if self.jitter {
+                    let cell = (v * self.usteps + u) as u32;
+                    (hash_to_unit(&[seed[0], seed[1], seed[2], cell, 0]), hash_to_unit(&[seed[0], seed[1], seed[2], cell, 1]))
+                } else {
+                    (0.5, 0.5)
+                };
+                positions.push(self.point_on_light((u as f32 + du) / self.usteps as f32, (v as f32 + dv) / self.vsteps as f32));
+            }
+        }
+        positions
+    }
+
+    fn center(&self) -> Tuple {
+        match self.emitter {
+            Emitter::Rectangle { .. } => self.point_on_light(0.5, 0.5),
+            Emitter::Disk { center, .. } => center
+        }
+    }
+}
+
+impl Light for AreaLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: &Tuple) -> Tuple {
+        (&self.center() - point).normalize()
+    }
+
+    fn distance_from(&self, point: &Tuple) -> f32 {
+        (&self.center() - point).magnitude()
+    }
+
+    fn samples(&self, point: &Tuple) -> Vec<LightSample> {
+        self.positions(point).iter()
+            .map(|position| {
+                let v = position - point;
+                LightSample { direction: v.normalize(), distance: v.magnitude(), intensity: self.intensity }
+            })
+            .collect()
+    }
+}
+
+/// Shades a point with the Phong reflection model,
+/// adding up the ambient, diffuse and specular contributions.
+/// The diffuse and specular terms are averaged over `samples`, the light as seen from the
+/// point, in which the samples blocked by another object are black.
+/// The hit gives the object and the time needed to evaluate the material's pattern in object space.
+pub fn lighting(material: &Material, hit: &Intersection, light: &dyn Light, point: &Tuple,
+                eyev: &Tuple, normalv: &Tuple, samples: &[LightSample]) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let color = match &material.pattern {
+        Some(pattern) => pattern.pattern_at_shape(hit.object, point, hit.time),
+        None => material.color
+    };
+    let ambient = color.hadamard_product(&light.intensity()) * material.ambient;
+    if samples.is_empty() {
+        return ambient;
+    }
+
+    let mut sum = black;
+    for sample in samples {
+        let lightv = sample.direction;
+        // a negative value means the light is on the other side of the surface
+        let light_dot_normal = lightv.dot(normalv);
+        if light_dot_normal < 0.0 {
+            continue;
+        }
+        let diffuse = color.hadamard_product(&sample.intensity) * material.diffuse * light_dot_normal;
+
+        // a negative value means the light reflects away from the eye
+        let reflectv = lightv.negate().reflect(normalv);
+        let reflect_dot_eye = reflectv.dot(eyev);
+        let specular = if reflect_dot_eye <= 0.0 {
+            black
+        } else {
+            let factor = reflect_dot_eye.powf(material.shininess);
+            sample.intensity * material.specular * factor
+        };
+        sum = sum + diffuse + specular;
+    }
+
+    ambient + sum * (1.0 / samples.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::FRAC_1_SQRT_2, sync::Arc};
+
+    use crate::{sphere::Sphere, pattern::StripePattern};
+
+    use super::*;
+
+    fn setup() -> (Material, Tuple) {
+        (Material::default(), Tuple::new_point(0.0, 0.0, 0.0))
+    }
+
+    fn blocked(samples: Vec<LightSample>) -> Vec<LightSample> {
+        samples.into_iter().map(|sample| LightSample { intensity: Color::new(0.0, 0.0, 0.0), ..sample }).collect()
+    }
+
+    #[test]
+    fn point_light_has_position_and_intensity() {
+        let intensity = Color::new(1.0, 1.0, 1.0);
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+        let light = PointLight::new(position, intensity);
+        assert_eq!(light.position, position);
+        assert_eq!(light.intensity, intensity);
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_the_light_and_the_surface() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_light_and_surface_eye_offset_45_degrees() {
+        let (m, position) = setup();
+        let value = 2f32.sqrt() / 2.0;
+        let eyev = Tuple::new_vector(0.0, value, -value);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn lighting_with_eye_opposite_surface_light_offset_45_degrees() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        let expected = 0.1 + 0.9 * 2f32.sqrt() / 2.0;
+        assert_eq!(result, Color::new(expected, expected, expected));
+    }
+
+    #[test]
+    fn lighting_with_eye_in_the_path_of_the_reflection_vector() {
+        let (m, position) = setup();
+        let value = 2f32.sqrt() / 2.0;
+        let eyev = Tuple::new_vector(0.0, -value, -value);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        // specular term is raised to the 200th power, so f32 drifts past EPSILON here
+        assert!((result.red() - 1.6364).abs() < 0.0001);
+        assert!((result.green() - 1.6364).abs() < 0.0001);
+        assert!((result.blue() - 1.6364).abs() < 0.0001);
+    }
+
+    #[test]
+    fn lighting_with_the_light_behind_the_surface() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_the_surface_in_shadow() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &blocked(light.samples(&position)));
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_a_pattern_applied() {
+        let m = Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::new_with_pattern(Arc::new(StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))))
+        };
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let object = Sphere::new();
+        let (p1, p2) = (Tuple::new_point(0.9, 0.0, 0.0), Tuple::new_point(1.1, 0.0, 0.0));
+        let c1 = lighting(&m, &Intersection::new(0.0, &object), &light, &p1, &eyev, &normalv, &light.samples(&p1));
+        let c2 = lighting(&m, &Intersection::new(0.0, &object), &light, &p2, &eyev, &normalv, &light.samples(&p2));
+        assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
+    }
+
+    fn unjittered(mut light: AreaLight) -> AreaLight {
+        light.jitter = false;
+        light
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let light = AreaLight::new_rectangle(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(2.0, 0.0, 0.0), 4,
+                                             Tuple::new_vector(0.0, 0.0, 1.0), 2, Color::new(1.0, 1.0, 1.0));
+        assert_eq!((light.usteps, light.vsteps), (4, 2));
+        assert_eq!(light.point_on_light(1.0, 1.0), Tuple::new_point(2.0, 0.0, 1.0));
+        assert_eq!(light.intensity(), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn samples_of_an_area_light_without_jitter_are_the_cell_centers() {
+        let light = unjittered(AreaLight::new_rectangle(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(2.0, 0.0, 0.0), 4,
+                                                        Tuple::new_vector(0.0, 0.0, 1.0), 2, Color::new(1.0, 1.0, 1.0)));
+        let samples = light.positions(&Tuple::new_point(0.0, 5.0, 0.0));
+        assert_eq!(samples.len(), 8);
+        assert_eq!(samples[0], Tuple::new_point(0.25, 0.0, 0.25));
+        assert_eq!(samples[2], Tuple::new_point(1.25, 0.0, 0.25));
+        assert_eq!(samples[3], Tuple::new_point(1.75, 0.0, 0.25));
+        assert_eq!(samples[7], Tuple::new_point(1.75, 0.0, 0.75));
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells_and_depend_only_on_the_point() {
+        let light = AreaLight::new_rectangle(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(2.0, 0.0, 0.0), 4,
+                                             Tuple::new_vector(0.0, 0.0, 1.0), 2, Color::new(1.0, 1.0, 1.0));
+        let point = Tuple::new_point(0.3, 5.0, -1.0);
+        let samples = light.positions(&point);
+        for (index, sample) in samples.iter().enumerate() {
+            let (u, v) = ((index % 4) as f32 * 0.5, (index / 4) as f32 * 0.5);
+            assert!((u..u + 0.5).contains(&sample.x) && (v..v + 0.5).contains(&sample.z), "{:?}", sample);
+            assert_eq!(sample.y, 0.0);
+        }
+        assert_eq!(light.positions(&point), samples);
+        assert_ne!(light.positions(&Tuple::new_point(0.3, 5.0, -1.5)), samples);
+    }
+
+    #[test]
+    fn samples_of_a_disk_light_lie_on_the_disk() {
+        let center = Tuple::new_point(1.0, 4.0, 0.0);
+        let light = AreaLight::new_disk(center, Tuple::new_vector(0.0, -2.0, 0.0), 0.5, 3, 8, Color::new(1.0, 1.0, 1.0));
+        let samples = light.positions(&Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(samples.len(), 24);
+        for sample in &samples {
+            assert!((sample.y - 4.0).abs() < 0.0001 && (sample - &center).magnitude() <= 0.5, "{:?}", sample);
+        }
+        let unjittered = unjittered(light).positions(&Tuple::new_point(0.0, 0.0, 0.0));
+        let radius = (&unjittered[0] - &center).magnitude();
+        assert!((radius - 0.5 * (0.5f32 / 3.0).sqrt()).abs() < 0.0001);
+    }
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let light = unjittered(AreaLight::new_rectangle(Tuple::new_point(-0.5, -0.5, -5.0), Tuple::new_vector(1.0, 0.0, 0.0), 2,
+                                                        Tuple::new_vector(0.0, 1.0, 0.0), 2, Color::new(1.0, 1.0, 1.0)));
+        let m = Material { ambient: 0.1, diffuse: 0.9, specular: 0.0, color: Color::new(1.0, 1.0, 1.0), ..Material::default() };
+        let eye = Tuple::new_point(0.0, 0.0, -5.0);
+        let cases = [(Tuple::new_point(0.0, 0.0, -1.0), 0.9965), (Tuple::new_point(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), 0.62318)];
+        for (point, expected) in cases {
+            let eyev = (&eye - &point).normalize();
+            let normalv = Tuple::new_vector(point.x, point.y, point.z);
+            let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &point, &eyev, &normalv, &light.samples(&point));
+            assert!((result.red() - expected).abs() < 0.0001, "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn lighting_scales_with_the_fraction_of_the_light_that_is_visible() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let samples = [light.samples(&position), blocked(light.samples(&position))].concat();
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &samples);
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn a_point_light_gives_the_direction_and_distance_to_it() {
+        let light = PointLight::new(Tuple::new_point(0.0, 4.0, 3.0), Color::new(1.0, 1.0, 1.0));
+        let point = Tuple::new_point(0.0, 0.0, 0.0);
+        assert_eq!(light.direction_from(&point), Tuple::new_vector(0.0, 0.8, 0.6));
+        assert_eq!(light.distance_from(&point), 5.0);
+        assert_eq!(light.samples(&point).len(), 1);
+    }
+
+    #[test]
+    fn a_directional_light_comes_from_the_same_direction_everywhere() {
+        let light = DirectionalLight::new(Tuple::new_vector(0.0, -2.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        for point in [Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_point(100.0, -5.0, 3.0)] {
+            let sample = light.samples(&point)[0];
+            assert_eq!(sample.direction, Tuple::new_vector(0.0, 1.0, 0.0));
+            assert_eq!(sample.distance, f32::INFINITY);
+            assert_eq!(sample.intensity, Color::new(1.0, 1.0, 1.0));
+        }
+        let (m, position) = setup();
+        let normalv = Tuple::new_vector(0.0, 1.0, 0.0);
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &normalv, &normalv, &light.samples(&position));
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn a_spot_light_fades_out_between_its_inner_and_outer_angles() {
+        let light = SpotLight::new(Tuple::new_point(0.0, 10.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0),
+                                   PI / 8.0, PI / 4.0, Color::new(1.0, 1.0, 1.0));
+        let at_angle = |angle: f32| light.intensity_towards(&Tuple::new_point(10.0 * angle.tan(), 0.0, 0.0)).red();
+        assert_eq!(at_angle(0.0), 1.0);
+        assert_eq!(at_angle(PI / 10.0), 1.0);
+        assert_eq!(at_angle(PI / 3.0), 0.0);
+        let (a, b, c) = (at_angle(0.45), at_angle(0.55), at_angle(0.7));
+        assert!(1.0 > a && a > b && b > c && c > 0.0, "{} {} {}", a, b, c);
+        // a point above the spot is behind it
+        assert_eq!(light.intensity_towards(&Tuple::new_point(0.0, 20.0, 0.0)).red(), 0.0);
+        assert_eq!(light.distance_from(&Tuple::new_point(0.0, 0.0, 0.0)), 10.0);
+    }
+
+    #[test]
+    fn lighting_outside_of_a_spot_light_cone_is_only_ambient() {
+        let (m, position) = setup();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = SpotLight::new(Tuple::new_point(0.0, 0.0, -10.0), Tuple::new_vector(0.0, 1.0, 0.0),
+                                   PI / 8.0, PI / 6.0, Color::new(1.0, 1.0, 1.0));
+        let result = lighting(&m, &Intersection::new(0.0, &Sphere::new()), &light, &position, &eyev, &normalv, &light.samples(&position));
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+}
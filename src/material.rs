@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::{color::Color, pattern::Pattern};
+
+/// Surface attributes of the Phong reflection model
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub color: Color,
+    /// When set, replaces the flat `color` with a color that varies over the surface
+    pub pattern: Option<Arc<dyn Pattern>>,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    /// 0 is completely matte, 1 is a perfect mirror
+    pub reflective: f32,
+    /// 0 is opaque, 1 lets all the light through
+    pub transparency: f32,
+    pub refractive_index: f32
+}
+
+impl Material {
+    pub fn new(color: Color, ambient: f32, diffuse: f32, specular: f32, shininess: f32) -> Material {
+        Material { color, ambient, diffuse, specular, shininess, ..Material::default() }
+    }
+
+    pub fn new_with_pattern(pattern: Arc<dyn Pattern>) -> Material {
+        Material { pattern: Some(pattern), ..Material::default() }
+    }
+}
+
+/// Patterns are compared by identity, two materials only match when they share the same one
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        let same_pattern = match (&self.pattern, &other.pattern) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false
+        };
+        same_pattern
+            && self.color == other.color
+            && self.ambient == other.ambient
+            && self.diffuse == other.diffuse
+            && self.specular == other.specular
+            && self.shininess == other.shininess
+            && self.reflective == other.reflective
+            && self.transparency == other.transparency
+            && self.refractive_index == other.refractive_index
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_material() {
+        let m = Material::default();
+        assert_eq!(m.color, Color::new(1.0, 1.0, 1.0));
+        assert!(m.pattern.is_none());
+        assert_eq!(m.ambient, 0.1);
+        assert_eq!(m.diffuse, 0.9);
+        assert_eq!(m.specular, 0.9);
+        assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+    }
+}
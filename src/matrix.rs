@@ -1,322 +1,361 @@
-use std::ops::{Mul, Neg};
-use crate::{tuple::Tuple, helper::Axis};
-
-#[derive(Debug, PartialEq)]
-pub struct Matrix {
-    row: usize,
-    column: usize,
-    data: Vec<f32>
+use std::{fmt, ops::{Mul, Neg, Index, IndexMut}};
+use crate::{tuple::Tuple, point::Point3, vector::Vector3, helper::Axis};
+
+/// A square matrix of `N` rows and columns stored inline, so it is `Copy` and never allocates.
+/// Transformations are 4x4, which is the default size; 3x3 and 2x2 matrices
+/// only show up while computing cofactors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix<const N: usize = 4> {
+    data: [[f32; N]; N]
 }
 
-impl Matrix {
-    pub fn new(row: usize, column: usize) -> Matrix {
-        Matrix { 
-                row,
-                column,
-                data: vec![0.0; row * column]
-        }
-    }
-
-    pub fn new_with_data(row: usize, column: usize, data: Vec<f32>) -> Matrix {
-        Matrix { 
-            row,
-            column,
-            data
-        }
-    }
+pub type Matrix4 = Matrix<4>;
+pub type Matrix3 = Matrix<3>;
+pub type Matrix2 = Matrix<2>;
 
-    pub fn new_4x4_with_data(data: Vec<f32>) -> Matrix {
-        Matrix::new_with_data(4, 4, data)
-    }
+/// Default for `Matrix::try_inverse_with_tolerance`
+pub const SINGULARITY_TOLERANCE: f32 = 1e-6;
 
-    pub fn new_3x3_with_data(data: Vec<f32>) -> Matrix {
-        Matrix::new_with_data(3, 3, data)
-    }
+/// The error of an operation that needs the inverse of a singular matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SingularMatrix;
 
-    pub fn new_identity_matrix() -> Matrix {
-        Matrix::new_4x4_with_data( vec![1.0, 0.0, 0.0, 0.0,
-                                        0.0, 1.0, 0.0, 0.0,
-                                        0.0, 0.0, 1.0, 0.0,
-                                        0.0, 0.0, 0.0, 1.0])
+impl fmt::Display for SingularMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the matrix is singular and has no inverse")
     }
+}
 
-    pub fn rotation(axis: Axis, value: f32) -> Matrix {
-        let mut result = Matrix::new_identity_matrix();
-        match axis {
-            Axis::X => {
-                result.data[5] = value.cos();
-                result.data[6] = value.sin().neg();
-                result.data[9] = value.sin();
-                result.data[10] = value.cos();
-            },
-            Axis::Y => {
-                result.data[0] = value.cos();
-                result.data[2] = value.sin();
-                result.data[8] = value.sin().neg();
-                result.data[10] = value.cos();    
-            },
-            Axis::Z => {
-                result.data[0] = value.cos();
-                result.data[1] = value.sin().neg();
-                result.data[4] = value.sin();
-                result.data[5] = value.cos();
-            }
-        }
-        result
-    }
+impl std::error::Error for SingularMatrix {}
 
-    pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Matrix {
-        let mut result = Matrix::new_identity_matrix();
-        result.data[1] = xy;
-        result.data[2] = xz;
-        result.data[4] = yx;
-        result.data[6] = yz;
-        result.data[8] = zx;
-        result.data[9] = zy;
-        result
+impl<const N: usize> Matrix<N> {
+    /// Builds a matrix from its rows
+    pub fn new(rows: [[f32; N]; N]) -> Matrix<N> {
+        Matrix { data: rows }
     }
 
-    pub fn translation(x: f32, y: f32, z: f32) -> Matrix {
-        let mut result = Matrix::new_identity_matrix();
-        result.data[3] = x;
-        result.data[7] = y;
-        result.data[11] = z;
-        result
+    pub fn new_zero() -> Matrix<N> {
+        Matrix { data: [[0.0; N]; N] }
     }
 
-    pub fn scaling(x: f32, y: f32, z: f32) -> Matrix {
-        let mut result = Matrix::new_identity_matrix();
-        result.data[0] = x;
-        result.data[5] = y;
-        result.data[10] = z;
+    pub fn new_identity_matrix() -> Matrix<N> {
+        let mut result = Matrix::new_zero();
+        for i in 0..N {
+            result.data[i][i] = 1.0;
+        }
         result
     }
 
     pub fn print(&self) {
-        for i in 0..self.row {
-            for j in 0..self.column {
-                print!("{:?} ", self.get(i, j).unwrap());
+        for row in &self.data {
+            for value in row {
+                print!("{:?} ", value);
             }
-            println!("");
+            println!();
         }
     }
 
     pub fn get(&self, row: usize, column: usize) -> Option<f32> {
-        match self.is_in_bound(row, column) {
-            true => Some(self.data[row * self.column + column]),
-            false => None
-        }
+        self.data.get(row)?.get(column).copied()
     }
 
     pub fn set(&mut self, row: usize, column: usize, value: f32) {
-        if self.is_in_bound(row, column) {
-            self.data[row * self.column + column] = value;
+        if let Some(element) = self.data.get_mut(row).and_then(|row| row.get_mut(column)) {
+            *element = value;
         }
     }
 
-    pub fn transpose(&self) -> Matrix {
-        let mut result_data = Vec::with_capacity(self.row * self.column);
-        for i in 0..=3 {
-            result_data.push(self.data[0 + i]);
-            result_data.push(self.data[4 + i]);
-            result_data.push(self.data[8 + i]);
-            result_data.push(self.data[12 + i]);
+    pub fn transpose(&self) -> Matrix<N> {
+        let mut result = Matrix::new_zero();
+        for (i, row) in self.data.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                result.data[j][i] = *value;
+            }
         }
-        Matrix { row: self.row, column: self.column, data: result_data }
+        result
     }
 
-    pub fn submatrix(&self, row: usize, column: usize) -> Matrix {
-        let mut result = Matrix::new_with_data(self.row - 1, self.column - 1, 
-        Vec::with_capacity((self.row - 1) * (self.column - 1)));
-        // data between min_row and max_row will be deleted
-        let min_row = row * self.row;
-        let max_row = (row + 1) * self.row - 1;
-        
-        let column_indexes = self.get_indexes_of_column(column);
-
-        let mut i = 0;
-        while i < self.data.len() {
-            if !(i >= min_row && i <= max_row || column_indexes.contains(&i)) {
-                result.data.push(self.data[i]);
+    /// Computes the determinant with an LU decomposition with partial pivoting,
+    /// in double precision so integer matrices give exact results
+    pub fn determinant(&self) -> f32 {
+        let mut rows = self.data.map(|row| row.map(f64::from));
+        let mut determinant = 1.0;
+        for column in 0..N {
+            let pivot = (column..N).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs())).unwrap();
+            if rows[pivot][column] == 0.0 {
+                return 0.0;
+            }
+            if pivot != column {
+                rows.swap(pivot, column);
+                determinant = -determinant;
+            }
+            determinant *= rows[column][column];
+            let pivot_row = rows[column];
+            for row in rows.iter_mut().skip(column + 1) {
+                let factor = row[column] / pivot_row[column];
+                for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(column) {
+                    *value -= factor * pivot_value;
+                }
             }
-            i += 1;
         }
+        determinant as f32
+    }
 
-        result
+    /// Returns the inverse, or `None` when the matrix is singular within `SINGULARITY_TOLERANCE`
+    pub fn try_inverse(&self) -> Option<Matrix<N>> {
+        self.try_inverse_with_tolerance(SINGULARITY_TOLERANCE)
     }
 
-    pub fn inverse(&self) -> Matrix {
-        let mut result = Matrix::new_with_data(self.row, self.column, 
-        Vec::with_capacity(self.row * self.column));
-        let determinant = self.determinant();
-        
-        for i in 0..self.data.len() {
-            let indexes = self.get_row_and_column(i);
-            result.data.push(self.cofactor(indexes.0, indexes.1));
+    /// Inverts the matrix by Gauss-Jordan elimination with partial pivoting, in double precision.
+    /// A pivot counts as zero when it is below `tolerance` times the largest entry of its
+    /// column, which keeps the test independent of the units of each axis.
+    /// Matrices with infinite or NaN entries have no inverse either.
+    pub fn try_inverse_with_tolerance(&self, tolerance: f32) -> Option<Matrix<N>> {
+        if self.data.iter().flatten().any(|value| !value.is_finite()) {
+            return None;
+        }
+        let mut rows = self.data.map(|row| row.map(f64::from));
+        let mut inverse = Matrix::<N>::new_identity_matrix().data.map(|row| row.map(f64::from));
+        let mut column_sizes = [0.0f64; N];
+        for row in &rows {
+            for (size, value) in column_sizes.iter_mut().zip(row) {
+                *size = size.max(value.abs());
+            }
         }
-        result = result.transpose();
 
-        for i in 0..result.data.len() {
-            result.data[i] = result.data[i] / determinant;
+        for column in 0..N {
+            let pivot = (column..N).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs())).unwrap();
+            if rows[pivot][column].abs() <= tolerance as f64 * column_sizes[column] {
+                return None;
+            }
+            rows.swap(pivot, column);
+            inverse.swap(pivot, column);
+
+            let divisor = rows[column][column];
+            for k in 0..N {
+                rows[column][k] /= divisor;
+                inverse[column][k] /= divisor;
+            }
+            for row in (0..N).filter(|&row| row != column) {
+                let factor = rows[row][column];
+                if factor == 0.0 {
+                    continue;
+                }
+                for k in 0..N {
+                    rows[row][k] -= factor * rows[column][k];
+                    inverse[row][k] -= factor * inverse[column][k];
+                }
+            }
         }
+        Some(Matrix { data: inverse.map(|row| row.map(|value| value as f32)) })
+    }
 
-        result
+    /// # Panics
+    /// When the matrix is singular, `try_inverse` handles matrices that may be
+    pub fn inverse(&self) -> Matrix<N> {
+        self.try_inverse().expect("the matrix is singular and has no inverse")
     }
 
     pub fn is_invertible(&self) -> bool {
-        self.determinant() != 0.0
+        self.try_inverse().is_some()
     }
+}
 
-    pub fn cofactor(&self, row: usize, column: usize) -> f32 {
-        let result = self.minor(row, column);
-        if (row + column) % 2 == 0 {
-            return result;
+impl Matrix<4> {
+    pub fn rotation(axis: Axis, value: f32) -> Matrix {
+        let (sin, cos) = (value.sin(), value.cos());
+        let mut result = Matrix::new_identity_matrix();
+        match axis {
+            Axis::X => {
+                result.data[1][1] = cos;
+                result.data[1][2] = sin.neg();
+                result.data[2][1] = sin;
+                result.data[2][2] = cos;
+            },
+            Axis::Y => {
+                result.data[0][0] = cos;
+                result.data[0][2] = sin;
+                result.data[2][0] = sin.neg();
+                result.data[2][2] = cos;
+            },
+            Axis::Z => {
+                result.data[0][0] = cos;
+                result.data[0][1] = sin.neg();
+                result.data[1][0] = sin;
+                result.data[1][1] = cos;
+            }
         }
-        result.neg()
+        result
     }
 
-    fn minor(&self, row: usize, column: usize) -> f32 {
-        let submatrix = self.submatrix(row, column);
-        submatrix.determinant()
+    pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Matrix {
+        Matrix::new([[1.0, xy, xz, 0.0],
+                     [yx, 1.0, yz, 0.0],
+                     [zx, zy, 1.0, 0.0],
+                     [0.0, 0.0, 0.0, 1.0]])
     }
 
-    fn determinant(&self) -> f32 {
-        if self.row == 2 || self.column == 2 {
-            return self.data[0] * self.data[3] - self.data[1] * self.data[2]
-        }
-        let mut result = 0.0;
-        for i in 0..self.row {
-            result +=  self.data[i] * self.cofactor(0, i);
-        }
-
-        result
+    pub fn translation(x: f32, y: f32, z: f32) -> Matrix {
+        Matrix::new([[1.0, 0.0, 0.0, x],
+                     [0.0, 1.0, 0.0, y],
+                     [0.0, 0.0, 1.0, z],
+                     [0.0, 0.0, 0.0, 1.0]])
     }
 
-    fn is_in_bound(&self, row: usize, column: usize) -> bool {
-        self.row > row && self.column > column
+    pub fn scaling(x: f32, y: f32, z: f32) -> Matrix {
+        Matrix::new([[x, 0.0, 0.0, 0.0],
+                     [0.0, y, 0.0, 0.0],
+                     [0.0, 0.0, z, 0.0],
+                     [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    /// Returns the transformation that orients the world relative to an eye
+    /// placed at `from`, looking at `to`, with `up` roughly pointing upwards
+    pub fn view_transform(from: &Tuple, to: &Tuple, up: &Tuple) -> Matrix {
+        let forward = (to - from).normalize();
+        let left = forward.cross(&up.normalize());
+        let true_up = left.cross(&forward);
+        let orientation = Matrix::new([[left.x, left.y, left.z, 0.0],
+                                       [true_up.x, true_up.y, true_up.z, 0.0],
+                                       [-forward.x, -forward.y, -forward.z, 0.0],
+                                       [0.0, 0.0, 0.0, 1.0]]);
+        orientation * Matrix::translation(-from.x, -from.y, -from.z)
+    }
+
+    /// Rotation by `angle` radians around an arbitrary axis through the origin,
+    /// turning the same way as `rotation` does around the coordinate axes
+    pub fn rotation_around(axis: &Tuple, angle: f32) -> Matrix {
+        let Tuple { x, y, z, .. } = axis.normalize();
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let t = 1.0 - cos;
+        Matrix::new([[t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y, 0.0],
+                     [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x, 0.0],
+                     [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos, 0.0],
+                     [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    /// Moves an object from the origin to `from` and turns it so that its negative
+    /// z axis points at `to` and its y axis is as close to `up` as possible.
+    /// This is the inverse of `view_transform`, without its skew when `up` is not
+    /// perpendicular to the line of sight.
+    pub fn look_at(from: &Tuple, to: &Tuple, up: &Tuple) -> Matrix {
+        let forward = (to - from).normalize();
+        let left = forward.cross(&up.normalize()).normalize();
+        let true_up = left.cross(&forward);
+        Matrix::new([[left.x, true_up.x, -forward.x, from.x],
+                     [left.y, true_up.y, -forward.y, from.y],
+                     [left.z, true_up.z, -forward.z, from.z],
+                     [0.0, 0.0, 0.0, 1.0]])
     }
+}
 
-    /// given the column number, returns the column indexes
-    fn get_indexes_of_column(&self, column_number: usize) -> Vec<usize> {
-        let mut column_indexes = vec![0; self.column];
-        for i in 0..self.column {
-            column_indexes[i] = column_number + i * self.column;
+/// Cofactors need the matrix one size smaller, which const generics cannot
+/// spell on stable Rust, so each size that has a smaller one gets its own impl.
+/// The determinant and inverse above work on any size without them.
+macro_rules! impl_cofactors {
+    ($size:literal, $smaller:literal) => {
+        impl Matrix<$size> {
+            /// Returns a copy of the matrix without the given row and column
+            pub fn submatrix(&self, row: usize, column: usize) -> Matrix<$smaller> {
+                let mut result = Matrix::<$smaller>::new_zero();
+                for (i, source_row) in (0..$size).filter(|&i| i != row).enumerate() {
+                    for (j, source_column) in (0..$size).filter(|&j| j != column).enumerate() {
+                        result.data[i][j] = self.data[source_row][source_column];
+                    }
+                }
+                result
+            }
+
+            pub fn cofactor(&self, row: usize, column: usize) -> f32 {
+                let result = self.minor(row, column);
+                if (row + column).is_multiple_of(2) {
+                    return result;
+                }
+                result.neg()
+            }
+
+            fn minor(&self, row: usize, column: usize) -> f32 {
+                self.submatrix(row, column).determinant()
+            }
         }
-        column_indexes
-    }
+    };
+}
+
+impl_cofactors!(4, 3);
+impl_cofactors!(3, 2);
 
-    fn get_row_and_column(&self, index: usize) -> (usize, usize) {
-        let column = index % self.column;
-        let row = index / self.row;
+impl<const N: usize> Index<(usize, usize)> for Matrix<N> {
+    type Output = f32;
 
-        (row, column)
+    fn index(&self, (row, column): (usize, usize)) -> &f32 {
+        &self.data[row][column]
     }
+}
 
+impl<const N: usize> IndexMut<(usize, usize)> for Matrix<N> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut f32 {
+        &mut self.data[row][column]
+    }
 }
 
-impl Mul for Matrix {
-    type Output = Matrix;
+impl<const N: usize> Mul for Matrix<N> {
+    type Output = Matrix<N>;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut new_matrix = Matrix::new(self.row, self.column);
-        if self.data.len() != rhs.data.len() ||
-            self.row != rhs.row ||
-            self.column != rhs.column
-        {
-            return new_matrix; 
-        }
-
-        for row in 0..self.row {
-            for column in 0..self.column {
-                new_matrix.data[row * self.column + column] = self.data[row * self.column + 0] * rhs.data[0 * self.column + column] +
-                                                             self.data[row * self.column + 1] * rhs.data[1 * self.column + column] +
-                                                             self.data[row * self.column + 2] * rhs.data[2 * self.column + column] +
-                                                             self.data[row * self.column + 3] * rhs.data[3 * self.column + column]
+        let mut new_matrix = Matrix::new_zero();
+        for row in 0..N {
+            for column in 0..N {
+                new_matrix.data[row][column] = (0..N)
+                    .map(|k| self.data[row][k] * rhs.data[k][column])
+                    .sum();
             }
         }
         new_matrix
     }
 }
 
-impl<'a, 'b> Mul<&'b Matrix> for &'a Matrix {
-    type Output = Matrix;
+impl<'b, const N: usize> Mul<&'b Matrix<N>> for &Matrix<N> {
+    type Output = Matrix<N>;
 
-    fn mul(self, rhs:&'b Matrix) -> Self::Output {
-        let mut new_matrix = Matrix::new(self.row, self.column);
-        if self.data.len() != rhs.data.len() ||
-            self.row != rhs.row ||
-            self.column != rhs.column
-        {
-            return new_matrix; 
-        }
-
-        for row in 0..self.row {
-            for column in 0..self.column {
-                new_matrix.data[row * self.column + column] = self.data[row * self.column + 0] * rhs.data[0 * self.column + column] +
-                                                             self.data[row * self.column + 1] * rhs.data[1 * self.column + column] +
-                                                             self.data[row * self.column + 2] * rhs.data[2 * self.column + column] +
-                                                             self.data[row * self.column + 3] * rhs.data[3 * self.column + column]
-            }
-        }
-        new_matrix
+    fn mul(self, rhs: &'b Matrix<N>) -> Self::Output {
+        *self * *rhs
     }
 }
 
-impl Mul<Tuple> for Matrix {
+impl Mul<Tuple> for Matrix<4> {
     type Output = Tuple;
 
     fn mul(self, rhs: Tuple) -> Self::Output {
-        let mut result = Tuple::new(0.0, 0.0, 0.0, 0.0);
-        if self.row != 4 || self.column != 4 {
-            return result;
-        }
-        
-        let mut buffer = [0.0; 4];
-        let mut i = 0;
-        let mut buffer_index = 0;
-        while i < 13 {
-            buffer[buffer_index] = self.data[0 + i] * rhs.x + 
-                        self.data[1 + i] * rhs.y +
-                        self.data[2 + i] * rhs.z +
-                        self.data[3 + i] * rhs.w;
-            buffer_index += 1;
-            i += 4;
-        }
-
-        result.x = buffer[0];
-        result.y = buffer[1];
-        result.z = buffer[2];
-        result.w = buffer[3];
-        result
+        &self * &rhs
     }
 }
 
-impl<'a, 'b> Mul<&'b Tuple> for &'a Matrix {
+impl<'b> Mul<&'b Tuple> for &Matrix<4> {
     type Output = Tuple;
 
     fn mul(self, rhs: &'b Tuple) -> Self::Output {
-        let mut result = Tuple::new(0.0, 0.0, 0.0, 0.0);
-        if self.row != 4 || self.column != 4 {
-            return result;
-        }
-        
-        let mut buffer = [0.0; 4];
-        let mut i = 0;
-        let mut buffer_index = 0;
-        while i < 13 {
-            buffer[buffer_index] = self.data[0 + i] * rhs.x + 
-                        self.data[1 + i] * rhs.y +
-                        self.data[2 + i] * rhs.z +
-                        self.data[3 + i] * rhs.w;
-            buffer_index += 1;
-            i += 4;
-        }
+        let [x, y, z, w] = self.data.map(|row| row[0] * rhs.x + row[1] * rhs.y + row[2] * rhs.z + row[3] * rhs.w);
+        Tuple::new(x, y, z, w)
+    }
+}
 
-        result.x = buffer[0];
-        result.y = buffer[1];
-        result.z = buffer[2];
-        result.w = buffer[3];
-        result
+impl Mul<Point3> for Matrix<4> {
+    type Output = Point3;
+
+    /// Divides by `w` afterwards, which only matters for projective matrices
+    fn mul(self, rhs: Point3) -> Self::Output {
+        let Tuple { x, y, z, w } = self * Tuple::from(rhs);
+        Point3::new(x / w, y / w, z / w)
+    }
+}
+
+impl Mul<Vector3> for Matrix<4> {
+    type Output = Vector3;
+
+    /// Leaves out the translation, like for a `Tuple` with `w` of 0
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        let Tuple { x, y, z, .. } = self * Tuple::from(rhs);
+        Vector3::new(x, y, z)
     }
 }
 
@@ -328,13 +367,20 @@ mod tests {
 
     use super::*;
 
+    fn assert_matrix_near<const N: usize>(expected: &Matrix<N>, actual: &Matrix<N>) {
+        for (expected_row, actual_row) in expected.data.iter().zip(&actual.data) {
+            for (e, a) in expected_row.iter().zip(actual_row) {
+                assert!(equal(*e, *a), "expected {:?}, got {:?}", expected, actual);
+            }
+        }
+    }
+
     #[test]
     fn new_4x4_matrix() {
-        let matrix_values: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 
-                                        5.5, 6.5, 7.5, 8.5, 
-                                        9.0, 10.0, 11.0, 12.0, 
-                                        13.5, 14.5, 15.5, 16.5];
-        let matrix = Matrix::new_with_data(4, 4, matrix_values);
+        let matrix = Matrix::new([[1.0, 2.0, 3.0, 4.0],
+                                  [5.5, 6.5, 7.5, 8.5],
+                                  [9.0, 10.0, 11.0, 12.0],
+                                  [13.5, 14.5, 15.5, 16.5]]);
         assert_eq!(matrix.get(0, 0), Some(1.0));
         assert_eq!(matrix.get(0, 3), Some(4.0));
         assert_eq!(matrix.get(1, 0), Some(5.5));
@@ -346,8 +392,7 @@ mod tests {
 
     #[test]
     fn new_2x2_matrix() {
-        let matrix_values = vec![-3.0, 5.0, 1.0, -2.0];
-        let matrix = Matrix::new_with_data(2, 2, matrix_values);
+        let matrix = Matrix2::new([[-3.0, 5.0], [1.0, -2.0]]);
         assert_eq!(matrix.get(0, 0), Some(-3.0));
         assert_eq!(matrix.get(0, 1), Some(5.0));
         assert_eq!(matrix.get(1, 0), Some(1.0));
@@ -356,22 +401,38 @@ mod tests {
 
     #[test]
     fn new_3x3_matrix() {
-        let matrix_values = vec![-3.0, 5.0, 0.0, 1.0, -2.0, -7.0, 0.0, 1.0, 1.0];
-        let matrix = Matrix::new_with_data(3, 3, matrix_values);
+        let matrix = Matrix3::new([[-3.0, 5.0, 0.0], [1.0, -2.0, -7.0], [0.0, 1.0, 1.0]]);
         assert_eq!(matrix.get(0, 0), Some(-3.0));
         assert_eq!(matrix.get(1, 1), Some(-2.0));
         assert_eq!(matrix.get(2, 2), Some(1.0));
     }
 
     #[test]
-    fn multiplying_two_matrices() {
-        let matrix_a = Matrix::new_4x4_with_data(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0,
-                                                                9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0]);
-        let matrix_b = Matrix::new_4x4_with_data(vec![-2.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, -1.0,
-                                                                4.0, 3.0, 6.0, 5.0, 1.0, 2.0, 7.0, 8.0]);
+    fn matrices_are_copied_and_indexed_by_row_and_column() {
+        let mut a = Matrix::translation(1.0, 2.0, 3.0);
+        let b = a;
+        a[(0, 3)] = 5.0;
+        assert_eq!(a[(0, 3)], 5.0);
+        assert_eq!(b[(0, 3)], 1.0);
+        assert_eq!(b.get(4, 0), None);
+        assert_eq!(Matrix3::new_identity_matrix().transpose(), Matrix3::new_identity_matrix());
+    }
 
-        let expected = Matrix::new_4x4_with_data(vec![20.0, 22.0, 50.0, 48.0, 44.0, 54.0, 114.0, 108.0,
-                                                                40.0, 58.0, 110.0, 102.0, 16.0, 26.0, 46.0, 42.0]);
+    #[test]
+    fn multiplying_two_matrices() {
+        let matrix_a = Matrix::new([[1.0, 2.0, 3.0, 4.0],
+                                    [5.0, 6.0, 7.0, 8.0],
+                                    [9.0, 8.0, 7.0, 6.0],
+                                    [5.0, 4.0, 3.0, 2.0]]);
+        let matrix_b = Matrix::new([[-2.0, 1.0, 2.0, 3.0],
+                                    [3.0, 2.0, 1.0, -1.0],
+                                    [4.0, 3.0, 6.0, 5.0],
+                                    [1.0, 2.0, 7.0, 8.0]]);
+
+        let expected = Matrix::new([[20.0, 22.0, 50.0, 48.0],
+                                    [44.0, 54.0, 114.0, 108.0],
+                                    [40.0, 58.0, 110.0, 102.0],
+                                    [16.0, 26.0, 46.0, 42.0]]);
         let result = matrix_a * matrix_b;
 
         assert_eq!(expected, result);
@@ -379,8 +440,10 @@ mod tests {
 
     #[test]
     fn multiply_matrix_and_tuple() {
-        let matrix = Matrix::new_4x4_with_data(vec![1.0, 2.0, 3.0, 4.0, 2.0, 4.0, 4.0, 2.0,
-                                                                8.0, 6.0, 4.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        let matrix = Matrix::new([[1.0, 2.0, 3.0, 4.0],
+                                  [2.0, 4.0, 4.0, 2.0],
+                                  [8.0, 6.0, 4.0, 1.0],
+                                  [0.0, 0.0, 0.0, 1.0]]);
         let tuple = Tuple::new(1.0, 2.0, 3.0, 1.0);
         let expected = Tuple::new(18.0, 24.0, 33.0, 1.0);
         let result = matrix * tuple;
@@ -389,17 +452,21 @@ mod tests {
 
     #[test]
     fn transpose() {
-        let matrix = Matrix::new_4x4_with_data(vec![0.0, 9.0, 3.0, 0.0, 9.0, 8.0, 0.0, 8.0,
-                                                        1.0, 8.0, 5.0, 3.0, 0.0, 0.0, 5.0, 8.0]);
-        let expected = Matrix::new_4x4_with_data(vec![0.0, 9.0, 1.0, 0.0, 9.0, 8.0, 8.0, 0.0,
-                                                        3.0, 0.0, 5.0, 5.0, 0.0, 8.0, 3.0, 8.0]);
+        let matrix = Matrix::new([[0.0, 9.0, 3.0, 0.0],
+                                  [9.0, 8.0, 0.0, 8.0],
+                                  [1.0, 8.0, 5.0, 3.0],
+                                  [0.0, 0.0, 5.0, 8.0]]);
+        let expected = Matrix::new([[0.0, 9.0, 1.0, 0.0],
+                                    [9.0, 8.0, 8.0, 0.0],
+                                    [3.0, 0.0, 5.0, 5.0],
+                                    [0.0, 8.0, 3.0, 8.0]]);
         let result = matrix.transpose();
         assert_eq!(expected, result);
     }
 
     #[test]
     fn determinant() {
-        let matrix = Matrix::new_with_data(2, 2, vec![1.0, 5.0, -3.0, 2.0]);
+        let matrix = Matrix::new([[1.0, 5.0], [-3.0, 2.0]]);
         let expected = 17.0;
         let result = matrix.determinant();
         assert_eq!(expected, result);
@@ -407,26 +474,26 @@ mod tests {
 
     #[test]
     fn submatrix_of_3x3() {
-        let matrix = Matrix::new_with_data(3, 3, vec![1.0, 5.0, 0.0, -3.0, 2.0, 7.0,
-                                                                                    0.0, 6.0, -3.0]);
-        let expected = Matrix::new_with_data(2, 2, vec![-3.0, 2.0, 0.0, 6.0]);
+        let matrix = Matrix::new([[1.0, 5.0, 0.0], [-3.0, 2.0, 7.0], [0.0, 6.0, -3.0]]);
+        let expected = Matrix::new([[-3.0, 2.0], [0.0, 6.0]]);
         let result = matrix.submatrix(0, 2);
         assert_eq!(expected, result);
     }
 
     #[test]
     fn submatrix_of_4x4() {
-        let matrix = Matrix::new_4x4_with_data(vec![-6.0, 1.0, 1.0, 6.0, -8.0, 5.0, 8.0, 6.0,
-                                                            -1.0, 0.0, 8.0, 2.0, -7.0, 1.0, -1.0, 1.0]);
-        let expected = Matrix::new_with_data(3, 3, vec![-6.0, 1.0, 6.0, -8.0,
-                                                            8.0, 6.0 ,-7.0, -1.0, 1.0]);
+        let matrix = Matrix::new([[-6.0, 1.0, 1.0, 6.0],
+                                  [-8.0, 5.0, 8.0, 6.0],
+                                  [-1.0, 0.0, 8.0, 2.0],
+                                  [-7.0, 1.0, -1.0, 1.0]]);
+        let expected = Matrix::new([[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]]);
         let result = matrix.submatrix(2, 1);
         assert_eq!(expected, result);
     }
 
     #[test]
     fn minor() {
-        let matrix = Matrix::new_3x3_with_data(vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0]);
+        let matrix = Matrix::new([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
         let expected = 25.0;
         let result = matrix.minor(1, 0);
         assert_eq!(expected, result);
@@ -434,14 +501,14 @@ mod tests {
 
     #[test]
     fn cofactor() {
-        let matrix = Matrix::new_3x3_with_data(vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0]);
+        let matrix = Matrix::new([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
         assert_eq!(matrix.minor(0, 0), matrix.cofactor(0, 0));
         assert_eq!(matrix.minor(1, 0), matrix.cofactor(1, 0).neg());
     }
 
     #[test]
     fn determinant_of_3x3() {
-        let matrix = Matrix::new_3x3_with_data(vec![1.0, 2.0, 6.0, -5.0, 8.0, -4.0, 2.0, 6.0, 4.0]);
+        let matrix = Matrix::new([[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]]);
         assert_eq!(56.0, matrix.cofactor(0, 0));
         assert_eq!(12.0, matrix.cofactor(0, 1));
         assert_eq!(-46.0, matrix.cofactor(0, 2));
@@ -450,8 +517,10 @@ mod tests {
 
     #[test]
     fn determinant_of_4x4() {
-        let matrix = Matrix::new_4x4_with_data(vec![-2.0, -8.0, 3.0, 5.0, -3.0, 1.0, 7.0, 3.0,
-                                                            1.0, 2.0, -9.0, 6.0, -6.0, 7.0, 7.0, -9.0]);
+        let matrix = Matrix::new([[-2.0, -8.0, 3.0, 5.0],
+                                  [-3.0, 1.0, 7.0, 3.0],
+                                  [1.0, 2.0, -9.0, 6.0],
+                                  [-6.0, 7.0, 7.0, -9.0]]);
         assert_eq!(690.0, matrix.cofactor(0, 0));
         assert_eq!(447.0, matrix.cofactor(0, 1));
         assert_eq!(210.0, matrix.cofactor(0, 2));
@@ -461,8 +530,10 @@ mod tests {
 
     #[test]
     fn matrix_is_invertible() {
-        let matrix = Matrix::new_4x4_with_data(vec![6.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 6.0,
-                                                            4.0, -9.0, 3.0, -7.0, 9.0, 1.0, 7.0, -6.0]);
+        let matrix = Matrix::new([[6.0, 4.0, 4.0, 4.0],
+                                  [5.0, 5.0, 7.0, 6.0],
+                                  [4.0, -9.0, 3.0, -7.0],
+                                  [9.0, 1.0, 7.0, -6.0]]);
         let expected = true;
         let result = matrix.is_invertible();
         assert_eq!(expected, result);
@@ -470,71 +541,125 @@ mod tests {
 
     #[test]
     fn matrix_is_not_invertible() {
-        let matrix = Matrix::new_4x4_with_data(vec![-4.0, 2.0, -2.0, -3.0, 9.0, 6.0, 2.0, 6.0,
-                                                            0.0, -5.0, 1.0, -5.0, 0.0, 0.0, 0.0, 0.0]);
+        let matrix = Matrix::new([[-4.0, 2.0, -2.0, -3.0],
+                                  [9.0, 6.0, 2.0, 6.0],
+                                  [0.0, -5.0, 1.0, -5.0],
+                                  [0.0, 0.0, 0.0, 0.0]]);
         let expected = false;
         let result = matrix.is_invertible();
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn trying_to_invert_a_singular_matrix() {
+        assert_eq!(Matrix::scaling(1.0, 0.0, 1.0).try_inverse(), None);
+        assert_eq!(Matrix::new([[1.0, 2.0], [2.0, 4.0]]).try_inverse(), None);
+        let almost_dependent = Matrix::new([[1.0, 2.0], [2.0, 4.000001]]);
+        assert_eq!(almost_dependent.try_inverse(), None);
+        assert!(almost_dependent.try_inverse_with_tolerance(0.0).is_some());
+        let mut not_a_number = Matrix4::new_identity_matrix();
+        not_a_number[(1, 2)] = f32::NAN;
+        assert_eq!(not_a_number.try_inverse(), None);
+    }
+
+    #[test]
+    fn the_tolerance_is_relative_to_each_column() {
+        // tiny next to the translation, but nowhere near flat
+        let small = Matrix::translation(1000.0, 0.0, 0.0) * Matrix::scaling(1e-4, 1e-4, 1e-4);
+        let inverse = small.try_inverse().unwrap();
+        assert_eq!(inverse * Tuple::new_vector(0.0, 1e-4, 0.0), Tuple::new_vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn determinant_and_inverse_of_a_5x5_matrix() {
+        let matrix = Matrix::new([[2.0, 0.0, 1.0, 3.0, -1.0],
+                                  [1.0, 4.0, 0.0, -2.0, 2.0],
+                                  [0.0, 1.0, 3.0, 1.0, 0.0],
+                                  [5.0, -1.0, 2.0, 0.0, 1.0],
+                                  [1.0, 2.0, -3.0, 1.0, 4.0]]);
+        let mut expected = 0.0;
+        for column in 0..5 {
+            let mut minor = [[0.0; 4]; 4];
+            for row in 1..5 {
+                let values = (0..5).filter(|&c| c != column).map(|c| matrix[(row, c)]);
+                for (target, value) in minor[row - 1].iter_mut().zip(values) {
+                    *target = value;
+                }
+            }
+            let sign = if column % 2 == 0 { 1.0 } else { -1.0 };
+            expected += sign * matrix[(0, column)] * Matrix::new(minor).determinant();
+        }
+        assert_eq!(matrix.determinant(), expected);
+        assert_matrix_near(&(matrix * matrix.inverse()), &Matrix::new_identity_matrix());
+        assert_eq!(Matrix::new([[3.0, 8.0], [4.0, 6.0]]).determinant(), -14.0);
+        assert_eq!(Matrix::new([[7.0]]).determinant(), 7.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "singular")]
+    fn inverting_a_singular_matrix_panics() {
+        Matrix::scaling(0.0, 1.0, 1.0).inverse();
+    }
+
     #[test]
     fn inverse_of_4x4_matrix() {
-        let matrix = Matrix::new_4x4_with_data(vec![-5.0, 2.0, 6.0, -8.0, 1.0, -5.0, 1.0, 8.0, 
-                                                7.0, 7.0, -6.0, -7.0, 1.0, -3.0, 7.0, 4.0]);
+        let matrix = Matrix::new([[-5.0, 2.0, 6.0, -8.0],
+                                  [1.0, -5.0, 1.0, 8.0],
+                                  [7.0, 7.0, -6.0, -7.0],
+                                  [1.0, -3.0, 7.0, 4.0]]);
         let inversed = matrix.inverse();
-        let expected = Matrix::new_4x4_with_data(vec![0.21805, 0.45113, 0.24060, -0.04511,
-                                                            -0.80827, -1.45677, -0.44361, 0.52068,
-                                                            -0.07895, -0.22368, -0.05263, 0.19737,
-                                                            -0.52256, -0.81391, -0.30075, 0.30639]);
+        let expected = Matrix::new([[0.21805, 0.45113, 0.24060, -0.04511],
+                                    [-0.80827, -1.45677, -0.44361, 0.52068],
+                                    [-0.07895, -0.22368, -0.05263, 0.19737],
+                                    [-0.52256, -0.81391, -0.30075, 0.30639]]);
         assert_eq!(532.0, matrix.determinant());
         assert_eq!(-160.0, matrix.cofactor(2, 3));
         assert_eq!(105.0, matrix.cofactor(3, 2));
-        for i in 0..inversed.data.len() {
-            assert!(equal(expected.data[i], inversed.data[i]));
-        }
+        assert_matrix_near(&expected, &inversed);
     }
 
     #[test]
     fn inversing_4x4_matrices() {
-        let matrix1 = Matrix::new_4x4_with_data(vec![8.0, -5.0, 9.0, 2.0, 7.0, 5.0, 6.0, 1.0, 
-                                                            -6.0, 0.0, 9.0, 6.0, -3.0, 0.0, -9.0, -4.0]);
-        let matrix2 = Matrix::new_4x4_with_data(vec![9.0, 3.0, 0.0, 9.0, -5.0, -2.0, -6.0, -3.0, 
-                                                            -4.0, 9.0, 6.0, 4.0, -7.0, 6.0, 6.0, 2.0]);
+        let matrix1 = Matrix::new([[8.0, -5.0, 9.0, 2.0],
+                                   [7.0, 5.0, 6.0, 1.0],
+                                   [-6.0, 0.0, 9.0, 6.0],
+                                   [-3.0, 0.0, -9.0, -4.0]]);
+        let matrix2 = Matrix::new([[9.0, 3.0, 0.0, 9.0],
+                                   [-5.0, -2.0, -6.0, -3.0],
+                                   [-4.0, 9.0, 6.0, 4.0],
+                                   [-7.0, 6.0, 6.0, 2.0]]);
         
-        let expected1 = Matrix::new_4x4_with_data(vec![-0.15385, -0.15385, -0.28205, -0.53846,
-                                                                -0.07692, 0.12308, 0.02564, 0.03077,
-                                                                0.35897, 0.35897, 0.43590, 0.92308,
-                                                                -0.69231, -0.69231, -0.76923, -1.92308]);
-        let expected2 = Matrix::new_4x4_with_data(vec![-0.04074, -0.07778, 0.14444, -0.22222,
-                                                                -0.07778, 0.03333, 0.36667, -0.33333,
-                                                                -0.02901, -0.14630, -0.10926, 0.12963,
-                                                                0.17778, 0.06667, -0.26667, 0.33333]);
+        let expected1 = Matrix::new([[-0.15385, -0.15385, -0.28205, -0.53846],
+                                     [-0.07692, 0.12308, 0.02564, 0.03077],
+                                     [0.35897, 0.35897, 0.43590, 0.92308],
+                                     [-0.69231, -0.69231, -0.76923, -1.92308]]);
+        let expected2 = Matrix::new([[-0.04074, -0.07778, 0.14444, -0.22222],
+                                     [-0.07778, 0.03333, 0.36667, -0.33333],
+                                     [-0.02901, -0.14630, -0.10926, 0.12963],
+                                     [0.17778, 0.06667, -0.26667, 0.33333]]);
         let result1 = matrix1.inverse();
         let result2 = matrix2.inverse();
 
-        for i in 0..expected1.data.len() {
-            assert!(equal(expected1.data[i], result1.data[i]));
-        }
+        assert_matrix_near(&expected1, &result1);
         
-        for i in 0..expected2.data.len() {
-            assert!(equal(expected2.data[i], result2.data[i]));
-        }
+        assert_matrix_near(&expected2, &result2);
     }
 
     #[test]
     fn multiplying_a_product_with_its_inverse() {
-        let matrixa = Matrix::new_4x4_with_data(vec![3.0, -9.0, 7.0, 3.0, 3.0, -8.0, 2.0, -9.0, 
-                                                            -4.0, 4.0, 4.0, 1.0, -6.0, 5.0, -1.0, 1.0]);
+        let matrixa = Matrix::new([[3.0, -9.0, 7.0, 3.0],
+                                   [3.0, -8.0, 2.0, -9.0],
+                                   [-4.0, 4.0, 4.0, 1.0],
+                                   [-6.0, 5.0, -1.0, 1.0]]);
                                                             
-        let matrixb = Matrix::new_4x4_with_data(vec![8.0, 2.0, 2.0, 2.0, 3.0, -1.0, 7.0, 0.0, 
-                                                            7.0, 0.0, 5.0, 4.0, 6.0, -2.0, 0.0, 5.0]);
-        let c = &matrixa * &matrixb;
-        let inversed = &c * &matrixb.inverse();
-
-        for i in 0..matrixa.data.len() {
-            println!("{}) {:?}, {:?}", i, matrixa.data[i], inversed.data[i]);
-            assert!(equal(matrixa.data[i], inversed.data[i]));
-        }
+        let matrixb = Matrix::new([[8.0, 2.0, 2.0, 2.0],
+                                   [3.0, -1.0, 7.0, 0.0],
+                                   [7.0, 0.0, 5.0, 4.0],
+                                   [6.0, -2.0, 0.0, 5.0]]);
+        let c = matrixa * matrixb;
+        let inversed = c * matrixb.inverse();
+
+        assert_matrix_near(&matrixa, &inversed);
     }
 
     #[test]
@@ -611,8 +736,8 @@ mod tests {
         let expected1 = Tuple::new_point(0.0, 2f32.sqrt() / 2f32, 2f32.sqrt() / 2f32);
         let expected2 = Tuple::new_point(0.0, 0.0, 1.0);
 
-        let result1 = &half_quarter * &p;
-        let result2 = &full_quarter * &p;
+        let result1 = half_quarter * p;
+        let result2 = full_quarter * p;
 
         assert_eq!(expected1, result1);
         assert_eq!(expected2, result2);
@@ -638,8 +763,8 @@ mod tests {
         let expected1 = Tuple::new_point(2f32.sqrt() / 2f32, 0.0, 2f32.sqrt() / 2f32);
         let expected2 = Tuple::new_point(1.0, 0.0, 0.0);
 
-        let result1 = &half_quarter * &p;
-        let result2 = &full_quarter * &p;
+        let result1 = half_quarter * p;
+        let result2 = full_quarter * p;
 
         assert_eq!(expected1, result1);
         assert_eq!(expected2, result2);
@@ -654,8 +779,8 @@ mod tests {
         let expected1 = Tuple::new_point(2f32.sqrt().neg() / 2f32, 2f32.sqrt() / 2f32, 0.0);
         let expected2 = Tuple::new_point(-1.0, 0.0, 0.0);
 
-        let result1 = &half_quarter * &p;
-        let result2 = &full_quarter * &p;
+        let result1 = half_quarter * p;
+        let result2 = full_quarter * p;
 
         assert_eq!(expected1, result1);
         assert_eq!(expected2, result2);
@@ -729,15 +854,15 @@ mod tests {
         let c = Matrix::translation(10.0, 5.0, 7.0);
 
         let expected_p2 = Tuple::new_point(1.0, -1.0, 0.0);
-        let p2 = &a * &p;
+        let p2 = a * p;
         assert_eq!(expected_p2, p2);
 
         let expected_p3 = Tuple::new_point(5.0, -5.0, 0.0);
-        let p3 = &b * &p2;
+        let p3 = b * p2;
         assert_eq!(expected_p3, p3);
 
         let expected_p4 = Tuple::new_point(15.0, 0.0, 7.0);
-        let p4 = &c * &p3;
+        let p4 = c * p3;
         assert_eq!(expected_p4, p4);
 
         let expected_result = Tuple::new_point(15.0, 0.0, 7.0);
@@ -745,6 +870,85 @@ mod tests {
         assert_eq!(expected_result, result);
     }
 
+    #[test]
+    fn transformation_matrix_for_the_default_orientation() {
+        let from = Tuple::new_point(0.0, 0.0, 0.0);
+        let to = Tuple::new_point(0.0, 0.0, -1.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        assert_eq!(Matrix::view_transform(&from, &to, &up), Matrix::new_identity_matrix());
+    }
+
+    #[test]
+    fn view_transformation_matrix_looking_in_positive_z_direction() {
+        let from = Tuple::new_point(0.0, 0.0, 0.0);
+        let to = Tuple::new_point(0.0, 0.0, 1.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        assert_eq!(Matrix::view_transform(&from, &to, &up), Matrix::scaling(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn view_transformation_moves_the_world() {
+        let from = Tuple::new_point(0.0, 0.0, 8.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        assert_eq!(Matrix::view_transform(&from, &to, &up), Matrix::translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn arbitrary_view_transformation() {
+        let from = Tuple::new_point(1.0, 3.0, 2.0);
+        let to = Tuple::new_point(4.0, -2.0, 8.0);
+        let up = Tuple::new_vector(1.0, 1.0, 0.0);
+        let result = Matrix::view_transform(&from, &to, &up);
+        let expected = Matrix::new([[-0.50709, 0.50709, 0.67612, -2.36643],
+                                    [0.76772, 0.60609, 0.12122, -2.82843],
+                                    [-0.35857, 0.59761, -0.71714, 0.0],
+                                    [0.0, 0.0, 0.0, 1.0]]);
+        assert_matrix_near(&expected, &result);
+    }
+
+    #[test]
+    fn rotating_around_a_coordinate_axis_matches_the_axis_rotations() {
+        let axes = [(Axis::X, Tuple::new_vector(1.0, 0.0, 0.0)),
+                    (Axis::Y, Tuple::new_vector(0.0, 2.0, 0.0)),
+                    (Axis::Z, Tuple::new_vector(0.0, 0.0, 1.0))];
+        for (axis, vector) in axes {
+            assert_matrix_near(&Matrix::rotation(axis, 0.7), &Matrix::rotation_around(&vector, 0.7));
+        }
+    }
+
+    #[test]
+    fn rotating_around_a_diagonal_cycles_the_axes() {
+        let r = Matrix::rotation_around(&Tuple::new_vector(1.0, 1.0, 1.0), 2.0 * PI / 3.0);
+        assert_eq!(r * Tuple::new_point(1.0, 0.0, 0.0), Tuple::new_point(0.0, 1.0, 0.0));
+        assert_eq!(r * Tuple::new_point(0.0, 1.0, 0.0), Tuple::new_point(0.0, 0.0, 1.0));
+        assert_eq!(r * Tuple::new_point(2.0, 2.0, 2.0), Tuple::new_point(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn look_at_places_an_object_facing_the_target() {
+        let from = Tuple::new_point(1.0, 3.0, 2.0);
+        let to = Tuple::new_point(4.0, -2.0, 8.0);
+        let up = Tuple::new_vector(1.0, 1.0, 0.0);
+        let m = Matrix::look_at(&from, &to, &up);
+        assert_eq!(m * Tuple::new_point(0.0, 0.0, 0.0), from);
+        assert_eq!(m * Tuple::new_vector(0.0, 0.0, -1.0), (&to - &from).normalize());
+        assert!(equal(m.determinant(), 1.0));
+        let m = Matrix::look_at(&Tuple::new_point(0.0, 0.0, 8.0), &Tuple::new_point(0.0, 0.0, 0.0), &Tuple::new_vector(0.0, 1.0, 0.0));
+        assert_matrix_near(&m, &Matrix::view_transform(&Tuple::new_point(0.0, 0.0, 8.0), &Tuple::new_point(0.0, 0.0, 0.0),
+                                                       &Tuple::new_vector(0.0, 1.0, 0.0)).inverse());
+    }
+
+    #[test]
+    fn transforming_points_and_vectors() {
+        let transform = Matrix::translation(5.0, -3.0, 2.0) * Matrix::scaling(2.0, 2.0, 2.0);
+        assert_eq!(transform * Point3::new(-3.0, 4.0, 5.0), Point3::new(-1.0, 5.0, 12.0));
+        assert_eq!(transform * Vector3::new(-3.0, 4.0, 5.0), Vector3::new(-6.0, 8.0, 10.0));
+        let mut projection = Matrix4::new_identity_matrix();
+        projection[(3, 2)] = 1.0;
+        assert_eq!(projection * Point3::new(2.0, 4.0, 1.0), Point3::new(1.0, 2.0, 0.5));
+    }
+
     #[test]
     fn deneme12() {
         let twelve = Tuple::new_point(0.0, 0.0, 1.0);
@@ -0,0 +1,392 @@
+use crate::{matrix::{Matrix, Matrix3}, tuple::Tuple, bounds::BoundingBox};
+
+/// How closely the rotation found by `polar_decomposition` must stop changing
+const POLAR_TOLERANCE: f32 = 1e-6;
+const MAX_POLAR_ITERATIONS: usize = 30;
+
+/// A transform that changes over time, from `open` at time 0 to `close` at time 1.
+///
+/// It is a chain of layers multiplied together at the time they are needed, the first
+/// one being the outermost: a shape in a moving group keeps the group's motion and its own
+/// as separate layers, so it swings around the group's pivot instead of cutting across.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Motion {
+    open: Matrix,
+    close: Matrix,
+    layers: Vec<Layer>
+}
+
+/// One link of a `Motion`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layer {
+    Fixed(Matrix),
+    Moving(Segment)
+}
+
+impl Motion {
+    /// # Panics
+    /// When either end cannot be inverted, or only one of them mirrors the shape,
+    /// which would flatten it somewhere along the way
+    pub fn new(open: Matrix, close: Matrix) -> Motion {
+        Motion { open, close, layers: vec![Layer::Moving(Segment::new(open, close))] }
+    }
+
+    /// Chains the layers, the first one being applied last
+    pub fn from_layers(layers: Vec<Layer>) -> Motion {
+        let product = |time: f32| layers.iter()
+            .fold(Matrix::new_identity_matrix(), |matrix, layer| matrix * layer.at(time));
+        Motion { open: product(0.0), close: product(1.0), layers }
+    }
+
+    /// Returns if any of the layers moves, a chain of fixed ones is just a matrix
+    pub fn is_moving(&self) -> bool {
+        self.layers.iter().any(|layer| matches!(layer, Layer::Moving(_)))
+    }
+
+    pub fn open(&self) -> &Matrix {
+        &self.open
+    }
+
+    pub fn close(&self) -> &Matrix {
+        &self.close
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Returns the transform at the given time, times outside 0 to 1 stay at the nearest end
+    pub fn at(&self, time: f32) -> Matrix {
+        if time <= 0.0 {
+            return self.open;
+        }
+        if time >= 1.0 {
+            return self.close;
+        }
+        self.layers.iter().fold(Matrix::new_identity_matrix(), |matrix, layer| matrix * layer.at(time))
+    }
+
+    /// Returns a box around everything the given object space box covers during the motion.
+    /// Each layer sweeps the box of the layers inside it, which holds them at every time.
+    pub fn sweep(&self, local: &BoundingBox) -> BoundingBox {
+        self.layers.iter().rev().fold(*local, |bounds, layer| match layer {
+            Layer::Fixed(matrix) => bounds.transform(matrix),
+            Layer::Moving(segment) => segment.sweep(&bounds)
+        })
+    }
+}
+
+impl Layer {
+    fn at(&self, time: f32) -> Matrix {
+        match self {
+            Layer::Fixed(matrix) => *matrix,
+            Layer::Moving(segment) => segment.at(time)
+        }
+    }
+}
+
+/// A single move between two transforms. Both ends are split into a translation, a rotation
+/// and the stretch left over (scaling and shearing). In between the translation and the
+/// stretch are interpolated linearly and the rotation spherically, so a spinning shape keeps
+/// its size and shape instead of shrinking through the middle the way interpolated matrices do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    open: Matrix,
+    close: Matrix,
+    start: Keyframe,
+    end: Keyframe
+}
+
+impl Segment {
+    fn new(open: Matrix, close: Matrix) -> Segment {
+        assert!(open.determinant().signum() == close.determinant().signum(),
+                "a motion cannot go between a mirrored and an unmirrored transform");
+        let start = Keyframe::new(&open);
+        let mut end = Keyframe::new(&close);
+        // q and -q are the same rotation, the one closer to the start turns the short way around
+        if start.rotation.dot(&end.rotation) < 0.0 {
+            end.rotation = end.rotation.scale(-1.0);
+        }
+        Segment { open, close, start, end }
+    }
+
+    fn at(&self, time: f32) -> Matrix {
+        if time <= 0.0 {
+            return self.open;
+        }
+        if time >= 1.0 {
+            return self.close;
+        }
+        let lerp = |a: f32, b: f32| a + (b - a) * time;
+        let rotation = self.start.rotation.slerp(&self.end.rotation, time).to_matrix();
+        let mut stretch = Matrix3::new_zero();
+        for row in 0..3 {
+            for column in 0..3 {
+                stretch[(row, column)] = lerp(self.start.stretch[(row, column)], self.end.stretch[(row, column)]);
+            }
+        }
+        let linear = rotation * stretch;
+
+        let mut matrix = Matrix::new_identity_matrix();
+        for row in 0..3 {
+            for column in 0..3 {
+                matrix[(row, column)] = linear[(row, column)];
+            }
+            matrix[(row, 3)] = lerp(self.start.translation[row], self.end.translation[row]);
+        }
+        matrix
+    }
+
+    fn sweep(&self, local: &BoundingBox) -> BoundingBox {
+        let ends = local.transform(&self.open).merge(&local.transform(&self.close));
+        // without a change of rotation every point moves along a straight line between its ends
+        if local.is_empty() || self.start.rotation.dot(&self.end.rotation) >= 1.0 - POLAR_TOLERANCE {
+            return ends;
+        }
+        if !local.is_finite() {
+            return BoundingBox::new_infinite();
+        }
+
+        // the rotation moves a point around the translation without changing its distance,
+        // and the stretch is interpolated linearly so that distance peaks at one of the ends
+        let corners = [local.min.x, local.max.x].into_iter()
+            .flat_map(|x| [local.min.y, local.max.y].into_iter().map(move |y| (x, y)))
+            .flat_map(|(x, y)| [local.min.z, local.max.z].into_iter().map(move |z| [x, y, z]));
+        let radius = corners
+            .flat_map(|corner| [&self.start.stretch, &self.end.stretch].map(|stretch| {
+                (0..3).map(|row| (0..3).map(|column| stretch[(row, column)] * corner[column]).sum::<f32>().powi(2))
+                    .sum::<f32>().sqrt()
+            }))
+            .fold(0.0, f32::max);
+        let (a, b) = (self.start.translation, self.end.translation);
+        let swept = BoundingBox::new(Tuple::new_point(a[0].min(b[0]) - radius, a[1].min(b[1]) - radius, a[2].min(b[2]) - radius),
+                                     Tuple::new_point(a[0].max(b[0]) + radius, a[1].max(b[1]) + radius, a[2].max(b[2]) + radius));
+        swept.merge(&ends)
+    }
+}
+
+/// One end of a motion, taken apart so it can be interpolated piece by piece
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Keyframe {
+    translation: [f32; 3],
+    rotation: Quaternion,
+    stretch: Matrix3
+}
+
+impl Keyframe {
+    fn new(matrix: &Matrix) -> Keyframe {
+        let mut linear = Matrix3::new_zero();
+        for row in 0..3 {
+            for column in 0..3 {
+                linear[(row, column)] = matrix[(row, column)];
+            }
+        }
+        let (rotation, stretch) = polar_decomposition(&linear);
+        Keyframe {
+            translation: [matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]],
+            rotation: Quaternion::from_rotation(&rotation),
+            stretch
+        }
+    }
+}
+
+/// Splits a matrix into `rotation * stretch`, finding the rotation by averaging the
+/// matrix with its inverse transpose until it stops changing (Shoemake and Duff).
+/// A mirroring matrix keeps its mirror in the stretch so the rotation stays a rotation.
+fn polar_decomposition(linear: &Matrix3) -> (Matrix3, Matrix3) {
+    let mut rotation = *linear;
+    for _ in 0..MAX_POLAR_ITERATIONS {
+        let inverse_transpose = rotation.inverse().transpose();
+        let mut change: f32 = 0.0;
+        for row in 0..3 {
+            for column in 0..3 {
+                let average = (rotation[(row, column)] + inverse_transpose[(row, column)]) / 2.0;
+                change = change.max((average - rotation[(row, column)]).abs());
+                rotation[(row, column)] = average;
+            }
+        }
+        if change < POLAR_TOLERANCE {
+            break;
+        }
+    }
+    if rotation.determinant() < 0.0 {
+        for row in 0..3 {
+            for column in 0..3 {
+                rotation[(row, column)] = -rotation[(row, column)];
+            }
+        }
+    }
+    (rotation, rotation.transpose() * *linear)
+}
+
+/// A rotation as a unit quaternion, which turns smoothly from one orientation to another
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Quaternion {
+    w: f32,
+    x: f32,
+    y: f32,
+    z: f32
+}
+
+impl Quaternion {
+    /// Expects a pure rotation, picks the largest component first to stay precise (Shepperd's method)
+    fn from_rotation(m: &Matrix3) -> Quaternion {
+        let trace = m[(0, 0)] + m[(1, 1)] + m[(2, 2)];
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion { w: s / 4.0, x: (m[(2, 1)] - m[(1, 2)]) / s, y: (m[(0, 2)] - m[(2, 0)]) / s, z: (m[(1, 0)] - m[(0, 1)]) / s }
+        } else if m[(0, 0)] > m[(1, 1)] && m[(0, 0)] > m[(2, 2)] {
+            let s = (1.0 + m[(0, 0)] - m[(1, 1)] - m[(2, 2)]).sqrt() * 2.0;
+            Quaternion { w: (m[(2, 1)] - m[(1, 2)]) / s, x: s / 4.0, y: (m[(0, 1)] + m[(1, 0)]) / s, z: (m[(0, 2)] + m[(2, 0)]) / s }
+        } else if m[(1, 1)] > m[(2, 2)] {
+            let s = (1.0 + m[(1, 1)] - m[(0, 0)] - m[(2, 2)]).sqrt() * 2.0;
+            Quaternion { w: (m[(0, 2)] - m[(2, 0)]) / s, x: (m[(0, 1)] + m[(1, 0)]) / s, y: s / 4.0, z: (m[(1, 2)] + m[(2, 1)]) / s }
+        } else {
+            let s = (1.0 + m[(2, 2)] - m[(0, 0)] - m[(1, 1)]).sqrt() * 2.0;
+            Quaternion { w: (m[(1, 0)] - m[(0, 1)]) / s, x: (m[(0, 2)] + m[(2, 0)]) / s, y: (m[(1, 2)] + m[(2, 1)]) / s, z: s / 4.0 }
+        };
+        quaternion.normalize()
+    }
+
+    fn dot(&self, other: &Quaternion) -> f32 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn scale(&self, factor: f32) -> Quaternion {
+        Quaternion { w: self.w * factor, x: self.x * factor, y: self.y * factor, z: self.z * factor }
+    }
+
+    fn add(&self, other: &Quaternion) -> Quaternion {
+        Quaternion { w: self.w + other.w, x: self.x + other.x, y: self.y + other.y, z: self.z + other.z }
+    }
+
+    fn normalize(&self) -> Quaternion {
+        self.scale(1.0 / self.dot(self).sqrt())
+    }
+
+    /// Turns at a constant speed along the shortest arc, expects `other` on the same side as `self`
+    fn slerp(&self, other: &Quaternion, time: f32) -> Quaternion {
+        let cos = self.dot(other).min(1.0);
+        // nearly the same rotation, the arc is too short to divide by its sine
+        if cos > 1.0 - POLAR_TOLERANCE {
+            return self.scale(1.0 - time).add(&other.scale(time)).normalize();
+        }
+        let angle = cos.acos();
+        let sin = angle.sin();
+        self.scale(((1.0 - time) * angle).sin() / sin).add(&other.scale((time * angle).sin() / sin))
+    }
+
+    fn to_matrix(self) -> Matrix3 {
+        let Quaternion { w, x, y, z } = self;
+        Matrix3::new([[1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+                      [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+                      [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{helper::Axis, transform::Transform};
+
+    use super::*;
+
+    fn assert_matrix_near(expected: &Matrix, actual: &Matrix) {
+        for row in 0..4 {
+            for column in 0..4 {
+                assert!((expected[(row, column)] - actual[(row, column)]).abs() < 1e-4,
+                        "expected {:?}, got {:?}", expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn a_motion_starts_and_ends_on_its_transforms() {
+        let open = Transform::identity().scale(1.0, 2.0, 3.0).rotate(Axis::Y, 0.3).translate(1.0, 2.0, 3.0).matrix();
+        let close = Transform::identity().rotate(Axis::Z, 2.0).translate(-4.0, 0.0, 1.0).matrix();
+        let motion = Motion::new(open, close);
+        assert_eq!(motion.at(0.0), open);
+        assert_eq!(motion.at(-1.0), open);
+        assert_eq!(motion.at(1.0), close);
+        assert_eq!(motion.at(2.0), close);
+        assert_matrix_near(&open, &motion.at(1e-6));
+        assert_matrix_near(&close, &motion.at(1.0 - 1e-6));
+    }
+
+    #[test]
+    fn translations_are_interpolated_linearly() {
+        let motion = Motion::new(Matrix::translation(0.0, 0.0, 0.0), Matrix::translation(4.0, -2.0, 8.0));
+        assert_matrix_near(&Matrix::translation(1.0, -0.5, 2.0), &motion.at(0.25));
+        let motion = Motion::new(Matrix::scaling(1.0, 1.0, 1.0), Matrix::scaling(3.0, 1.0, 5.0));
+        assert_matrix_near(&Matrix::scaling(2.0, 1.0, 3.0), &motion.at(0.5));
+    }
+
+    #[test]
+    fn rotations_turn_at_a_constant_speed_without_shrinking() {
+        let motion = Motion::new(Matrix::new_identity_matrix(), Matrix::rotation(Axis::Y, PI / 2.0));
+        assert_matrix_near(&Matrix::rotation(Axis::Y, PI / 4.0), &motion.at(0.5));
+        assert_matrix_near(&Matrix::rotation(Axis::Y, PI / 6.0), &motion.at(1.0 / 3.0));
+
+        let open = Transform::identity().scale(2.0, 1.0, 1.0).rotate(Axis::Z, 0.5).translate(0.0, 1.0, 0.0).matrix();
+        let close = Transform::identity().scale(2.0, 1.0, 1.0).rotate(Axis::Z, 2.5).translate(0.0, 1.0, 0.0).matrix();
+        let halfway = Transform::identity().scale(2.0, 1.0, 1.0).rotate(Axis::Z, 1.5).translate(0.0, 1.0, 0.0).matrix();
+        assert_matrix_near(&halfway, &Motion::new(open, close).at(0.5));
+    }
+
+    #[test]
+    fn rotations_take_the_short_way_around() {
+        let motion = Motion::new(Matrix::rotation(Axis::X, -3.0), Matrix::rotation(Axis::X, 3.0));
+        assert_matrix_near(&Matrix::rotation(Axis::X, PI), &motion.at(0.5));
+    }
+
+    #[test]
+    fn mirrored_transforms_keep_their_mirror() {
+        let open = Matrix::scaling(-1.0, 1.0, 1.0);
+        let close = Matrix::scaling(-1.0, 1.0, 1.0) * Matrix::rotation(Axis::X, 1.0);
+        let motion = Motion::new(open, close);
+        assert_matrix_near(&(Matrix::scaling(-1.0, 1.0, 1.0) * Matrix::rotation(Axis::X, 0.5)), &motion.at(0.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "mirrored")]
+    fn a_motion_cannot_turn_a_shape_inside_out() {
+        Motion::new(Matrix::new_identity_matrix(), Matrix::scaling(-1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn chained_layers_are_multiplied_at_every_time() {
+        let spin = Motion::new(Matrix::new_identity_matrix(), Matrix::rotation(Axis::Z, PI / 2.0));
+        let offset = Matrix::translation(2.0, 0.0, 0.0);
+        let chain = Motion::from_layers(vec![spin.layers()[0].clone(), Layer::Fixed(offset)]);
+        assert!(chain.is_moving());
+        for time in [0.0, 0.3, 0.5, 1.0] {
+            assert_matrix_near(&(spin.at(time) * offset), &chain.at(time));
+        }
+        let local = BoundingBox::new(Tuple::new_point(-0.5, -0.5, -0.5), Tuple::new_point(0.5, 0.5, 0.5));
+        let swept = chain.sweep(&local);
+        for step in 0..=20 {
+            let at = local.transform(&chain.at(step as f32 / 20.0));
+            assert!(swept.contains_box(&at), "time {}: {:?} is outside {:?}", step, at, swept);
+        }
+        assert!(!Motion::from_layers(vec![Layer::Fixed(offset)]).is_moving());
+    }
+
+    #[test]
+    fn the_swept_box_holds_the_shape_all_along_the_motion() {
+        let local = BoundingBox::new(Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_point(1.0, 1.0, 1.0));
+        let motion = Motion::new(Matrix::translation(0.0, 0.0, 0.0), Matrix::translation(5.0, 0.0, 0.0));
+        assert_eq!(motion.sweep(&local), BoundingBox::new(Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_point(6.0, 1.0, 1.0)));
+
+        let open = Transform::identity().scale(3.0, 0.5, 0.5).translate(2.0, 0.0, 0.0).matrix();
+        let close = Transform::identity().scale(3.0, 0.5, 0.5).rotate(Axis::Z, PI).translate(-2.0, 1.0, 0.0).matrix();
+        let motion = Motion::new(open, close);
+        let swept = motion.sweep(&local);
+        for step in 0..=20 {
+            let at = local.transform(&motion.at(step as f32 / 20.0));
+            assert!(swept.contains_box(&at), "time {}: {:?} is outside {:?}", step, at, swept);
+        }
+        assert!(BoundingBox::new_empty().transform(&open).is_empty());
+        assert!(motion.sweep(&BoundingBox::new_empty()).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use std::{fs, io, path::Path};
+
+use crate::{tuple::Tuple, group::Group, triangle::{Triangle, SmoothTriangle}, shape::Shape};
+
+/// A line of the file that could not be understood and was left out of the mesh
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoredLine {
+    /// 1-based, as shown by text editors
+    pub line_number: usize,
+    pub reason: String
+}
+
+/// The content of a Wavefront OBJ file.
+/// Faces before any `g` statement go to the default group, the others
+/// to the group named by the last `g` statement above them.
+#[derive(Debug, Default)]
+pub struct ObjFile {
+    pub vertices: Vec<Tuple>,
+    pub normals: Vec<Tuple>,
+    pub texture_coordinates: Vec<(f32, f32)>,
+    pub ignored: Vec<IgnoredLine>,
+    default_group: Group,
+    groups: Vec<(String, Group)>
+}
+
+/// Indices of one corner of a face, already resolved to 0-based
+#[derive(Debug, Clone, Copy)]
+struct FaceVertex {
+    vertex: usize,
+    normal: Option<usize>
+}
+
+impl ObjFile {
+    pub fn default_group(&self) -> &Group {
+        &self.default_group
+    }
+
+    /// Returns the group declared with `g name`
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter()
+            .find(|(group_name, _)| group_name == name)
+            .map(|(_, group)| group)
+    }
+
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn ignored_count(&self) -> usize {
+        self.ignored.len()
+    }
+
+    /// Collects the default group and every named group into a single group
+    pub fn into_group(self) -> Group {
+        let mut group = self.default_group;
+        for (_, named) in self.groups {
+            group.add_child(Box::new(named));
+        }
+        group
+    }
+
+    fn current_group(&mut self, name: &Option<String>) -> &mut Group {
+        match name {
+            None => &mut self.default_group,
+            Some(name) => {
+                let index = self.groups.iter().position(|(group_name, _)| group_name == name)
+                    .unwrap_or_else(|| {
+                        self.groups.push((name.clone(), Group::new()));
+                        self.groups.len() - 1
+                    });
+                &mut self.groups[index].1
+            }
+        }
+    }
+
+    fn ignore(&mut self, line_number: usize, reason: String) {
+        self.ignored.push(IgnoredLine { line_number, reason });
+    }
+}
+
+/// Parses the text of an OBJ file. Lines that are malformed or use unsupported
+/// statements never abort the parse, they are collected in `ObjFile::ignored`.
+pub fn parse_obj(source: &str) -> ObjFile {
+    let mut obj = ObjFile::default();
+    let mut group_name: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        let result = match keyword {
+            "v" => parse_floats(&arguments, 3).map(|v| obj.vertices.push(Tuple::new_point(v[0], v[1], v[2]))),
+            "vn" => parse_floats(&arguments, 3).map(|v| obj.normals.push(Tuple::new_vector(v[0], v[1], v[2]))),
+            // the optional w of a texture coordinate is ignored
+            "vt" => parse_floats(&arguments, 1).map(|v| obj.texture_coordinates.push((v[0], *v.get(1).unwrap_or(&0.0)))),
+            "f" => parse_face(&obj, &arguments).map(|corners| {
+                let triangles = fan_triangulation(&obj, &corners);
+                let group = obj.current_group(&group_name);
+                for triangle in triangles {
+                    group.add_child(triangle);
+                }
+            }),
+            "g" => if arguments.is_empty() {
+                Err("group statement without a name".to_string())
+            } else {
+                group_name = Some(arguments.join(" "));
+                Ok(())
+            },
+            _ => Err(format!("unsupported statement '{}'", keyword))
+        };
+
+        if let Err(reason) = result {
+            obj.ignore(line_number, reason);
+        }
+    }
+    obj
+}
+
+/// Reads and parses an OBJ file from disk
+pub fn load_obj_file<P: AsRef<Path>>(path: P) -> io::Result<ObjFile> {
+    Ok(parse_obj(&fs::read_to_string(path)?))
+}
+
+fn parse_floats(arguments: &[&str], minimum: usize) -> Result<Vec<f32>, String> {
+    if arguments.len() < minimum {
+        return Err(format!("expected at least {} numbers, found {}", minimum, arguments.len()));
+    }
+    arguments.iter()
+        .map(|argument| argument.parse::<f32>().map_err(|_| format!("'{}' is not a number", argument)))
+        .collect()
+}
+
+/// Converts an OBJ index, 1-based or negative to count from the end, to a 0-based one
+fn resolve_index(token: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token.parse().map_err(|_| format!("'{}' is not a valid {} index", token, kind))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} is out of range, {} defined so far", kind, index, count));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parses the corners of a face given as `v`, `v/vt`, `v//vn` or `v/vt/vn`
+fn parse_face(obj: &ObjFile, arguments: &[&str]) -> Result<Vec<FaceVertex>, String> {
+    if arguments.len() < 3 {
+        return Err(format!("a face needs at least 3 vertices, found {}", arguments.len()));
+    }
+    arguments.iter()
+        .map(|argument| {
+            let mut parts = argument.split('/');
+            let vertex = resolve_index(parts.next().unwrap_or(""), obj.vertices.len(), "vertex")?;
+            if let Some(texture) = parts.next().filter(|texture| !texture.is_empty()) {
+                resolve_index(texture, obj.texture_coordinates.len(), "texture coordinate")?;
+            }
+            let normal = match parts.next().filter(|normal| !normal.is_empty()) {
+                Some(normal) => Some(resolve_index(normal, obj.normals.len(), "normal")?),
+                None => None
+            };
+            Ok(FaceVertex { vertex, normal })
+        })
+        .collect()
+}
+
+/// Splits a convex polygon into triangles that all share its first corner.
+/// Smooth triangles are used when every corner of the face has a normal.
+fn fan_triangulation(obj: &ObjFile, corners: &[FaceVertex]) -> Vec<Box<dyn Shape>> {
+    let normals: Option<Vec<Tuple>> = corners.iter()
+        .map(|corner| corner.normal.map(|normal| obj.normals[normal]))
+        .collect();
+    let vertex = |index: usize| obj.vertices[corners[index].vertex];
+
+    (1..corners.len() - 1)
+        .map(|index| -> Box<dyn Shape> {
+            match &normals {
+                Some(normals) => Box::new(SmoothTriangle::new(vertex(0), vertex(index), vertex(index + 1),
+                                                              normals[0], normals[index], normals[index + 1])),
+                None => Box::new(Triangle::new(vertex(0), vertex(index), vertex(index + 1)))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.\n";
+        let obj = parse_obj(gibberish);
+        assert_eq!(obj.ignored_count(), 5);
+        assert!(obj.default_group().is_empty());
+    }
+
+    #[test]
+    fn vertex_records() {
+        let obj = parse_obj("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n");
+        assert_eq!(obj.vertices, vec![Tuple::new_point(-1.0, 1.0, 0.0), Tuple::new_point(-1.0, 0.5, 0.0),
+                                      Tuple::new_point(1.0, 0.0, 0.0), Tuple::new_point(1.0, 1.0, 0.0)]);
+        assert_eq!(obj.ignored_count(), 0);
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n");
+        let children = obj.default_group().children();
+        assert_eq!(children.len(), 2);
+        let r = crate::ray::Ray::new(Tuple::new_point(0.5, 0.75, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(children[0].intersect(&r).is_empty());
+        assert_eq!(children[1].intersect(&r).len(), 1);
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n");
+        assert_eq!(obj.default_group().children().len(), 3);
+        assert_eq!(obj.ignored_count(), 0);
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                             g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n");
+        assert!(obj.default_group().is_empty());
+        assert_eq!(obj.group("FirstGroup").unwrap().children().len(), 1);
+        assert_eq!(obj.group("SecondGroup").unwrap().children().len(), 1);
+        assert_eq!(obj.group_names().collect::<Vec<_>>(), vec!["FirstGroup", "SecondGroup"]);
+    }
+
+    #[test]
+    fn converting_an_obj_file_to_a_group() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 4\n\
+                             g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n");
+        let group = obj.into_group();
+        assert_eq!(group.children().len(), 3);
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let obj = parse_obj("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\n");
+        assert_eq!(obj.normals, vec![Tuple::new_vector(0.0, 0.0, 1.0), Tuple::new_vector(0.707, 0.0, -0.707),
+                                     Tuple::new_vector(1.0, 2.0, 3.0)]);
+    }
+
+    #[test]
+    fn texture_coordinate_records() {
+        let obj = parse_obj("vt 0.5 0.25\nvt 1\n");
+        assert_eq!(obj.texture_coordinates, vec![(0.5, 0.25), (1.0, 0.0)]);
+    }
+
+    #[test]
+    fn faces_with_normals_and_texture_coordinates() {
+        let obj = parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0 0\n\
+                             vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+                             f 1//3 2//1 3//2\nf 1/1/3 2/1/1 3/1/2\nf -3/-1/-1 -2/-1/-3 -1/-1/-2\n");
+        assert_eq!(obj.ignored_count(), 0);
+        let children = obj.default_group().children();
+        assert_eq!(children.len(), 3);
+        let r = crate::ray::Ray::new(Tuple::new_point(-0.2, 0.3, -2.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = children[0].intersect(&r);
+        let n = xs[0].object.normal_at(&Tuple::new_point(0.0, 0.0, 0.0), &xs[0]);
+        assert_eq!(n, Tuple::new_vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_line_number() {
+        let obj = parse_obj("v 1 2\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 9\nf 1 2\nvn a b c\ng\nf 1 2 3 # comment\n");
+        let lines: Vec<usize> = obj.ignored.iter().map(|ignored| ignored.line_number).collect();
+        assert_eq!(lines, vec![1, 5, 6, 7, 8]);
+        assert_eq!(obj.default_group().children().len(), 1);
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt::Debug;
+
+use crate::{color::Color, tuple::Tuple, matrix::Matrix, helper::EPSILON, shape::{Shape, CachedTransform}};
+
+/// A color that varies over space. Every pattern has its own transform,
+/// so a point goes from world space to object space and then to pattern space.
+pub trait Pattern: Debug + Send + Sync {
+    fn transformation(&self) -> &CachedTransform;
+
+    fn set_transform(&mut self, transform: Matrix);
+
+    /// Returns the color at a point which is already converted to pattern space
+    fn local_pattern_at(&self, point: &Tuple) -> Color;
+
+    fn transform(&self) -> &Matrix {
+        &self.transformation().matrix
+    }
+
+    /// Returns the color at a point given in the space the pattern is placed in,
+    /// the object space of a shape or the pattern space of an enclosing pattern
+    fn pattern_at(&self, point: &Tuple) -> Color {
+        let pattern_point = &self.transformation().inverse * point;
+        self.local_pattern_at(&pattern_point)
+    }
+
+    /// Returns the color at a world space point on the given object, placed where it is at `time`
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: &Tuple, time: f32) -> Color {
+        let object_point = object.world_to_object(world_point, time);
+        self.pattern_at(&object_point)
+    }
+}
+
+/// Returns the integer below the value, as used by the alternating patterns.
+/// Points on a surface at an integer coordinate, like a plane at y = 0, land a
+/// rounding error above or below it, so values that close count as the integer.
+fn floor(value: f32) -> i64 {
+    (value + EPSILON).floor() as i64
+}
+
+/// A single color everywhere, mostly used as a building block of nested patterns
+#[derive(Debug)]
+pub struct SolidPattern {
+    transformation: CachedTransform,
+    pub color: Color
+}
+
+impl SolidPattern {
+    pub fn new(color: Color) -> SolidPattern {
+        SolidPattern { transformation: CachedTransform::default(), color }
+    }
+}
+
+impl Pattern for SolidPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, _point: &Tuple) -> Color {
+        self.color
+    }
+}
+
+/// Alternates between `a` and `b` every unit along x
+#[derive(Debug)]
+pub struct StripePattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl StripePattern {
+    pub fn new(a: Color, b: Color) -> StripePattern {
+        StripePattern::new_nested(Box::new(SolidPattern::new(a)), Box::new(SolidPattern::new(b)))
+    }
+
+    pub fn new_nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> StripePattern {
+        StripePattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for StripePattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        if floor(point.x).rem_euclid(2) == 0 {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+/// Blends linearly from `a` to `b` along x, repeating every unit
+#[derive(Debug)]
+pub struct GradientPattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl GradientPattern {
+    pub fn new(a: Color, b: Color) -> GradientPattern {
+        GradientPattern::new_nested(Box::new(SolidPattern::new(a)), Box::new(SolidPattern::new(b)))
+    }
+
+    pub fn new_nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> GradientPattern {
+        GradientPattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for GradientPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        let a = self.a.pattern_at(point);
+        let b = self.b.pattern_at(point);
+        let fraction = point.x - point.x.floor();
+        a + (b - a) * fraction
+    }
+}
+
+/// Alternates between `a` and `b` in concentric rings around the y axis
+#[derive(Debug)]
+pub struct RingPattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl RingPattern {
+    pub fn new(a: Color, b: Color) -> RingPattern {
+        RingPattern::new_nested(Box::new(SolidPattern::new(a)), Box::new(SolidPattern::new(b)))
+    }
+
+    pub fn new_nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> RingPattern {
+        RingPattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for RingPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt();
+        if floor(distance).rem_euclid(2) == 0 {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+/// Alternates between `a` and `b` in unit cubes, like a 3D checkerboard
+#[derive(Debug)]
+pub struct CheckersPattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl CheckersPattern {
+    pub fn new(a: Color, b: Color) -> CheckersPattern {
+        CheckersPattern::new_nested(Box::new(SolidPattern::new(a)), Box::new(SolidPattern::new(b)))
+    }
+
+    pub fn new_nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> CheckersPattern {
+        CheckersPattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for CheckersPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        if (floor(point.x) + floor(point.y) + floor(point.z)).rem_euclid(2) == 0 {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+/// Blends linearly from `a` to `b` with the distance from the y axis, repeating every unit
+#[derive(Debug)]
+pub struct RadialGradientPattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl RadialGradientPattern {
+    pub fn new(a: Color, b: Color) -> RadialGradientPattern {
+        RadialGradientPattern::new_nested(Box::new(SolidPattern::new(a)), Box::new(SolidPattern::new(b)))
+    }
+
+    pub fn new_nested(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> RadialGradientPattern {
+        RadialGradientPattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for RadialGradientPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        let a = self.a.pattern_at(point);
+        let b = self.b.pattern_at(point);
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt();
+        let fraction = distance - distance.floor();
+        a + (b - a) * fraction
+    }
+}
+
+/// Averages the colors of two patterns at every point
+#[derive(Debug)]
+pub struct BlendedPattern {
+    transformation: CachedTransform,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>
+}
+
+impl BlendedPattern {
+    pub fn new(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> BlendedPattern {
+        BlendedPattern { transformation: CachedTransform::default(), a, b }
+    }
+}
+
+impl Pattern for BlendedPattern {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transformation = CachedTransform::new(transform);
+    }
+
+    fn local_pattern_at(&self, point: &Tuple) -> Color {
+        (self.a.pattern_at(point) + self.b.pattern_at(point)) * 0.5
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{sphere::Sphere};
+
+    use super::*;
+
+    /// Returns the pattern space point as a color, to check the transforms applied
+    #[derive(Debug, Default)]
+    pub(crate) struct TestPattern {
+        transformation: CachedTransform
+    }
+
+    impl Pattern for TestPattern {
+        fn transformation(&self) -> &CachedTransform {
+            &self.transformation
+        }
+
+        fn set_transform(&mut self, transform: Matrix) {
+            self.transformation = CachedTransform::new(transform);
+        }
+
+        fn local_pattern_at(&self, point: &Tuple) -> Color {
+            Color::new(point.x, point.y, point.z)
+        }
+    }
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn the_default_pattern_transformation() {
+        let pattern = TestPattern::default();
+        assert_eq!(pattern.transform(), &Matrix::new_identity_matrix());
+    }
+
+    #[test]
+    fn pattern_with_an_object_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let pattern = TestPattern::default();
+        let c = pattern.pattern_at_shape(&shape, &Tuple::new_point(2.0, 3.0, 4.0), 0.0);
+        assert_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn pattern_with_a_pattern_transformation() {
+        let shape = Sphere::new();
+        let mut pattern = TestPattern::default();
+        pattern.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let c = pattern.pattern_at_shape(&shape, &Tuple::new_point(2.0, 3.0, 4.0), 0.0);
+        assert_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn pattern_with_both_an_object_and_a_pattern_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut pattern = TestPattern::default();
+        pattern.set_transform(Matrix::translation(0.5, 1.0, 1.5));
+        let c = pattern.pattern_at_shape(&shape, &Tuple::new_point(2.5, 3.0, 3.5), 0.0);
+        assert_eq!(c, Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn stripe_pattern_is_constant_in_y_and_z() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 1.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 2.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 1.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 2.0)), white());
+    }
+
+    #[test]
+    fn stripe_pattern_alternates_in_x() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.9, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(-0.1, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(-1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(-1.1, 0.0, 0.0)), white());
+    }
+
+    #[test]
+    fn gradient_linearly_interpolates_between_colors() {
+        let pattern = GradientPattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn ring_should_extend_in_both_x_and_z() {
+        let pattern = RingPattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 1.0)), black());
+        // 0.708 is just slightly more than sqrt(2)/2
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.708, 0.0, 0.708)), black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_every_dimension() {
+        let pattern = CheckersPattern::new(white(), black());
+        for (x, y, z) in [(0.0, 0.0, 0.0), (0.99, 0.0, 0.0), (0.0, 0.99, 0.0), (0.0, 0.0, 0.99)] {
+            assert_eq!(pattern.pattern_at(&Tuple::new_point(x, y, z)), white());
+        }
+        for (x, y, z) in [(1.01, 0.0, 0.0), (0.0, 1.01, 0.0), (0.0, 0.0, 1.01)] {
+            assert_eq!(pattern.pattern_at(&Tuple::new_point(x, y, z)), black());
+        }
+    }
+
+    #[test]
+    fn checkers_are_stable_on_a_surface_at_an_integer_coordinate() {
+        let pattern = CheckersPattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.5, -0.0000001, 0.5)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.5, 0.0000001, 0.5)), white());
+    }
+
+    #[test]
+    fn radial_gradient_interpolates_with_the_distance_from_the_axis() {
+        let pattern = RadialGradientPattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.0, 5.0, 0.25)), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.6, 0.0, 0.8)), white());
+    }
+
+    #[test]
+    fn blended_pattern_averages_both_patterns() {
+        let mut vertical = StripePattern::new(white(), black());
+        vertical.set_transform(Matrix::rotation(crate::helper::Axis::Y, std::f32::consts::PI / 2.0));
+        let pattern = BlendedPattern::new(Box::new(StripePattern::new(white(), black())), Box::new(vertical));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(0.5, 0.0, -0.5)), white());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(1.5, 0.0, -0.5)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(1.5, 0.0, 0.5)), black());
+    }
+
+    #[test]
+    fn nested_patterns_use_their_own_transforms() {
+        let mut inner = TestPattern::default();
+        inner.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        let mut pattern = StripePattern::new_nested(Box::new(inner), Box::new(SolidPattern::new(black())));
+        pattern.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        // (3, 2, 4) is (1.5, 1, 2) in stripe space, which is an `a` stripe,
+        // and (0.5, 1, 2) once the nested pattern's translation is undone
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(3.0, 2.0, 4.0)), black());
+        assert_eq!(pattern.pattern_at(&Tuple::new_point(1.0, 2.0, 4.0)), Color::new(-0.5, 1.0, 2.0));
+    }
+}
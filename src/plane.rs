@@ -0,0 +1,116 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::Intersection, helper::EPSILON,
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox};
+
+/// An infinite plane spanning x and z in its object space
+#[derive(Debug, Default)]
+pub struct Plane {
+    transformation: CachedTransform,
+    material: MaterialSlot
+}
+
+impl Plane {
+    pub fn new() -> Plane {
+        Plane {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default()
+        }
+    }
+}
+
+impl Shape for Plane {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        // a ray parallel to the plane, or coplanar with it, never hits
+        if ray.direction.y.abs() < EPSILON {
+            return vec![];
+        }
+
+        let t = -ray.origin.y / ray.direction.y;
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _point: &Tuple, _hit: &Intersection) -> Tuple {
+        Tuple::new_vector(0.0, 1.0, 0.0)
+    }
+
+    /// Infinite along x and z, flat along y
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+                         Tuple::new_point(f32::INFINITY, 0.0, f32::INFINITY))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_of_a_plane_is_constant_everywhere() {
+        let p = Plane::new();
+        let i = Intersection::new(0.0, &p);
+        let expected = Tuple::new_vector(0.0, 1.0, 0.0);
+        assert_eq!(p.local_normal_at(&Tuple::new_point(0.0, 0.0, 0.0), &i), expected);
+        assert_eq!(p.local_normal_at(&Tuple::new_point(10.0, 0.0, -10.0), &i), expected);
+        assert_eq!(p.local_normal_at(&Tuple::new_point(-5.0, 0.0, 150.0), &i), expected);
+    }
+
+    #[test]
+    fn intersect_with_a_ray_parallel_to_the_plane() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::new_point(0.0, 10.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersect_with_a_coplanar_ray() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_intersecting_a_plane_from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::new_point(0.0, 1.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.0);
+        assert!(std::ptr::addr_eq(xs[0].object, &p));
+    }
+
+    #[test]
+    fn ray_intersecting_a_plane_from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Tuple::new_point(0.0, -1.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 1.0);
+        assert!(std::ptr::addr_eq(xs[0].object, &p));
+    }
+
+    #[test]
+    fn a_plane_has_a_bounding_box() {
+        let b = Plane::new().local_bounds();
+        assert_eq!((b.min.x, b.min.y, b.min.z), (f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY));
+        assert_eq!((b.max.x, b.max.y, b.max.z), (f32::INFINITY, 0.0, f32::INFINITY));
+    }
+}
@@ -0,0 +1,450 @@
+use crate::{canvas::{Canvas, ImageError}, color::Color, zlib::{self, Compression}};
+
+/// The eight bytes every PNG file starts with
+pub const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// CRC-32 as used by PNG chunks and gzip (polynomial 0xEDB88320)
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0xFFFF_FFFF, data) ^ 0xFFFF_FFFF
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32_update(crc32_update(0xFFFF_FFFF, kind), data) ^ 0xFFFF_FFFF;
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub(crate) fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filters every scanline with the PNG filter that gives the smallest sum of
+/// absolute differences, the usual heuristic for photographic images
+fn filter_scanlines(pixels: &[u8], stride: usize) -> Vec<u8> {
+    const BYTES_PER_PIXEL: usize = 3;
+    let zero_row = vec![0; stride];
+    let mut filtered = Vec::with_capacity(pixels.len() + pixels.len() / stride.max(1));
+    let mut candidate = vec![0u8; stride];
+
+    let rows: Vec<&[u8]> = pixels.chunks(stride).collect();
+    for (y, row) in rows.iter().enumerate() {
+        let above = if y == 0 { &zero_row[..] } else { rows[y - 1] };
+        let mut best: Option<(u64, u8, Vec<u8>)> = None;
+        for filter in 0..5u8 {
+            for x in 0..stride {
+                let left = if x >= BYTES_PER_PIXEL { row[x - BYTES_PER_PIXEL] } else { 0 };
+                let upper_left = if x >= BYTES_PER_PIXEL { above[x - BYTES_PER_PIXEL] } else { 0 };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => above[x],
+                    3 => ((left as u16 + above[x] as u16) / 2) as u8,
+                    _ => paeth(left, above[x], upper_left)
+                };
+                candidate[x] = row[x].wrapping_sub(predicted);
+            }
+            let cost = candidate.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+            if best.as_ref().is_none_or(|(best_cost, _, _)| cost < *best_cost) {
+                best = Some((cost, filter, candidate.clone()));
+            }
+        }
+        let (_, filter, bytes) = best.unwrap();
+        filtered.push(filter);
+        filtered.extend_from_slice(&bytes);
+    }
+    filtered
+}
+
+/// Encodes the canvas as an 8 bit RGB PNG, clamping colors like the PPM output
+pub fn encode(canvas: &Canvas, compression: Compression) -> Vec<u8> {
+    let stride = canvas.width * 3;
+    let pixels: Vec<u8> = canvas.pixels.iter().flat_map(|color| color.to_rgb8()).collect();
+    let filtered = match compression {
+        Compression::Stored => pixels.chunks(stride.max(1)).flat_map(|row| std::iter::once(0).chain(row.iter().copied())).collect(),
+        Compression::Fixed => filter_scanlines(&pixels, stride.max(1))
+    };
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(canvas.width as u32).to_be_bytes());
+    header.extend_from_slice(&(canvas.height as u32).to_be_bytes());
+    // bit depth 8, truecolor, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib::compress(&filtered, compression));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Starting column and row, then column and row steps, of the seven Adam7 passes
+const ADAM7: [(usize, usize, usize, usize); 7] =
+    [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+/// The fields of the IHDR chunk the decoder needs
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Header, ImageError> {
+        if data.len() != 13 {
+            return Err(decode_error(format!("IHDR has {} bytes instead of 13", data.len())));
+        }
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let (bit_depth, color_type) = (data[8], data[9]);
+        if width == 0 || height == 0 {
+            return Err(decode_error(format!("invalid size {}x{}", width, height)));
+        }
+        let depths: &[u8] = match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(decode_error(format!("unknown color type {}", color_type)))
+        };
+        if !depths.contains(&bit_depth) {
+            return Err(decode_error(format!("bit depth {} is not allowed for color type {}", bit_depth, color_type)));
+        }
+        if data[10] != 0 || data[11] != 0 {
+            return Err(decode_error("unknown compression or filter method".to_string()));
+        }
+        let interlaced = match data[12] {
+            0 => false,
+            1 => true,
+            method => return Err(decode_error(format!("unknown interlace method {}", method)))
+        };
+        Ok(Header { width, height, bit_depth, color_type, interlaced })
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    /// Bytes per row of a `width` pixels wide image, without the filter byte, `None` on overflow
+    fn stride(&self, width: usize) -> Option<usize> {
+        width.checked_mul(self.bits_per_pixel()).map(|bits| bits.div_ceil(8))
+    }
+}
+
+fn decode_error(message: String) -> ImageError {
+    ImageError::Decode(message)
+}
+
+/// Reverses the scanline filters in place, `filtered` holds one filter byte before each row
+fn unfilter(filtered: &[u8], rows: usize, stride: usize, bytes_per_pixel: usize) -> Result<Vec<u8>, ImageError> {
+    let mut pixels = vec![0u8; rows * stride];
+    for y in 0..rows {
+        let filter = filtered[y * (stride + 1)];
+        let source = &filtered[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (previous, current) = pixels.split_at_mut(y * stride);
+        let above = if y == 0 { None } else { Some(&previous[(y - 1) * stride..]) };
+        let row = &mut current[..stride];
+        for x in 0..stride {
+            let left = if x >= bytes_per_pixel { row[x - bytes_per_pixel] } else { 0 };
+            let up = above.map_or(0, |above| above[x]);
+            let upper_left = match above {
+                Some(above) if x >= bytes_per_pixel => above[x - bytes_per_pixel],
+                _ => 0
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, upper_left),
+                _ => return Err(decode_error(format!("unknown filter type {} on row {}", filter, y)))
+            };
+            row[x] = source[x].wrapping_add(predicted);
+        }
+    }
+    Ok(pixels)
+}
+
+/// Returns the `index`th sample of an unfiltered row, whatever the bit depth
+fn sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+fn pixel_color(header: &Header, palette: &[[u8; 3]], row: &[u8], x: usize) -> Result<Color, ImageError> {
+    let channels = header.channels();
+    let maximum = ((1u32 << header.bit_depth) - 1) as f32;
+    let channel = |c: usize| sample(row, x * channels + c, header.bit_depth) as f32 / maximum;
+    match header.color_type {
+        0 | 4 => Ok(Color::new(channel(0), channel(0), channel(0))),
+        3 => {
+            let index = sample(row, x, header.bit_depth) as usize;
+            let [r, g, b] = palette.get(index)
+                .ok_or_else(|| decode_error(format!("palette index {} is out of range", index)))?;
+            Ok(Color::new(*r as f32 / 255.0, *g as f32 / 255.0, *b as f32 / 255.0))
+        }
+        _ => Ok(Color::new(channel(0), channel(1), channel(2)))
+    }
+}
+
+/// Decodes a PNG of any standard color type and bit depth, interlaced or not.
+/// Transparency is dropped and ancillary chunks such as gamma are ignored.
+pub fn decode(data: &[u8]) -> Result<Canvas, ImageError> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(decode_error("missing PNG signature".to_string()));
+    }
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut compressed = Vec::new();
+    let mut position = SIGNATURE.len();
+    loop {
+        if data.len() < position + 12 {
+            return Err(decode_error("truncated file, missing IEND".to_string()));
+        }
+        let length = u32::from_be_bytes([data[position], data[position + 1], data[position + 2], data[position + 3]]) as usize;
+        let kind: [u8; 4] = [data[position + 4], data[position + 5], data[position + 6], data[position + 7]];
+        let name = String::from_utf8_lossy(&kind).into_owned();
+        let end = (position + 8).checked_add(length).filter(|end| end + 4 <= data.len())
+            .ok_or_else(|| decode_error(format!("truncated {} chunk", name)))?;
+        let chunk = &data[position + 8..end];
+        let crc = u32::from_be_bytes([data[end], data[end + 1], data[end + 2], data[end + 3]]);
+        if crc32(&data[position + 4..end]) != crc {
+            return Err(decode_error(format!("CRC mismatch in {} chunk", name)));
+        }
+        position = end + 4;
+
+        if header.is_none() && &kind != b"IHDR" {
+            return Err(decode_error(format!("expected IHDR as the first chunk, found {}", name)));
+        }
+        match &kind {
+            b"IHDR" if header.is_none() => header = Some(Header::parse(chunk)?),
+            b"PLTE" => {
+                if !chunk.len().is_multiple_of(3) || chunk.len() > 256 * 3 {
+                    return Err(decode_error(format!("palette has {} bytes", chunk.len())));
+                }
+                palette = chunk.chunks(3).map(|rgb| [rgb[0], rgb[1], rgb[2]]).collect();
+            }
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            // the fifth bit of the first letter is set for chunks that are safe to ignore
+            _ if kind[0] & 0x20 != 0 => {}
+            _ => return Err(decode_error(format!("unsupported critical chunk {}", name)))
+        }
+    }
+
+    let header = header.ok_or_else(|| decode_error("missing IHDR".to_string()))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(decode_error("indexed image without a palette".to_string()));
+    }
+    let passes: Vec<(usize, usize, usize, usize)> = if header.interlaced {
+        ADAM7.to_vec()
+    } else {
+        vec![(0, 0, 1, 1)]
+    };
+    // the size of every pass is known before anything is inflated or allocated, so neither
+    // a header claiming a huge image nor a stream inflating past it can exhaust memory
+    let too_large = || decode_error(format!("{}x{} is too large", header.width, header.height));
+    let mut layout = Vec::with_capacity(passes.len());
+    let mut expected: usize = 0;
+    for (x0, y0, dx, dy) in passes {
+        let columns = header.width.saturating_sub(x0).div_ceil(dx);
+        let rows = header.height.saturating_sub(y0).div_ceil(dy);
+        if columns == 0 || rows == 0 {
+            continue;
+        }
+        let stride = header.stride(columns).ok_or_else(too_large)?;
+        let size = (stride + 1).checked_mul(rows).ok_or_else(too_large)?;
+        expected = expected.checked_add(size).ok_or_else(too_large)?;
+        layout.push(((x0, y0, dx, dy), columns, rows, stride));
+    }
+    let filtered = zlib::decompress(&compressed, expected).map_err(|message| decode_error(format!("IDAT: {}", message)))?;
+    if filtered.len() < expected {
+        return Err(decode_error("not enough image data".to_string()));
+    }
+
+    let bytes_per_pixel = header.bits_per_pixel().div_ceil(8);
+    let mut canvas = Canvas::new(header.width, header.height);
+    let mut offset = 0;
+    for ((x0, y0, dx, dy), columns, rows, stride) in layout {
+        let size = rows * (stride + 1);
+        let pixels = unfilter(&filtered[offset..offset + size], rows, stride, bytes_per_pixel)?;
+        offset += size;
+        for (y, row) in pixels.chunks(stride).enumerate() {
+            for x in 0..columns {
+                canvas.write_pixel(x0 + x * dx, y0 + y * dy, pixel_color(&header, &palette, row, x)?);
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::*;
+
+    #[test]
+    fn crc32_of_known_inputs() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn png_has_signature_header_and_chunks() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        let png = encode(&canvas, Compression::Fixed);
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(png[16..29], [0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(png[29..33], crc32(&png[12..29]).to_be_bytes());
+        assert_eq!(png[png.len() - 12..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn paeth_predictor_picks_the_closest_neighbour() {
+        assert_eq!(paeth(10, 20, 10), 20);
+        assert_eq!(paeth(20, 10, 10), 20);
+        assert_eq!(paeth(10, 10, 20), 10);
+        assert_eq!(paeth(50, 60, 55), 55);
+    }
+    /// Builds a PNG around unfiltered scanlines, each filter byte is set to 0 here
+    fn png_with(header: [u8; 13], palette: Option<&[u8]>, scanlines: &[&[u8]]) -> Vec<u8> {
+        let raw: Vec<u8> = scanlines.iter().flat_map(|row| std::iter::once(0).chain(row.iter().copied())).collect();
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        if let Some(palette) = palette {
+            write_chunk(&mut png, b"PLTE", palette);
+        }
+        write_chunk(&mut png, b"tEXt", b"Comment\0ignored");
+        write_chunk(&mut png, b"IDAT", &zlib::compress(&raw, Compression::Fixed));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn ihdr(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace: u8) -> [u8; 13] {
+        let mut header = [0; 13];
+        header[..4].copy_from_slice(&width.to_be_bytes());
+        header[4..8].copy_from_slice(&height.to_be_bytes());
+        header[8..].copy_from_slice(&[bit_depth, color_type, 0, 0, interlace]);
+        header
+    }
+
+    #[test]
+    fn decoding_what_the_encoder_wrote() {
+        let mut canvas = Canvas::new(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                canvas.write_pixel(x, y, Color::new(x as f32 / 6.0, y as f32 / 4.0, ((x * y) % 3) as f32 / 2.0));
+            }
+        }
+        for compression in [Compression::Stored, Compression::Fixed] {
+            let decoded = decode(&encode(&canvas, compression)).unwrap();
+            assert_eq!((decoded.width, decoded.height), (7, 5));
+            for (expected, actual) in canvas.pixels.iter().zip(&decoded.pixels) {
+                assert_eq!(expected.to_rgb8(), actual.to_rgb8());
+            }
+        }
+    }
+
+    #[test]
+    fn decoding_low_bit_depth_grayscale() {
+        let png = png_with(ihdr(5, 1, 2, 0, 0), None, &[&[0b00_01_10_11, 0b11_000000]]);
+        let canvas = decode(&png).unwrap();
+        let grays: Vec<f32> = canvas.pixels.iter().map(|color| color.red()).collect();
+        assert_eq!(grays, vec![0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn decoding_a_palette_image() {
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let png = png_with(ihdr(3, 1, 4, 3, 0), Some(&palette), &[&[0x21, 0x00]]);
+        let canvas = decode(&png).unwrap();
+        assert_eq!(canvas.pixels, vec![Color::new(0.0, 0.0, 1.0), Color::new(0.0, 1.0, 0.0), Color::new(1.0, 0.0, 0.0)]);
+        let png = png_with(ihdr(1, 1, 8, 3, 0), Some(&palette), &[&[3]]);
+        assert!(decode(&png).is_err());
+    }
+
+    #[test]
+    fn decoding_sixteen_bit_rgba_drops_the_alpha() {
+        let png = png_with(ihdr(1, 1, 16, 6, 0), None, &[&[0xFF, 0xFF, 0x80, 0x00, 0x00, 0x00, 0x12, 0x34]]);
+        let canvas = decode(&png).unwrap();
+        assert_eq!(canvas.pixels[0], Color::new(1.0, 32768.0 / 65535.0, 0.0));
+    }
+
+    #[test]
+    fn decoding_an_interlaced_image() {
+        // a 3x3 grayscale image whose pixel values are 10 * y + x
+        let passes: [&[u8]; 6] = [&[0], &[2], &[20, 22], &[1], &[21], &[10, 11, 12]];
+        let png = png_with(ihdr(3, 3, 8, 0, 1), None, &passes);
+        let canvas = decode(&png).unwrap();
+        for y in 0..3 {
+            for x in 0..3 {
+                let expected = (10 * y + x) as f32 / 255.0;
+                assert_eq!(canvas.pixel_at(x, y).unwrap().red(), expected, "pixel {} {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_png_files_are_errors() {
+        let png = encode(&Canvas::new(2, 2), Compression::Fixed);
+        let mut corrupted = png.clone();
+        corrupted[20] ^= 1;
+        assert!(matches!(decode(&corrupted), Err(ImageError::Decode(message)) if message.contains("CRC")));
+        assert!(decode(&png[..png.len() - 12]).is_err());
+        assert!(decode(&png[1..]).is_err());
+        let mut unknown = SIGNATURE.to_vec();
+        write_chunk(&mut unknown, b"IHDR", &ihdr(1, 1, 8, 0, 0));
+        write_chunk(&mut unknown, b"ABCD", &[]);
+        assert!(matches!(decode(&unknown), Err(ImageError::Decode(message)) if message.contains("ABCD")));
+        assert!(decode(&png_with(ihdr(1, 1, 3, 0, 0), None, &[&[0]])).is_err());
+        assert!(decode(&png_with(ihdr(2, 1, 8, 0, 0), None, &[&[0]])).is_err());
+        let huge = png_with(ihdr(4_000_000_000, 4_000_000_000, 8, 2, 0), None, &[&[0, 1, 2, 3]]);
+        assert!(matches!(decode(&huge), Err(ImageError::Decode(message)) if message.contains("too large")));
+        let huge = png_with(ihdr(100_000, 100_000, 8, 0, 1), None, &[&[0]]);
+        assert!(matches!(decode(&huge), Err(ImageError::Decode(message)) if message.contains("image data")));
+        // a tiny image whose data inflates far past its two bytes stops at the limit
+        let bomb = png_with(ihdr(1, 1, 8, 0, 0), None, &[&[0; 1_000_000]]);
+        assert!(matches!(decode(&bomb), Err(ImageError::Decode(message)) if message.contains("inflates past 2 bytes")));
+    }
+}
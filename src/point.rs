@@ -0,0 +1,103 @@
+use std::ops::{Add, Sub};
+
+use crate::{helper::equal, tuple::{Tuple, TupleKindError}, vector::Vector3};
+
+/// A position in space. Two points can only be subtracted, which gives the
+/// `Vector3` between them, and a point only moves by adding a vector to it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Point3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32
+}
+
+impl Point3 {
+    pub fn new(x: f32, y: f32, z: f32) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    pub fn new_origin() -> Point3 {
+        Point3::default()
+    }
+
+    pub fn distance(&self, other: &Point3) -> f32 {
+        (*self - *other).magnitude()
+    }
+}
+
+impl PartialEq for Point3 {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self.x, other.x) && equal(self.y, other.y) && equal(self.z, other.z)
+    }
+}
+
+impl From<Point3> for Tuple {
+    fn from(point: Point3) -> Self {
+        Tuple::new_point(point.x, point.y, point.z)
+    }
+}
+
+impl TryFrom<Tuple> for Point3 {
+    type Error = TupleKindError;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Self::Error> {
+        if tuple.is_a_point() {
+            Ok(Point3::new(tuple.x, tuple.y, tuple.z))
+        } else {
+            Err(TupleKindError::NotAPoint(tuple.w))
+        }
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Vector3;
+
+    fn sub(self, rhs: Point3) -> Self::Output {
+        Vector3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Add<Vector3> for Point3 {
+    type Output = Point3;
+
+    fn add(self, rhs: Vector3) -> Self::Output {
+        Point3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub<Vector3> for Point3 {
+    type Output = Point3;
+
+    fn sub(self, rhs: Vector3) -> Self::Output {
+        Point3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtracting_two_points_gives_a_vector() {
+        let a = Point3::new(3.0, 2.0, 1.0);
+        let b = Point3::new(5.0, 6.0, 7.0);
+        assert_eq!(a - b, Vector3::new(-2.0, -4.0, -6.0));
+        assert_eq!(Point3::new_origin().distance(&Point3::new(0.0, 3.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn moving_a_point_by_a_vector() {
+        let p = Point3::new(3.0, 2.0, 1.0);
+        let v = Vector3::new(5.0, 6.0, 7.0);
+        assert_eq!(p + v, Point3::new(8.0, 8.0, 8.0));
+        assert_eq!(p - v, Point3::new(-2.0, -4.0, -6.0));
+    }
+
+    #[test]
+    fn converting_between_points_and_tuples() {
+        let p = Point3::new(4.0, -4.0, 3.0);
+        assert_eq!(Tuple::from(p), Tuple::new_point(4.0, -4.0, 3.0));
+        assert_eq!(Point3::try_from(Tuple::new_point(4.0, -4.0, 3.0)), Ok(p));
+        assert_eq!(Point3::try_from(Tuple::new_vector(4.0, -4.0, 3.0)), Err(TupleKindError::NotAPoint(0.0)));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{canvas::{Canvas, ImageError}, color::Color};
+
+/// Reads the whitespace separated tokens of a PPM header, skipping `#` comments
+struct Tokens<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            if byte == b'#' {
+                while self.data.get(self.position).is_some_and(|&byte| byte != b'\n' && byte != b'\r') {
+                    self.position += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace_and_comments();
+        let start = self.position;
+        while self.data.get(self.position).is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#') {
+            self.position += 1;
+        }
+        if start == self.position { None } else { Some(&self.data[start..self.position]) }
+    }
+
+    fn number(&mut self, name: &str) -> Result<u32, ImageError> {
+        let token = self.next().ok_or_else(|| decode_error(format!("missing {}", name)))?;
+        std::str::from_utf8(token).ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| decode_error(format!("{} '{}' is not a whole number", name, String::from_utf8_lossy(token))))
+    }
+}
+
+fn decode_error(message: String) -> ImageError {
+    ImageError::Decode(message)
+}
+
+/// Parses an ASCII (P3) or binary (P6) PPM. Samples are scaled by the maxval,
+/// binary files with a maxval above 255 use two big-endian bytes per sample.
+pub fn decode(data: &[u8]) -> Result<Canvas, ImageError> {
+    let mut tokens = Tokens { data, position: 0 };
+    let binary = match tokens.next() {
+        Some(b"P3") => false,
+        Some(b"P6") => true,
+        _ => return Err(decode_error("not a PPM image, expected P3 or P6".to_string()))
+    };
+    let width = tokens.number("width")? as usize;
+    let height = tokens.number("height")? as usize;
+    let maxval = tokens.number("maxval")?;
+    if maxval == 0 || maxval > 65535 {
+        return Err(decode_error(format!("maxval {} is outside 1..65535", maxval)));
+    }
+    let samples = width.checked_mul(height).and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| decode_error(format!("{}x{} is too large", width, height)))?;
+
+    let values: Vec<u32> = if binary {
+        // a single whitespace byte separates the header from the raster
+        if !data.get(tokens.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            return Err(decode_error("missing whitespace after the header".to_string()));
+        }
+        let raster = &data[tokens.position + 1..];
+        let bytes_per_sample = if maxval > 255 { 2 } else { 1 };
+        if raster.len() < samples * bytes_per_sample {
+            return Err(decode_error(format!("expected {} bytes of pixel data, found {}",
+                                            samples * bytes_per_sample, raster.len())));
+        }
+        raster.chunks(bytes_per_sample).take(samples)
+            .map(|sample| sample.iter().fold(0, |value, &byte| value << 8 | byte as u32))
+            .collect()
+    } else {
+        (0..samples).map(|_| tokens.number("sample")).collect::<Result<_, _>>()?
+    };
+
+    let mut canvas = Canvas::new(width, height);
+    for (pixel, rgb) in canvas.pixels.iter_mut().zip(values.chunks(3)) {
+        if let Some(value) = rgb.iter().find(|&&value| value > maxval) {
+            return Err(decode_error(format!("sample {} is above the maxval {}", value, maxval)));
+        }
+        let scale = |value: u32| value as f32 / maxval as f32;
+        *pixel = Color::new(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]));
+    }
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_an_ascii_ppm_with_comments() {
+        let canvas = decode(b"P3\n# made by hand\n2 1 # width and height\n255\n255 0 0  0 51 255\n").unwrap();
+        assert_eq!((canvas.width, canvas.height), (2, 1));
+        assert_eq!(canvas.pixel_at(0, 0), Some(&Color::new(1.0, 0.0, 0.0)));
+        assert_eq!(canvas.pixel_at(1, 0), Some(&Color::new(0.0, 0.2, 1.0)));
+    }
+
+    #[test]
+    fn samples_are_scaled_by_the_maxval() {
+        let canvas = decode(b"P3 1 1 100 50 25 100").unwrap();
+        assert_eq!(canvas.pixel_at(0, 0), Some(&Color::new(0.5, 0.25, 1.0)));
+    }
+
+    #[test]
+    fn reading_a_binary_ppm() {
+        let canvas = decode(b"P6\n2 1\n255\n\xff\x00\x00\x00\x33\xff").unwrap();
+        assert_eq!(canvas.pixel_at(0, 0), Some(&Color::new(1.0, 0.0, 0.0)));
+        assert_eq!(canvas.pixel_at(1, 0), Some(&Color::new(0.0, 0.2, 1.0)));
+    }
+
+    #[test]
+    fn binary_ppm_with_sixteen_bit_samples() {
+        let canvas = decode(b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00").unwrap();
+        assert_eq!(canvas.pixel_at(0, 0), Some(&Color::new(1.0, 32768.0 / 65535.0, 0.0)));
+    }
+
+    #[test]
+    fn reading_back_the_written_ppm() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, Color::new(0.2, 0.4, 0.6));
+        let decoded = decode(&canvas.to_ppm_binary()).unwrap();
+        assert_eq!(decoded.pixel_at(2, 1).unwrap().to_rgb8(), [51, 102, 153]);
+        let decoded = decode(canvas.to_ppm_format().as_bytes()).unwrap();
+        assert_eq!(decoded.pixel_at(2, 1).unwrap().to_rgb8(), [51, 102, 153]);
+    }
+
+    #[test]
+    fn malformed_ppm_files_are_errors() {
+        for data in [&b"P5 1 1 255 0"[..], b"P3 1 1", b"P3 1 1 0 0 0 0", b"P3 1 1 255 0 0",
+                     b"P3 1 1 255 0 0 256", b"P3 x 1 255", b"P6 2 1 255\n\x00\x00\x00"] {
+            assert!(matches!(decode(data), Err(ImageError::Decode(_))), "{:?}", String::from_utf8_lossy(data));
+        }
+    }
+}
@@ -18,6 +18,6 @@ impl Projectile {
     pub fn tick(self, env: &Environment) -> Projectile {
         let position = &self.point + &self.velocity;
         let velocity = &(&self.velocity + &env.gravity) + &env.wind;
-        Projectile { point: position, velocity: velocity }
+        Projectile { point: position, velocity }
     }
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+use crate::{tuple::Tuple, matrix::Matrix};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Tuple,
+    pub direction: Tuple,
+    /// When the ray is cast, from 0 as the shutter opens to 1 as it closes,
+    /// moving shapes are intersected where they are at that time
+    pub time: f32
+}
+
+impl Ray {
+    pub fn new(origin: Tuple, direction: Tuple) -> Ray {
+        Ray::new_with_time(origin, direction, 0.0)
+    }
+
+    pub fn new_with_time(origin: Tuple, direction: Tuple, time: f32) -> Ray {
+        Ray {
+            origin: Tuple { w: 1.0, ..origin },
+            direction: Tuple { w: 0.0, ..direction },
+            time
+        }
+    }
+
+    /// Returns the point at the given distance along the ray
+    pub fn position(&self, t: f32) -> Tuple {
+        &self.origin + &self.direction.multiply(t)
+    }
+
+    /// Returns a new ray with the matrix applied to both origin and direction, cast at the same time
+    pub fn transform(&self, matrix: &Matrix) -> Ray {
+        Ray {
+            origin: matrix * &self.origin,
+            direction: matrix * &self.direction,
+            time: self.time
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creating_and_querying_a_ray() {
+        let origin = Tuple::new_point(1.0, 2.0, 3.0);
+        let direction = Tuple::new_vector(4.0, 5.0, 6.0);
+        let r = Ray::new(origin, direction);
+        assert_eq!(r.origin, origin);
+        assert_eq!(r.direction, direction);
+        assert_eq!(r.time, 0.0);
+    }
+
+    #[test]
+    fn computing_a_point_from_a_distance() {
+        let r = Ray::new(Tuple::new_point(2.0, 3.0, 4.0), Tuple::new_vector(1.0, 0.0, 0.0));
+        assert_eq!(r.position(0.0), Tuple::new_point(2.0, 3.0, 4.0));
+        assert_eq!(r.position(1.0), Tuple::new_point(3.0, 3.0, 4.0));
+        assert_eq!(r.position(-1.0), Tuple::new_point(1.0, 3.0, 4.0));
+        assert_eq!(r.position(2.5), Tuple::new_point(4.5, 3.0, 4.0));
+    }
+
+    #[test]
+    fn translating_a_ray() {
+        let r = Ray::new(Tuple::new_point(1.0, 2.0, 3.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        let m = Matrix::translation(3.0, 4.0, 5.0);
+        let r2 = r.transform(&m);
+        assert_eq!(r2.origin, Tuple::new_point(4.0, 6.0, 8.0));
+        assert_eq!(r2.direction, Tuple::new_vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn a_transformed_ray_keeps_its_time() {
+        let r = Ray::new_with_time(Tuple::new_point(1.0, 2.0, 3.0), Tuple::new_vector(0.0, 1.0, 0.0), 0.25);
+        let r2 = r.transform(&Matrix::translation(3.0, 4.0, 5.0));
+        assert_eq!(r2.origin, Tuple::new_point(4.0, 6.0, 8.0));
+        assert_eq!(r2.time, 0.25);
+    }
+
+    #[test]
+    fn scaling_a_ray() {
+        let r = Ray::new(Tuple::new_point(1.0, 2.0, 3.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        let m = Matrix::scaling(2.0, 3.0, 4.0);
+        let r2 = r.transform(&m);
+        assert_eq!(r2.origin, Tuple::new_point(2.0, 6.0, 12.0));
+        assert_eq!(r2.direction, Tuple::new_vector(0.0, 3.0, 0.0));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::helper::hash_to_unit;
+
+/// How the rays of a pixel are spread over it. Every sampler cuts the pixel into the
+/// grid of `grid_size` and puts one sample in each of its cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampler {
+    /// The center of every cell, regular patterns alias on regular details
+    #[default]
+    Grid,
+    /// A random point in every cell
+    Jittered,
+    /// A random point in every cell that also falls in its own row and column
+    /// out of as many as there are samples (multi-jittered)
+    Stratified,
+    /// A grid tilted so that no two samples share a row or a column,
+    /// which handles near horizontal and vertical edges well
+    RotatedGrid
+}
+
+impl Sampler {
+    pub fn from_name(name: &str) -> Option<Sampler> {
+        match name.to_ascii_lowercase().as_str() {
+            "grid" => Some(Sampler::Grid),
+            "jittered" => Some(Sampler::Jittered),
+            "stratified" => Some(Sampler::Stratified),
+            "rotated-grid" => Some(Sampler::RotatedGrid),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sampler::Grid => "grid",
+            Sampler::Jittered => "jittered",
+            Sampler::Stratified => "stratified",
+            Sampler::RotatedGrid => "rotated-grid"
+        }
+    }
+
+    /// Returns `count` points in the unit square, one in every cell of the grid of `grid_size`,
+    /// row by row. The random ones are derived from `seed`, usually the pixel coordinates,
+    /// so each pixel gets its own pattern on every render.
+    ///
+    /// # Panics
+    /// When `count` is zero
+    pub fn points(&self, count: usize, seed: [u32; 2]) -> Vec<(f32, f32)> {
+        let (columns, rows) = grid_size(count);
+        let random = |index: usize, dimension: u32| hash_to_unit(&[seed[0], seed[1], index as u32, dimension]);
+        let (width, height) = (columns as f32, rows as f32);
+
+        let mut points = Vec::with_capacity(count);
+        for j in 0..rows {
+            for i in 0..columns {
+                let point = match self {
+                    Sampler::Grid => ((i as f32 + 0.5) / width, (j as f32 + 0.5) / height),
+                    Sampler::Jittered => {
+                        let index = j * columns + i;
+                        ((i as f32 + random(index, 0)) / width, (j as f32 + random(index, 1)) / height)
+                    }
+                    // canonical arrangement, shuffled below
+                    Sampler::Stratified => {
+                        let index = j * columns + i;
+                        (((i * rows + j) as f32 + random(index, 0)) / count as f32,
+                         ((j * columns + i) as f32 + random(index, 1)) / count as f32)
+                    }
+                    Sampler::RotatedGrid => (((i * rows + j) as f32 + 0.5) / count as f32,
+                                             ((j * columns + columns - 1 - i) as f32 + 0.5) / count as f32)
+                };
+                points.push(point);
+            }
+        }
+
+        if *self == Sampler::Stratified {
+            // swapping x within a column of cells and y within a row keeps both stratifications
+            for i in 0..columns {
+                for j in 0..rows {
+                    let other = j + (random(count + j * columns + i, 2) * (rows - j) as f32) as usize;
+                    let (a, b) = (j * columns + i, other.min(rows - 1) * columns + i);
+                    let (xa, xb) = (points[a].0, points[b].0);
+                    points[a].0 = xb;
+                    points[b].0 = xa;
+                }
+            }
+            for j in 0..rows {
+                for i in 0..columns {
+                    let other = i + (random(2 * count + i * rows + j, 3) * (columns - i) as f32) as usize;
+                    let (a, b) = (j * columns + i, j * columns + other.min(columns - 1));
+                    let (ya, yb) = (points[a].1, points[b].1);
+                    points[a].1 = yb;
+                    points[b].1 = ya;
+                }
+            }
+        }
+        points
+    }
+}
+
+/// Returns the columns and rows of the grid `count` samples are laid out on, as close to
+/// square as `count` allows: 16 gives 4x4, 8 gives 4x2 and a prime like 5 gives 5x1
+///
+/// # Panics
+/// When `count` is zero
+pub fn grid_size(count: usize) -> (usize, usize) {
+    assert!(count > 0, "a pixel needs at least one sample");
+    let rows = (1..=count).take_while(|rows| rows * rows <= count).filter(|rows| count.is_multiple_of(*rows)).last().unwrap();
+    (count / rows, rows)
+}
+
+/// Reconstruction filter weighting the samples of a pixel by their distance from its
+/// center. Samples are spread over the whole footprint of the filter, which can reach
+/// into the neighbouring pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Equal weights inside the pixel, a plain average
+    #[default]
+    Box,
+    /// Weights fall off linearly up to one pixel away
+    Tent,
+    /// Smooth and slightly blurry
+    Gaussian,
+    /// Mitchell-Netravali with B = C = 1/3, sharper thanks to small negative lobes
+    Mitchell
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Filter> {
+        match name.to_ascii_lowercase().as_str() {
+            "box" => Some(Filter::Box),
+            "tent" => Some(Filter::Tent),
+            "gaussian" => Some(Filter::Gaussian),
+            "mitchell" => Some(Filter::Mitchell),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Box => "box",
+            Filter::Tent => "tent",
+            Filter::Gaussian => "gaussian",
+            Filter::Mitchell => "mitchell"
+        }
+    }
+
+    /// Distance in pixels from the center beyond which the weight is zero
+    pub fn radius(&self) -> f32 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0
+        }
+    }
+
+    /// Weight of a sample `x` and `y` pixels away from the center of the pixel
+    pub fn weight(&self, x: f32, y: f32) -> f32 {
+        self.weight_1d(x) * self.weight_1d(y)
+    }
+
+    /// Integral of the weight along one axis from `from` to `to` pixels away from the center.
+    /// The weight is separable, so the one over a rectangle is the product of the integrals along its sides.
+    pub fn integral(&self, from: f32, to: f32) -> f32 {
+        // Simpson's rule, the weights are smooth enough for a fixed number of steps
+        const STEPS: usize = 16;
+        let step = (to - from) / STEPS as f32;
+        let sum: f32 = (0..=STEPS).map(|k| {
+            let factor = if k == 0 || k == STEPS { 1.0 } else if k % 2 == 1 { 4.0 } else { 2.0 };
+            factor * self.weight_1d(from + k as f32 * step)
+        }).sum();
+        sum * step / 3.0
+    }
+
+    fn weight_1d(&self, x: f32) -> f32 {
+        let x = x.abs();
+        let radius = self.radius();
+        if x > radius {
+            return 0.0;
+        }
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - x / radius,
+            Filter::Gaussian => {
+                // shifted down so the weight reaches zero at the radius
+                const ALPHA: f32 = 2.0;
+                (-ALPHA * x * x).exp() - (-ALPHA * radius * radius).exp()
+            }
+            Filter::Mitchell => {
+                const B: f32 = 1.0 / 3.0;
+                const C: f32 = 1.0 / 3.0;
+                let x = 2.0 * x / radius;
+                let value = if x < 1.0 {
+                    (12.0 - 9.0 * B - 6.0 * C) * x.powi(3) + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2) + (6.0 - 2.0 * B)
+                } else {
+                    (-B - 6.0 * C) * x.powi(3) + (6.0 * B + 30.0 * C) * x.powi(2) + (-12.0 * B - 48.0 * C) * x + (8.0 * B + 24.0 * C)
+                };
+                value / 6.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(value: f32, cells: usize) -> usize {
+        (value * cells as f32) as usize
+    }
+
+    #[test]
+    fn a_grid_samples_the_center_of_every_cell() {
+        let points = Sampler::Grid.points(4, [0, 0]);
+        assert_eq!(points, vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]);
+        assert_eq!(Sampler::Grid.points(1, [3, 4]), vec![(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn jittered_samples_fall_in_their_cells() {
+        let points = Sampler::Jittered.points(9, [2, 7]);
+        for (index, (x, y)) in points.iter().enumerate() {
+            assert_eq!((cell(*x, 3), cell(*y, 3)), (index % 3, index / 3));
+        }
+        assert_eq!(Sampler::Jittered.points(9, [2, 7]), points);
+        assert_ne!(Sampler::Jittered.points(9, [3, 7]), points);
+    }
+
+    #[test]
+    fn stratified_samples_fill_every_cell_row_and_column() {
+        for seed in 0..20 {
+            let points = Sampler::Stratified.points(16, [seed, 1]);
+            let mut cells: Vec<_> = points.iter().map(|(x, y)| (cell(*x, 4), cell(*y, 4))).collect();
+            cells.sort();
+            cells.dedup();
+            assert_eq!(cells.len(), 16);
+            let mut columns: Vec<_> = points.iter().map(|(x, _)| cell(*x, 16)).collect();
+            let mut rows: Vec<_> = points.iter().map(|(_, y)| cell(*y, 16)).collect();
+            columns.sort();
+            rows.sort();
+            assert_eq!(columns, (0..16).collect::<Vec<_>>());
+            assert_eq!(rows, (0..16).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn a_rotated_grid_never_repeats_a_row_or_column() {
+        let points = Sampler::RotatedGrid.points(4, [0, 0]);
+        assert_eq!(points, vec![(0.125, 0.375), (0.625, 0.125), (0.375, 0.875), (0.875, 0.625)]);
+        let points = Sampler::RotatedGrid.points(9, [0, 0]);
+        let mut columns: Vec<_> = points.iter().map(|(x, _)| cell(*x, 9)).collect();
+        columns.sort();
+        assert_eq!(columns, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn any_number_of_samples_is_laid_out_on_a_grid() {
+        assert_eq!(grid_size(1), (1, 1));
+        assert_eq!(grid_size(16), (4, 4));
+        assert_eq!(grid_size(8), (4, 2));
+        assert_eq!(grid_size(12), (4, 3));
+        assert_eq!(grid_size(7), (7, 1));
+        assert_eq!(Sampler::Grid.points(2, [0, 0]), vec![(0.25, 0.5), (0.75, 0.5)]);
+        for sampler in [Sampler::Grid, Sampler::Jittered, Sampler::Stratified, Sampler::RotatedGrid] {
+            for count in [2, 3, 6, 8, 12] {
+                let (columns, rows) = grid_size(count);
+                let points = sampler.points(count, [5, 9]);
+                let mut cells: Vec<_> = points.iter().map(|(x, y)| (cell(*y, rows), cell(*x, columns))).collect();
+                cells.sort();
+                assert_eq!(cells, (0..rows).flat_map(|j| (0..columns).map(move |i| (j, i))).collect::<Vec<_>>(),
+                           "{:?} {}", sampler, count);
+            }
+        }
+        let points = Sampler::Stratified.points(8, [1, 2]);
+        let mut columns: Vec<_> = points.iter().map(|(x, _)| cell(*x, 8)).collect();
+        columns.sort();
+        assert_eq!(columns, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one sample")]
+    fn samplers_need_at_least_one_sample() {
+        Sampler::Jittered.points(0, [0, 0]);
+    }
+
+    #[test]
+    fn filters_weigh_the_center_the_most() {
+        for filter in [Filter::Box, Filter::Tent, Filter::Gaussian, Filter::Mitchell] {
+            let center = filter.weight(0.0, 0.0);
+            assert!(center > 0.0, "{:?}", filter);
+            assert!(filter.weight(0.3, -0.1) <= center, "{:?}", filter);
+            assert_eq!(filter.weight(0.4, 0.2), filter.weight(-0.4, -0.2));
+            assert_eq!(filter.weight(filter.radius() + 0.01, 0.0), 0.0, "{:?}", filter);
+            assert_eq!(Filter::from_name(filter.name()), Some(filter));
+        }
+        assert_eq!(Filter::Box.weight(0.49, -0.3), 1.0);
+        assert_eq!(Filter::Tent.weight(0.5, 0.0), 0.5);
+        assert!(Filter::Gaussian.weight(1.5, 0.0).abs() < 1e-6);
+        assert!(Filter::Mitchell.weight(1.5, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn filter_integrals_add_up_over_the_footprint() {
+        assert!((Filter::Box.integral(-0.5, 0.5) - 1.0).abs() < 1e-5);
+        assert!((Filter::Tent.integral(-1.0, 1.0) - 1.0).abs() < 1e-5);
+        assert!((Filter::Tent.integral(0.0, 1.0) - 0.5).abs() < 1e-5);
+        // Mitchell-Netravali is normalized, the negative lobes included
+        assert!((Filter::Mitchell.integral(-2.0, 2.0) - 1.0).abs() < 1e-3);
+        assert!(Filter::Mitchell.integral(1.0, 2.0) < 0.0);
+        for filter in [Filter::Box, Filter::Tent, Filter::Gaussian, Filter::Mitchell] {
+            let radius = filter.radius();
+            let halves = filter.integral(-radius, 0.0) + filter.integral(0.0, radius);
+            assert!((filter.integral(-radius, radius) - halves).abs() < 1e-4, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn samplers_are_found_by_name() {
+        for sampler in [Sampler::Grid, Sampler::Jittered, Sampler::Stratified, Sampler::RotatedGrid] {
+            assert_eq!(Sampler::from_name(sampler.name()), Some(sampler));
+        }
+        assert_eq!(Sampler::from_name("halton"), None);
+    }
+}
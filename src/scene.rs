@@ -0,0 +1,957 @@
+use std::{collections::HashMap, fmt, fs, io, path::{Path, PathBuf}, sync::Arc};
+
+use crate::{yaml::{self, Node, Value}, world::World, camera::{Camera, Lens}, light::{PointLight, SpotLight, DirectionalLight, AreaLight},
+            matrix::Matrix, transform::Transform, tuple::Tuple, color::Color, helper::Axis, material::Material, shape::Shape, sphere::Sphere,
+            plane::Plane, cube::Cube, cylinder::Cylinder, cone::Cone, triangle::{Triangle, SmoothTriangle},
+            group::Group, csg::{Csg, CsgOperation}, obj_file::load_obj_file,
+            pattern::{Pattern, SolidPattern, StripePattern, GradientPattern, RingPattern, CheckersPattern,
+                      RadialGradientPattern, BlendedPattern}};
+
+/// How deep `add` may follow defined shapes that are built from other defined shapes
+const MAX_DEFINE_DEPTH: usize = 32;
+
+/// Everything needed to render an image described by a scene file
+#[derive(Debug)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io { path: PathBuf, error: io::Error },
+    /// The file is not valid YAML or JSON, or does not describe a scene
+    Invalid { line: usize, message: String }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            SceneError::Invalid { line, message } => write!(f, "line {}: {}", line, message)
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<yaml::ParseError> for SceneError {
+    fn from(error: yaml::ParseError) -> Self {
+        SceneError::Invalid { line: error.line, message: error.message }
+    }
+}
+
+fn invalid<T>(node: &Node, message: impl Into<String>) -> Result<T, SceneError> {
+    Err(SceneError::Invalid { line: node.line, message: message.into() })
+}
+
+/// Reads a scene file. Paths inside it, like the `file` of an `obj`, are relative to the scene file.
+pub fn load_scene_file<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .map_err(|error| SceneError::Io { path: path.to_path_buf(), error })?;
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    SceneLoader::new(base_dir).load(&source)
+}
+
+/// Parses a scene written in YAML or JSON. Relative paths are resolved from the working directory.
+///
+/// The document is a list of entries, each either adds something to the scene:
+///
+/// ```yaml
+/// - add: camera
+///   width: 100
+///   height: 50
+///   field-of-view: 1.047
+///   from: [ 0, 1.5, -5 ]
+///   to: [ 0, 1, 0 ]
+///   up: [ 0, 1, 0 ]
+///
+/// - add: light
+///   at: [ -10, 10, -10 ]
+///   intensity: [ 1, 1, 1 ]
+///
+/// - add: area-light
+///   corner: [ -1, 4, -1 ]
+///   uvec: [ 2, 0, 0 ]
+///   usteps: 4
+///   vvec: [ 0, 0, 2 ]
+///   vsteps: 4
+///   intensity: [ 1, 1, 1 ]
+///
+/// - add: sphere
+///   material: red
+///   transform:
+///     - [ scale, 0.5, 0.5, 0.5 ]
+///     - [ translate, 1.5, 0.5, -0.5 ]
+/// ```
+///
+/// or defines a named material, transform list or shape that later entries refer to,
+/// optionally extending an earlier definition:
+///
+/// ```yaml
+/// - define: red
+///   extend: shiny
+///   value:
+///     color: [ 1, 0, 0 ]
+/// ```
+///
+/// Transforms are applied in the order they are listed, and the material of a `group` or `csg`
+/// goes to the children that do not set their own. A `disk-light` takes a `center`,
+/// `normal`, `radius`, `rings` and `sectors` instead of the corner and edges of an area light.
+/// Both sample a random spot in each cell unless `jitter` is false. A `spot-light` shines
+/// from `at` along `direction`, fading out between its `inner-angle` and `outer-angle`
+/// in radians, and a `directional-light` only has a `direction` and an `intensity`.
+/// A camera with an `aperture` radius blurs what is away from its `focal-distance`,
+/// which defaults to the distance to `to`. Its `blades` and `blade-rotation` shape
+/// the aperture as a polygon instead of a disk.
+///
+/// A shape with a `transform-close` moves from its `transform` at time 0 to it at time 1,
+/// sliding and turning along the shortest way. The camera's `shutter`, `[ 0, 1 ]` unless
+/// given, is the span of time its rays are spread over, which blurs moving shapes.
+pub fn parse_scene(source: &str) -> Result<Scene, SceneError> {
+    SceneLoader::new(PathBuf::new()).load(source)
+}
+
+struct SceneLoader {
+    base_dir: PathBuf,
+    defines: HashMap<String, Node>,
+    world: World,
+    camera: Option<Camera>
+}
+
+impl SceneLoader {
+    fn new(base_dir: PathBuf) -> SceneLoader {
+        SceneLoader { base_dir, defines: HashMap::new(), world: World::new(), camera: None }
+    }
+
+    fn load(mut self, source: &str) -> Result<Scene, SceneError> {
+        let document = yaml::parse(source)?;
+        let entries = match &document.value {
+            Value::Sequence(entries) => entries,
+            _ => return invalid(&document, "a scene must be a list of 'add' and 'define' entries")
+        };
+
+        for entry in entries {
+            if let Some(name) = entry.get("define") {
+                self.define(entry, name)?;
+            } else if let Some(kind) = entry.get("add") {
+                match string(kind)? {
+                    "camera" => self.add_camera(entry)?,
+                    "light" => self.add_light(entry)?,
+                    "area-light" => self.add_area_light(entry)?,
+                    "disk-light" => self.add_disk_light(entry)?,
+                    "spot-light" => self.add_spot_light(entry)?,
+                    "directional-light" => self.add_directional_light(entry)?,
+                    _ => {
+                        let shape = self.shape(entry, 0)?;
+                        self.world.add_object(shape);
+                    }
+                }
+            } else {
+                return invalid(entry, "expected an 'add' or 'define' entry");
+            }
+        }
+
+        match self.camera {
+            Some(camera) => Ok(Scene { world: self.world, camera }),
+            None => invalid(&document, "the scene has no camera")
+        }
+    }
+
+    fn define(&mut self, entry: &Node, name: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["define", "extend", "value"])?;
+        let name = string(name)?.to_string();
+        let value = required(entry, "value")?;
+        let value = match entry.get("extend") {
+            Some(parent) => extend(self.lookup(parent)?, value)?,
+            None => value.clone()
+        };
+        self.defines.insert(name, value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Node) -> Result<&Node, SceneError> {
+        let key = string(name)?;
+        match self.defines.get(key) {
+            Some(value) => Ok(value),
+            None => invalid(name, format!("'{}' is not defined", key))
+        }
+    }
+
+    fn add_camera(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "width", "height", "field-of-view", "from", "to", "up",
+                            "aperture", "focal-distance", "blades", "blade-rotation", "shutter"])?;
+        if self.camera.is_some() {
+            return invalid(entry, "the scene already has a camera");
+        }
+        let mut camera = Camera::new(size(required(entry, "width")?)?,
+                                     size(required(entry, "height")?)?,
+                                     number(required(entry, "field-of-view")?)?);
+        let from = point(required(entry, "from")?)?;
+        let to = point(required(entry, "to")?)?;
+        let up = vector(required(entry, "up")?)?;
+        let view = Matrix::view_transform(&from, &to, &up);
+        if !view.is_invertible() {
+            return invalid(entry, "the camera needs 'to' away from 'from' and 'up' not along the line between them");
+        }
+        camera.set_transform(view);
+        camera.set_lens(lens(entry, (&to - &from).magnitude())?);
+        if let Some(node) = entry.get("shutter") {
+            let [open, close] = pair(node)?;
+            let (open, close) = (number(open)?, number(close)?);
+            if open > close {
+                return invalid(node, "the shutter has to open before it closes");
+            }
+            camera.set_shutter(open, close);
+        }
+        self.camera = Some(camera);
+        Ok(())
+    }
+
+    fn add_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "at", "intensity"])?;
+        let position = point(required(entry, "at")?)?;
+        let intensity = color(required(entry, "intensity")?)?;
+        self.world.add_light(PointLight::new(position, intensity));
+        Ok(())
+    }
+
+    fn add_spot_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "at", "direction", "inner-angle", "outer-angle", "intensity"])?;
+        let position = point(required(entry, "at")?)?;
+        let direction = direction(required(entry, "direction")?)?;
+        let inner_angle = number(required(entry, "inner-angle")?)?;
+        let outer_node = required(entry, "outer-angle")?;
+        let outer_angle = number(outer_node)?;
+        if outer_angle < inner_angle {
+            return invalid(outer_node, "the outer angle cannot be smaller than the inner angle");
+        }
+        let intensity = color(required(entry, "intensity")?)?;
+        self.world.add_light(SpotLight::new(position, direction, inner_angle, outer_angle, intensity));
+        Ok(())
+    }
+
+    fn add_directional_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "direction", "intensity"])?;
+        let direction = direction(required(entry, "direction")?)?;
+        let intensity = color(required(entry, "intensity")?)?;
+        self.world.add_light(DirectionalLight::new(direction, intensity));
+        Ok(())
+    }
+
+    fn add_area_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "corner", "uvec", "usteps", "vvec", "vsteps", "intensity", "jitter"])?;
+        let mut light = AreaLight::new_rectangle(point(required(entry, "corner")?)?,
+                                                 vector(required(entry, "uvec")?)?, size(required(entry, "usteps")?)?,
+                                                 vector(required(entry, "vvec")?)?, size(required(entry, "vsteps")?)?,
+                                                 color(required(entry, "intensity")?)?);
+        light.jitter = optional(entry, "jitter", boolean)?.unwrap_or(true);
+        self.world.add_light(light);
+        Ok(())
+    }
+
+    fn add_disk_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        check_keys(entry, &["add", "center", "normal", "radius", "rings", "sectors", "intensity", "jitter"])?;
+        let normal = direction(required(entry, "normal")?)?;
+        let mut light = AreaLight::new_disk(point(required(entry, "center")?)?, normal, number(required(entry, "radius")?)?,
+                                            size(required(entry, "rings")?)?, size(required(entry, "sectors")?)?,
+                                            color(required(entry, "intensity")?)?);
+        light.jitter = optional(entry, "jitter", boolean)?.unwrap_or(true);
+        self.world.add_light(light);
+        Ok(())
+    }
+
+    fn shape(&self, entry: &Node, depth: usize) -> Result<Box<dyn Shape>, SceneError> {
+        let kind_node = required(entry, "add")?;
+        let kind = string(kind_node)?;
+
+        let mut shape: Box<dyn Shape> = match kind {
+            "sphere" | "plane" | "cube" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close"])?;
+                match kind {
+                    "sphere" => Box::new(Sphere::new()),
+                    "plane" => Box::new(Plane::new()),
+                    _ => Box::new(Cube::new())
+                }
+            }
+            "cylinder" | "cone" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "min", "max", "closed"])?;
+                let minimum = optional(entry, "min", number)?.unwrap_or(f32::NEG_INFINITY);
+                let maximum = optional(entry, "max", number)?.unwrap_or(f32::INFINITY);
+                let closed = optional(entry, "closed", boolean)?.unwrap_or(false);
+                match kind {
+                    "cylinder" => Box::new(Cylinder::new_truncated(minimum, maximum, closed)),
+                    _ => Box::new(Cone::new_truncated(minimum, maximum, closed))
+                }
+            }
+            "triangle" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "p1", "p2", "p3"])?;
+                Box::new(Triangle::new(point(required(entry, "p1")?)?,
+                                       point(required(entry, "p2")?)?,
+                                       point(required(entry, "p3")?)?))
+            }
+            "smooth-triangle" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "p1", "p2", "p3", "n1", "n2", "n3"])?;
+                Box::new(SmoothTriangle::new(point(required(entry, "p1")?)?,
+                                             point(required(entry, "p2")?)?,
+                                             point(required(entry, "p3")?)?,
+                                             vector(required(entry, "n1")?)?,
+                                             vector(required(entry, "n2")?)?,
+                                             vector(required(entry, "n3")?)?))
+            }
+            "group" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "children"])?;
+                let mut group = Group::new();
+                for child in sequence(required(entry, "children")?)? {
+                    group.add_child(self.shape(child, depth)?);
+                }
+                Box::new(group)
+            }
+            "csg" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "operation", "left", "right"])?;
+                let operation_node = required(entry, "operation")?;
+                let operation = match string(operation_node)? {
+                    "union" => CsgOperation::Union,
+                    "intersection" => CsgOperation::Intersection,
+                    "difference" => CsgOperation::Difference,
+                    other => return invalid(operation_node, format!("unknown csg operation '{}'", other))
+                };
+                Box::new(Csg::new(operation, self.shape(required(entry, "left")?, depth)?,
+                                  self.shape(required(entry, "right")?, depth)?))
+            }
+            "obj" => {
+                check_keys(entry, &["add", "material", "transform", "transform-close", "file"])?;
+                let file_node = required(entry, "file")?;
+                let path = self.base_dir.join(string(file_node)?);
+                match load_obj_file(&path) {
+                    Ok(obj) => Box::new(obj.into_group()),
+                    Err(error) => return invalid(file_node, format!("cannot read {}: {}", path.display(), error))
+                }
+            }
+            _ => {
+                // a defined shape, the keys of the entry override the ones of the definition
+                let template = match self.defines.get(kind) {
+                    Some(template) if template.get("add").is_some() => template,
+                    _ => return invalid(kind_node, format!("unknown shape '{}'", kind))
+                };
+                if depth >= MAX_DEFINE_DEPTH {
+                    return invalid(kind_node, format!("'{}' refers to itself", kind));
+                }
+                let mut overrides = entry.clone();
+                if let Value::Mapping(entries) = &mut overrides.value {
+                    entries.retain(|(key, _)| key != "add");
+                }
+                let merged = Node::new(extend(template, &overrides)?.value, entry.line);
+                return self.shape(&merged, depth + 1);
+            }
+        };
+
+        if let Some(material) = entry.get("material") {
+            shape.set_material(self.material(material)?);
+        }
+        let open = match entry.get("transform") {
+            Some(transform) => self.transform(transform)?,
+            None => Matrix::new_identity_matrix()
+        };
+        match entry.get("transform-close") {
+            Some(node) => {
+                let close = self.transform(node)?;
+                if open.determinant().signum() != close.determinant().signum() {
+                    return invalid(node, "a shape cannot move between a mirrored and an unmirrored transform");
+                }
+                shape.set_motion(open, close);
+            }
+            None if entry.get("transform").is_some() => shape.set_transform(open),
+            None => {}
+        }
+        Ok(shape)
+    }
+
+    /// Composes a list of transforms, each either `[ operation, arguments... ]`
+    /// or the name of a defined list. The first one listed is applied first.
+    fn transform(&self, node: &Node) -> Result<Matrix, SceneError> {
+        let mut result = Transform::identity();
+        for item in sequence(node)? {
+            let matrix = match &item.value {
+                Value::String(_) => self.transform(self.lookup(item)?)?,
+                Value::Sequence(parts) if !parts.is_empty() => {
+                    let operation = string(&parts[0])?;
+                    let arguments = parts[1..].iter().map(number).collect::<Result<Vec<f32>, _>>()?;
+                    let expected = match operation {
+                        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+                        "translate" | "scale" => 3,
+                        "shear" => 6,
+                        _ => return invalid(item, format!("unknown transform '{}'", operation))
+                    };
+                    if arguments.len() != expected {
+                        return invalid(item, format!("'{}' takes {} numbers, found {}", operation, expected, arguments.len()));
+                    }
+                    match operation {
+                        "rotate-x" => Matrix::rotation(Axis::X, arguments[0]),
+                        "rotate-y" => Matrix::rotation(Axis::Y, arguments[0]),
+                        "rotate-z" => Matrix::rotation(Axis::Z, arguments[0]),
+                        "translate" => Matrix::translation(arguments[0], arguments[1], arguments[2]),
+                        "scale" => Matrix::scaling(arguments[0], arguments[1], arguments[2]),
+                        _ => Matrix::shearing(arguments[0], arguments[1], arguments[2],
+                                              arguments[3], arguments[4], arguments[5])
+                    }
+                }
+                _ => return invalid(item, "expected [ operation, arguments... ] or the name of a defined transform")
+            };
+            if !matrix.is_invertible() {
+                return invalid(item, "the transform is degenerate and cannot be inverted, check for a zero scale");
+            }
+            result = result.then(&matrix);
+        }
+        if !result.matrix().is_invertible() {
+            return invalid(node, "the transforms combine into one that cannot be inverted");
+        }
+        Ok(result.matrix())
+    }
+
+    fn material(&self, node: &Node) -> Result<Material, SceneError> {
+        if let Value::String(_) = node.value {
+            return self.material(self.lookup(node)?);
+        }
+        check_keys(node, &["color", "pattern", "ambient", "diffuse", "specular", "shininess",
+                           "reflective", "transparency", "refractive-index"])?;
+        let mut material = Material::default();
+        if let Some(value) = optional(node, "color", color)? {
+            material.color = value;
+        }
+        if let Some(pattern) = node.get("pattern") {
+            material.pattern = Some(Arc::from(self.pattern(pattern)?));
+        }
+        let fields: [(&str, &mut f32); 7] = [
+            ("ambient", &mut material.ambient),
+            ("diffuse", &mut material.diffuse),
+            ("specular", &mut material.specular),
+            ("shininess", &mut material.shininess),
+            ("reflective", &mut material.reflective),
+            ("transparency", &mut material.transparency),
+            ("refractive-index", &mut material.refractive_index)
+        ];
+        for (key, field) in fields {
+            if let Some(value) = optional(node, key, number)? {
+                *field = value;
+            }
+        }
+        Ok(material)
+    }
+
+    /// A pattern is a mapping with a `type` and either two `colors` or two nested `patterns`,
+    /// a bare color is a solid pattern
+    fn pattern(&self, node: &Node) -> Result<Box<dyn Pattern>, SceneError> {
+        if let Value::Sequence(_) = node.value {
+            return Ok(Box::new(SolidPattern::new(color(node)?)));
+        }
+        check_keys(node, &["type", "colors", "patterns", "transform"])?;
+        let (a, b): (Box<dyn Pattern>, Box<dyn Pattern>) = match (node.get("colors"), node.get("patterns")) {
+            (Some(colors), None) => {
+                let [a, b] = pair(colors)?;
+                (Box::new(SolidPattern::new(color(a)?)), Box::new(SolidPattern::new(color(b)?)))
+            }
+            (None, Some(patterns)) => {
+                let [a, b] = pair(patterns)?;
+                (self.pattern(a)?, self.pattern(b)?)
+            }
+            _ => return invalid(node, "a pattern needs either 'colors' or 'patterns'")
+        };
+
+        let kind_node = required(node, "type")?;
+        let mut pattern: Box<dyn Pattern> = match string(kind_node)? {
+            "stripes" => Box::new(StripePattern::new_nested(a, b)),
+            "gradient" => Box::new(GradientPattern::new_nested(a, b)),
+            "rings" => Box::new(RingPattern::new_nested(a, b)),
+            "checkers" => Box::new(CheckersPattern::new_nested(a, b)),
+            "radial-gradient" => Box::new(RadialGradientPattern::new_nested(a, b)),
+            "blended" => Box::new(BlendedPattern::new(a, b)),
+            other => return invalid(kind_node, format!("unknown pattern '{}'", other))
+        };
+        if let Some(transform) = node.get("transform") {
+            pattern.set_transform(self.transform(transform)?);
+        }
+        Ok(pattern)
+    }
+}
+
+/// Combines a definition with the node extending it: mapping keys of the child
+/// replace the ones of the parent, lists are appended to the parent's list
+fn extend(parent: &Node, child: &Node) -> Result<Node, SceneError> {
+    match (&parent.value, &child.value) {
+        (Value::Mapping(parent_entries), Value::Mapping(child_entries)) => {
+            let mut entries: Vec<(String, Node)> = parent_entries.iter()
+                .filter(|(key, _)| child.get(key).is_none())
+                .cloned()
+                .collect();
+            entries.extend(child_entries.iter().cloned());
+            Ok(Node::new(Value::Mapping(entries), child.line))
+        }
+        (Value::Sequence(parent_items), Value::Sequence(child_items)) => {
+            let items = parent_items.iter().chain(child_items.iter()).cloned().collect();
+            Ok(Node::new(Value::Sequence(items), child.line))
+        }
+        _ => invalid(child, "can only extend a definition of the same kind, a mapping or a list")
+    }
+}
+
+fn check_keys(node: &Node, allowed: &[&str]) -> Result<(), SceneError> {
+    match &node.value {
+        Value::Mapping(entries) => {
+            for (key, value) in entries {
+                if !allowed.contains(&key.as_str()) {
+                    return invalid(value, format!("unknown key '{}'", key));
+                }
+            }
+            Ok(())
+        }
+        _ => invalid(node, "expected a mapping")
+    }
+}
+
+fn required<'a>(node: &'a Node, key: &str) -> Result<&'a Node, SceneError> {
+    match node.get(key) {
+        Some(value) => Ok(value),
+        None => invalid(node, format!("missing '{}'", key))
+    }
+}
+
+/// The lens of a camera, focused on its 'to' point unless told otherwise
+fn lens(entry: &Node, distance_to_target: f32) -> Result<Lens, SceneError> {
+    let mut lens = Lens { focal_distance: distance_to_target, ..Lens::default() };
+    if let Some(node) = entry.get("aperture") {
+        lens.aperture = number(node)?;
+        if lens.aperture < 0.0 {
+            return invalid(node, "the aperture cannot be negative");
+        }
+    }
+    if let Some(node) = entry.get("focal-distance") {
+        lens.focal_distance = number(node)?;
+        if lens.focal_distance <= 0.0 {
+            return invalid(node, "the focal distance must be positive");
+        }
+    }
+    if let Some(node) = entry.get("blades") {
+        lens.blades = size(node)?;
+        if lens.blades < 3 {
+            return invalid(node, "an aperture needs at least 3 blades");
+        }
+    }
+    lens.blade_rotation = optional(entry, "blade-rotation", number)?.unwrap_or(0.0);
+    Ok(lens)
+}
+
+fn optional<T>(node: &Node, key: &str, convert: fn(&Node) -> Result<T, SceneError>) -> Result<Option<T>, SceneError> {
+    node.get(key).map(convert).transpose()
+}
+
+fn string(node: &Node) -> Result<&str, SceneError> {
+    match &node.value {
+        Value::String(value) => Ok(value),
+        _ => invalid(node, "expected a name")
+    }
+}
+
+fn number(node: &Node) -> Result<f32, SceneError> {
+    match node.value {
+        Value::Number(value) => Ok(value),
+        _ => invalid(node, "expected a number")
+    }
+}
+
+fn boolean(node: &Node) -> Result<bool, SceneError> {
+    match node.value {
+        Value::Bool(value) => Ok(value),
+        _ => invalid(node, "expected true or false")
+    }
+}
+
+fn size(node: &Node) -> Result<usize, SceneError> {
+    match node.value {
+        Value::Number(value) if value >= 1.0 && value.fract() == 0.0 => Ok(value as usize),
+        _ => invalid(node, "expected a positive whole number")
+    }
+}
+
+fn sequence(node: &Node) -> Result<&[Node], SceneError> {
+    match &node.value {
+        Value::Sequence(items) => Ok(items),
+        _ => invalid(node, "expected a list")
+    }
+}
+
+fn pair(node: &Node) -> Result<[&Node; 2], SceneError> {
+    match sequence(node)? {
+        [a, b] => Ok([a, b]),
+        _ => invalid(node, "expected a list of two")
+    }
+}
+
+fn triple(node: &Node) -> Result<(f32, f32, f32), SceneError> {
+    match sequence(node)? {
+        [x, y, z] => Ok((number(x)?, number(y)?, number(z)?)),
+        _ => invalid(node, "expected a list of three numbers")
+    }
+}
+
+fn point(node: &Node) -> Result<Tuple, SceneError> {
+    let (x, y, z) = triple(node)?;
+    Ok(Tuple::new_point(x, y, z))
+}
+
+/// A vector that is normalized later, so it cannot be zero
+fn direction(node: &Node) -> Result<Tuple, SceneError> {
+    let vector = vector(node)?;
+    if vector.magnitude() == 0.0 {
+        return invalid(node, "expected a direction, not a zero vector");
+    }
+    Ok(vector)
+}
+
+fn vector(node: &Node) -> Result<Tuple, SceneError> {
+    let (x, y, z) = triple(node)?;
+    Ok(Tuple::new_vector(x, y, z))
+}
+
+fn color(node: &Node) -> Result<Color, SceneError> {
+    let (red, green, blue) = triple(node)?;
+    Ok(Color::new(red, green, blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{world::tests::assert_color_near, light::Light, ray::Ray};
+
+    use super::*;
+
+    const CAMERA: &str = "\
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+";
+
+    fn scene(entries: &str) -> Result<Scene, SceneError> {
+        parse_scene(&format!("{}{}", CAMERA, entries))
+    }
+
+    fn error_line(result: Result<Scene, SceneError>) -> usize {
+        match result {
+            Err(SceneError::Invalid { line, .. }) => line,
+            other => panic!("expected an invalid scene, found {:?}", other)
+        }
+    }
+
+    #[test]
+    fn loading_the_camera_and_lights() {
+        let scene = scene("- add: light\n  at: [ -10, 10, -10 ]\n  intensity: [ 1, 0.5, 1 ]\n").unwrap();
+        assert_eq!(scene.camera.hsize, 100);
+        assert_eq!(scene.camera.vsize, 50);
+        assert_eq!(scene.camera.field_of_view, 0.785);
+        let expected = Matrix::view_transform(&Tuple::new_point(0.0, 0.0, -5.0), &Tuple::new_point(0.0, 0.0, 0.0),
+                                              &Tuple::new_vector(0.0, 1.0, 0.0));
+        assert_eq!(scene.camera.transform(), &expected);
+        let origin = Tuple::new_point(0.0, 0.0, 0.0);
+        assert_eq!(scene.world.lights.len(), 1);
+        assert_eq!(scene.world.lights[0].samples(&origin),
+                   PointLight::new(Tuple::new_point(-10.0, 10.0, -10.0), Color::new(1.0, 0.5, 1.0)).samples(&origin));
+    }
+
+    #[test]
+    fn loading_spot_and_directional_lights() {
+        let loaded = scene("\
+- add: spot-light
+  at: [ 0, 10, 0 ]
+  direction: [ 0, -1, 0 ]
+  inner-angle: 0.3
+  outer-angle: 0.5
+  intensity: [ 1, 1, 1 ]
+- add: directional-light
+  direction: [ 0, 0, 2 ]
+  intensity: [ 0.5, 0.5, 0.5 ]
+").unwrap();
+        let origin = Tuple::new_point(0.0, 0.0, 0.0);
+        let expected = SpotLight::new(Tuple::new_point(0.0, 10.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0), 0.3, 0.5, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(loaded.world.lights[0].samples(&origin), expected.samples(&origin));
+        assert_eq!(loaded.world.lights[1].direction_from(&origin), Tuple::new_vector(0.0, 0.0, -1.0));
+        assert_eq!(loaded.world.lights[1].distance_from(&origin), f32::INFINITY);
+        let spot = "- add: spot-light\n  at: [ 0, 10, 0 ]\n  direction: [ 0, -1, 0 ]\n  inner-angle: 0.5\n  outer-angle: 0.3\n";
+        assert_eq!(error_line(scene(spot)), 12);
+        assert_eq!(error_line(scene("- add: directional-light\n  direction: [ 0, 0, 0 ]\n")), 9);
+    }
+
+    #[test]
+    fn loading_area_lights() {
+        let loaded = scene("\
+- add: area-light
+  corner: [ -1, 4, -1 ]
+  uvec: [ 2, 0, 0 ]
+  usteps: 4
+  vvec: [ 0, 0, 2 ]
+  vsteps: 2
+  jitter: false
+  intensity: [ 1, 1, 1 ]
+- add: disk-light
+  center: [ 0, 5, 0 ]
+  normal: [ 0, -1, 0 ]
+  radius: 0.5
+  rings: 2
+  sectors: 6
+  intensity: [ 0.5, 0.5, 0.5 ]
+").unwrap();
+        let mut expected = AreaLight::new_rectangle(Tuple::new_point(-1.0, 4.0, -1.0), Tuple::new_vector(2.0, 0.0, 0.0), 4,
+                                                    Tuple::new_vector(0.0, 0.0, 2.0), 2, Color::new(1.0, 1.0, 1.0));
+        expected.jitter = false;
+        let origin = Tuple::new_point(0.0, 0.0, 0.0);
+        assert_eq!(loaded.world.lights[0].samples(&origin), expected.samples(&origin));
+        assert_eq!(loaded.world.lights[1].samples(&origin).len(), 12);
+        assert_eq!(loaded.world.lights[1].direction_from(&origin), Tuple::new_vector(0.0, 1.0, 0.0));
+        assert_eq!(error_line(scene("- add: disk-light\n  center: [ 0, 5, 0 ]\n  normal: [ 0, 0, 0 ]\n")), 10);
+    }
+
+    #[test]
+    fn transforms_are_applied_in_the_order_listed() {
+        let scene = scene("\
+- add: sphere
+  transform:
+    - [ rotate-x, 1.5707964 ]
+    - [ scale, 5, 5, 5 ]
+    - [ translate, 10, 5, 7 ]
+").unwrap();
+        let expected = Matrix::translation(10.0, 5.0, 7.0) * Matrix::scaling(5.0, 5.0, 5.0)
+            * Matrix::rotation(Axis::X, PI / 2.0);
+        assert_eq!(scene.world.objects[0].transform(), &expected);
+    }
+
+    #[test]
+    fn materials_and_transforms_can_be_defined_and_extended() {
+        let scene = scene("\
+- define: white
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+    reflective: 0.1
+- define: blue
+  extend: white
+  value:
+    color: [ 0.5, 0.8, 0.9 ]
+- define: standard
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+- add: cube
+  material: blue
+  transform:
+    - standard
+    - [ translate, 4, 0, 0 ]
+").unwrap();
+        let material = scene.world.objects[0].material();
+        assert_eq!(material.color, Color::new(0.5, 0.8, 0.9));
+        assert_eq!(material.diffuse, 0.7);
+        assert_eq!(material.reflective, 0.1);
+        let expected = Matrix::translation(4.0, 0.0, 0.0) * Matrix::scaling(0.5, 0.5, 0.5)
+            * Matrix::translation(1.0, -1.0, 1.0);
+        assert_eq!(scene.world.objects[0].transform(), &expected);
+    }
+
+    #[test]
+    fn loading_every_kind_of_shape() {
+        let scene = scene("\
+- add: plane
+- add: cylinder
+  min: -1
+  max: 1
+  closed: true
+- add: cone
+- add: triangle
+  p1: [ 0, 1, 0 ]
+  p2: [ -1, 0, 0 ]
+  p3: [ 1, 0, 0 ]
+- add: group
+  transform:
+    - [ translate, 0, 2, 0 ]
+  children:
+    - add: sphere
+    - add: csg
+      operation: difference
+      left:
+        add: cube
+      right:
+        add: sphere
+        transform:
+          - [ scale, 0.5, 0.5, 0.5 ]
+").unwrap();
+        assert_eq!(scene.world.objects.len(), 5);
+        let r = crate::ray::Ray::new(Tuple::new_point(0.0, 2.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let ts: Vec<f32> = scene.world.objects[4].intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 4.0, 4.5, 5.5, 6.0, 6.0]);
+    }
+
+    #[test]
+    fn a_group_material_only_goes_to_children_without_their_own() {
+        let scene = scene("\
+- add: group
+  material:
+    color: [ 1, 0, 0 ]
+  children:
+    - add: sphere
+    - add: sphere
+      transform:
+        - [ translate, 3, 0, 0 ]
+      material:
+        color: [ 0, 0, 1 ]
+    - add: group
+      transform:
+        - [ translate, 6, 0, 0 ]
+      children:
+        - add: cube
+        - add: cube
+          transform:
+            - [ translate, 3, 0, 0 ]
+          material:
+            color: [ 0, 1, 0 ]
+").unwrap();
+        let color_at = |x: f32| {
+            let r = crate::ray::Ray::new(Tuple::new_point(x, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+            scene.world.objects[0].intersect(&r)[0].object.material().color
+        };
+        assert_eq!(color_at(0.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(color_at(3.0), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(color_at(6.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(color_at(9.0), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn defined_shapes_can_be_added_with_overrides() {
+        let scene = scene("\
+- define: pedestal
+  value:
+    add: cylinder
+    min: 0
+    max: 1
+    material:
+      color: [ 1, 0, 0 ]
+- add: pedestal
+  closed: true
+").unwrap();
+        let material = scene.world.objects[0].material();
+        assert_eq!(material.color, Color::new(1.0, 0.0, 0.0));
+        let r = crate::ray::Ray::new(Tuple::new_point(0.0, 2.0, 0.0), Tuple::new_vector(0.0, -1.0, 0.0));
+        assert_eq!(scene.world.objects[0].intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn materials_with_nested_patterns() {
+        let scene = scene("\
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      patterns:
+        - [ 1, 1, 1 ]
+        - type: stripes
+          colors: [ [ 1, 0, 0 ], [ 0, 0, 1 ] ]
+          transform:
+            - [ scale, 0.5, 0.5, 0.5 ]
+").unwrap();
+        let object = scene.world.objects[0].as_ref();
+        let pattern = object.material().pattern.as_ref().unwrap();
+        assert_color_near(pattern.pattern_at_shape(object, &Tuple::new_point(0.5, 0.0, 0.5), 0.0), Color::new(1.0, 1.0, 1.0));
+        assert_color_near(pattern.pattern_at_shape(object, &Tuple::new_point(1.25, 0.0, 0.5), 0.0), Color::new(1.0, 0.0, 0.0));
+        assert_color_near(pattern.pattern_at_shape(object, &Tuple::new_point(1.75, 0.0, 0.5), 0.0), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn loading_a_json_scene() {
+        let scene = parse_scene(r#"[
+            {"add": "camera", "width": 20, "height": 10, "field-of-view": 1.0,
+             "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0]},
+            {"add": "light", "at": [0, 10, 0], "intensity": [1, 1, 1]},
+            {"add": "sphere", "material": {"color": [1, 0, 0]}, "transform": [["scale", 2, 2, 2]]}
+        ]"#).unwrap();
+        assert_eq!(scene.camera.hsize, 20);
+        assert_eq!(scene.world.objects[0].transform(), &Matrix::scaling(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        assert_eq!(error_line(scene("- add: sphere\n  material: missing\n")), 9);
+        assert_eq!(error_line(scene("- add: sphere\n  transform:\n    - [ spin, 1 ]\n")), 10);
+        assert_eq!(error_line(scene("- add: sphere\n  transform:\n    - [ scale, 1, 2 ]\n")), 10);
+        assert_eq!(error_line(scene("- add: teapot\n")), 8);
+        assert_eq!(error_line(scene("- add: sphere\n  transform:\n    - [ translate, 1, 2, 3 ]\n    - [ scale, 1, 0, 1 ]\n")), 11);
+        assert_eq!(error_line(scene("- add: cube\n  colour: [ 1, 0, 0 ]\n")), 9);
+        assert_eq!(error_line(scene("- add: light\n  at: [ 1, 2 ]\n")), 9);
+        assert_eq!(error_line(parse_scene("- add: light\n  at: [ 0, 0, 0 ]\n  intensity: [ 1, 1, 1 ]\n")), 1);
+    }
+
+    #[test]
+    fn a_camera_looking_at_its_own_position_is_rejected() {
+        let result = parse_scene("- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 1, 0 ]\n  to: [ 0, 1, 0 ]\n  up: [ 0, 1, 0 ]\n");
+        assert_eq!(error_line(result), 1);
+        let result = parse_scene("- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 0, 0 ]\n  to: [ 0, 1, 0 ]\n  up: [ 0, 1, 0 ]\n");
+        assert_eq!(error_line(result), 1);
+    }
+
+    #[test]
+    fn loading_a_camera_with_a_lens() {
+        let camera = "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 0, -5 ]\n  to: [ 0, 0, -1 ]\n  up: [ 0, 1, 0 ]\n";
+        let loaded = parse_scene(&format!("{}  aperture: 0.1\n", camera)).unwrap();
+        assert_eq!(loaded.camera.lens(), &Lens { aperture: 0.1, focal_distance: 4.0, ..Lens::default() });
+        let loaded = parse_scene(&format!("{}  aperture: 0.2\n  focal-distance: 6\n  blades: 6\n  blade-rotation: 0.5\n", camera)).unwrap();
+        assert_eq!(loaded.camera.lens(), &Lens { aperture: 0.2, focal_distance: 6.0, blades: 6, blade_rotation: 0.5 });
+        assert!(parse_scene(camera).unwrap().camera.lens().is_pinhole());
+        assert_eq!(error_line(parse_scene(&format!("{}  aperture: -1\n", camera))), 8);
+        assert_eq!(error_line(parse_scene(&format!("{}  focal-distance: 0\n", camera))), 8);
+        assert_eq!(error_line(parse_scene(&format!("{}  blades: 2\n", camera))), 8);
+    }
+
+    #[test]
+    fn loading_a_moving_shape_and_the_shutter() {
+        let camera = "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 0, -5 ]\n  to: [ 0, 0, 0 ]\n  up: [ 0, 1, 0 ]\n";
+        let loaded = parse_scene(&format!("{}  shutter: [ 0.25, 0.5 ]
+- add: sphere
+  transform:
+    - [ translate, 1, 0, 0 ]
+  transform-close:
+    - [ scale, 2, 2, 2 ]
+    - [ translate, 3, 0, 0 ]
+- add: group
+  transform-close:
+    - [ translate, 0, 1, 0 ]
+  children:
+    - add: cube
+", camera)).unwrap();
+        assert_eq!(loaded.camera.shutter(), (0.25, 0.5));
+        let sphere = loaded.world.objects[0].transformation();
+        assert_eq!(sphere.matrix, Matrix::translation(1.0, 0.0, 0.0));
+        assert_eq!(sphere.close(), Matrix::translation(3.0, 0.0, 0.0) * Matrix::scaling(2.0, 2.0, 2.0));
+        let cube = loaded.world.objects[1].as_ref();
+        let r = Ray::new_with_time(Tuple::new_point(0.0, 1.5, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), 1.0);
+        assert_eq!(cube.intersect(&r).len(), 2);
+        assert!(cube.intersect(&Ray { time: 0.0, ..r }).is_empty());
+        assert_eq!(parse_scene(camera).unwrap().camera.shutter(), (0.0, 1.0));
+
+        assert_eq!(error_line(parse_scene(&format!("{}  shutter: [ 1, 0 ]\n", camera))), 8);
+        let mirrored = "- add: sphere\n  transform-close:\n    - [ scale, -1, 1, 1 ]\n";
+        assert_eq!(error_line(parse_scene(&format!("{}{}", camera, mirrored))), 10);
+    }
+
+    #[test]
+    fn a_defined_shape_cannot_refer_to_itself() {
+        let result = scene("- define: loop\n  value:\n    add: loop\n- add: loop\n");
+        assert!(matches!(result, Err(SceneError::Invalid { .. })));
+    }
+
+    #[test]
+    fn reading_a_missing_file_is_an_io_error() {
+        let result = load_scene_file("does/not/exist.yml");
+        assert!(matches!(result, Err(SceneError::Io { .. })));
+    }
+}
@@ -0,0 +1,405 @@
+use std::{fmt::Debug, borrow::Cow};
+
+use crate::{matrix::{Matrix, SingularMatrix}, tuple::Tuple, ray::Ray, intersection::Intersection, material::Material,
+            bounds::BoundingBox, motion::{Motion, Layer}};
+
+/// A transformation matrix together with its inverse and inverse-transpose,
+/// so they are computed once when the transform is set instead of per ray.
+/// A moving transform also holds its motion, `matrix` being where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedTransform {
+    pub matrix: Matrix,
+    pub inverse: Matrix,
+    pub inverse_transpose: Matrix,
+    pub motion: Option<Box<Motion>>
+}
+
+impl CachedTransform {
+    /// # Panics
+    /// When the matrix is singular, `try_new` handles matrices that may be
+    pub fn new(matrix: Matrix) -> CachedTransform {
+        CachedTransform::try_new(matrix).expect("a transform must be invertible")
+    }
+
+    /// Returns the cached transform, or an error when the matrix has no inverse
+    pub fn try_new(matrix: Matrix) -> Result<CachedTransform, SingularMatrix> {
+        let inverse = matrix.try_inverse().ok_or(SingularMatrix)?;
+        let inverse_transpose = inverse.transpose();
+        Ok(CachedTransform { matrix, inverse, inverse_transpose, motion: None })
+    }
+
+    /// A transform going from `open` at time 0 to `close` at time 1, see `Motion`
+    pub fn new_moving(open: Matrix, close: Matrix) -> CachedTransform {
+        CachedTransform { motion: Some(Box::new(Motion::new(open, close))), ..CachedTransform::new(open) }
+    }
+
+    /// The transform once the motion is over, the same as `matrix` when there is none
+    pub fn close(&self) -> Matrix {
+        self.motion.as_ref().map_or(self.matrix, |motion| *motion.close())
+    }
+
+    /// The transform of `inner` placed inside this one. A static outer transform is merged
+    /// into the first layer of a moving `inner`, a moving one is kept as layers of its own
+    /// in front of it so that `relative_to` can take them off again.
+    pub fn then(&self, inner: &CachedTransform) -> CachedTransform {
+        let mut layers = self.layers();
+        match (self.motion.is_none(), inner.layers().as_slice()) {
+            (true, [Layer::Fixed(first), rest @ ..]) => {
+                layers = vec![Layer::Fixed(self.matrix * *first)];
+                layers.extend_from_slice(rest);
+            }
+            (_, inner_layers) => layers.extend_from_slice(inner_layers)
+        }
+        CachedTransform::from_layers(layers)
+    }
+
+    /// The part of this transform inside `parent`, undoing what `then` did
+    ///
+    /// # Panics
+    /// When the parent moves and this transform does not start with its motion
+    pub fn relative_to(&self, parent: &CachedTransform) -> CachedTransform {
+        match &parent.motion {
+            None => CachedTransform::new(parent.inverse).then(self),
+            Some(motion) => {
+                let layers = self.layers();
+                let rest = layers.strip_prefix(motion.layers())
+                    .expect("a child of a moving parent must take part in its motion");
+                CachedTransform::from_layers(rest.to_vec())
+            }
+        }
+    }
+
+    fn layers(&self) -> Vec<Layer> {
+        match &self.motion {
+            Some(motion) => motion.layers().to_vec(),
+            None => vec![Layer::Fixed(self.matrix)]
+        }
+    }
+
+    fn from_layers(layers: Vec<Layer>) -> CachedTransform {
+        let motion = Motion::from_layers(layers);
+        let transformation = CachedTransform::new(*motion.open());
+        if motion.is_moving() {
+            CachedTransform { motion: Some(Box::new(motion)), ..transformation }
+        } else {
+            transformation
+        }
+    }
+
+    /// The transform at the given time, only a moving one has to be computed again
+    pub fn at(&self, time: f32) -> Cow<'_, CachedTransform> {
+        match &self.motion {
+            Some(motion) if time > 0.0 => Cow::Owned(CachedTransform::new(motion.at(time))),
+            _ => Cow::Borrowed(self)
+        }
+    }
+
+    /// Returns the box around an object space box at every time
+    pub fn bounds(&self, local: &BoundingBox) -> BoundingBox {
+        match &self.motion {
+            Some(motion) => motion.sweep(local),
+            None => local.transform(&self.matrix)
+        }
+    }
+}
+
+impl Default for CachedTransform {
+    fn default() -> Self {
+        CachedTransform::new(Matrix::new_identity_matrix())
+    }
+}
+
+/// The material of a shape, remembering if it was set on the shape itself.
+/// Until then the shape shows the default material or the one of its group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialSlot {
+    material: Material,
+    own: bool
+}
+
+impl MaterialSlot {
+    pub fn get(&self) -> &Material {
+        &self.material
+    }
+
+    /// Assigns the shape its own material, which parents no longer replace
+    pub fn set(&mut self, material: Material) {
+        self.material = material;
+        self.own = true;
+    }
+
+    /// Takes a parent's material, unless the shape has one of its own
+    pub fn inherit(&mut self, material: Material) {
+        if !self.own {
+            self.material = material;
+        }
+    }
+
+    pub fn is_own(&self) -> bool {
+        self.own
+    }
+}
+
+/// Common contract of every primitive. Implementors only provide the math in
+/// object space, conversion from and to world space is handled here.
+pub trait Shape: Debug + Send + Sync {
+    fn transformation(&self) -> &CachedTransform;
+
+    /// Replaces the transform, moving or not
+    fn set_transformation(&mut self, transformation: CachedTransform);
+
+    /// # Panics
+    /// When the matrix is singular, like a scaling by zero. `try_set_transform` handles matrices that may be.
+    fn set_transform(&mut self, transform: Matrix) {
+        self.set_transformation(CachedTransform::new(transform));
+    }
+
+    /// Sets the transform, or leaves the shape as it was when the matrix has no inverse
+    fn try_set_transform(&mut self, transform: Matrix) -> Result<(), SingularMatrix> {
+        self.set_transformation(CachedTransform::try_new(transform)?);
+        Ok(())
+    }
+
+    /// Makes the shape move from `open` at time 0 to `close` at time 1, see `Motion`.
+    /// Its transform is then the one at time 0.
+    ///
+    /// # Panics
+    /// When either end is singular, or only one of them mirrors the shape
+    fn set_motion(&mut self, open: Matrix, close: Matrix) {
+        self.set_transformation(CachedTransform::new_moving(open, close));
+    }
+
+    fn material(&self) -> &Material;
+
+    fn set_material(&mut self, material: Material);
+
+    /// Gives the shape the material of its group, unless it was given one of its own
+    fn inherit_material(&mut self, material: Material);
+
+    /// Intersects a ray which is already converted to object space
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+
+    /// Returns the normal of a point which is already converted to object space.
+    /// The hit is passed along for shapes that interpolate their normals.
+    fn local_normal_at(&self, point: &Tuple, hit: &Intersection) -> Tuple;
+
+    /// Returns the box around the shape in object space, before its transform
+    fn local_bounds(&self) -> BoundingBox;
+
+    /// Returns the box around the shape in world space, over its whole motion if it moves.
+    /// Groups compose their transforms into their children, so this is also the box in any parent's space.
+    fn bounds(&self) -> BoundingBox {
+        self.transformation().bounds(&self.local_bounds())
+    }
+
+    fn transform(&self) -> &Matrix {
+        &self.transformation().matrix
+    }
+
+    /// Returns the intersections of a world space ray with the shape, sorted by t.
+    /// A moving shape is intersected where it is at the time of the ray.
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let local_ray = ray.transform(&self.transformation().at(ray.time).inverse);
+        let mut xs = self.local_intersect(&local_ray);
+        for x in xs.iter_mut() {
+            x.time = ray.time;
+        }
+        xs
+    }
+
+    /// Returns the world space normal at the given world space point, at the time of the hit
+    fn normal_at(&self, point: &Tuple, hit: &Intersection) -> Tuple {
+        let local_point = self.world_to_object(point, hit.time);
+        let local_normal = self.local_normal_at(&local_point, hit);
+        self.normal_to_world(&local_normal, hit.time)
+    }
+
+    fn world_to_object(&self, point: &Tuple, time: f32) -> Tuple {
+        &self.transformation().at(time).inverse * point
+    }
+
+    fn normal_to_world(&self, normal: &Tuple, time: f32) -> Tuple {
+        let world_normal = &self.transformation().at(time).inverse_transpose * normal;
+        Tuple { w: 0.0, ..world_normal }.normalize()
+    }
+
+    /// Returns if the given shape is this shape or, for composite shapes, one of its descendants
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, f32::consts::{PI, FRAC_1_SQRT_2}};
+
+    use crate::helper::Axis;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestShape {
+        transformation: CachedTransform,
+        material: MaterialSlot,
+        saved_ray: Mutex<Option<Ray>>
+    }
+
+    impl Shape for TestShape {
+        fn transformation(&self) -> &CachedTransform {
+            &self.transformation
+        }
+
+        fn set_transformation(&mut self, transformation: CachedTransform) {
+            self.transformation = transformation;
+        }
+
+        fn material(&self) -> &Material {
+            self.material.get()
+        }
+
+        fn set_material(&mut self, material: Material) {
+            self.material.set(material);
+        }
+
+        fn inherit_material(&mut self, material: Material) {
+            self.material.inherit(material);
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+            *self.saved_ray.lock().unwrap() = Some(*ray);
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+            Tuple::new_vector(point.x, point.y, point.z)
+        }
+
+        fn local_bounds(&self) -> BoundingBox {
+            BoundingBox::new(Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_point(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn default_transformation() {
+        let s = TestShape::default();
+        assert_eq!(s.transform(), &Matrix::new_identity_matrix());
+    }
+
+    #[test]
+    fn assigning_a_transformation() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(2.0, 3.0, 4.0));
+        assert_eq!(s.transform(), &Matrix::translation(2.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn a_singular_transformation_is_rejected() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(2.0, 3.0, 4.0));
+        assert_eq!(s.try_set_transform(Matrix::scaling(1.0, 0.0, 1.0)), Err(SingularMatrix));
+        assert_eq!(s.transform(), &Matrix::translation(2.0, 3.0, 4.0));
+        assert_eq!(s.try_set_transform(Matrix::scaling(2.0, 2.0, 2.0)), Ok(()));
+        assert_eq!(s.transformation().inverse, Matrix::scaling(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "a transform must be invertible")]
+    fn setting_a_singular_transformation_panics() {
+        TestShape::default().set_transform(Matrix::scaling(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn default_material() {
+        let s = TestShape::default();
+        assert_eq!(s.material(), &Material::default());
+    }
+
+    #[test]
+    fn assigning_a_material() {
+        let mut s = TestShape::default();
+        let m = Material { ambient: 1.0, ..Material::default() };
+        s.set_material(m.clone());
+        assert_eq!(s.material(), &m);
+    }
+
+    #[test]
+    fn intersecting_a_scaled_shape_with_a_ray() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        s.intersect(&r);
+        let saved_ray = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::new_point(0.0, 0.0, -2.5));
+        assert_eq!(saved_ray.direction, Tuple::new_vector(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn intersecting_a_translated_shape_with_a_ray() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        s.intersect(&r);
+        let saved_ray = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::new_point(-5.0, 0.0, -5.0));
+        assert_eq!(saved_ray.direction, Tuple::new_vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn computing_the_normal_on_a_translated_shape() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(&Tuple::new_point(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2), &Intersection::new(0.0, &s));
+        assert_eq!(n, Tuple::new_vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn a_moving_shape_is_intersected_where_it_is_at_the_time_of_the_ray() {
+        let mut s = TestShape::default();
+        s.set_motion(Matrix::translation(0.0, 0.0, 0.0), Matrix::translation(4.0, 0.0, 0.0));
+        assert_eq!(s.transform(), &Matrix::new_identity_matrix());
+        assert_eq!(s.transformation().close(), Matrix::translation(4.0, 0.0, 0.0));
+        for (time, x) in [(0.0, 0.0), (0.25, -1.0), (1.0, -4.0)] {
+            s.intersect(&Ray::new_with_time(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0), time));
+            let saved_ray = s.saved_ray.lock().unwrap().unwrap();
+            assert_eq!(saved_ray.origin, Tuple::new_point(x, 0.0, -5.0));
+            assert_eq!(saved_ray.time, time);
+        }
+    }
+
+    #[test]
+    fn the_normal_of_a_moving_shape_follows_it() {
+        let mut s = TestShape::default();
+        s.set_motion(Matrix::new_identity_matrix(), Matrix::rotation(Axis::Z, PI / 2.0));
+        let hit = Intersection { time: 0.5, ..Intersection::new(1.0, &s) };
+        let n = s.normal_at(&Tuple::new_point(1.0, 0.0, 0.0), &hit);
+        assert_eq!(n, Tuple::new_vector(1.0, 0.0, 0.0));
+        assert_eq!(s.world_to_object(&Tuple::new_point(0.0, 1.0, 0.0), 1.0), Tuple::new_point(1.0, 0.0, 0.0));
+        assert_eq!(s.normal_to_world(&Tuple::new_vector(1.0, 0.0, 0.0), 0.5), Tuple::new_vector(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0));
+    }
+
+    #[test]
+    fn the_bounds_of_a_moving_shape_cover_its_whole_path() {
+        let mut s = TestShape::default();
+        s.set_motion(Matrix::translation(0.0, 0.0, 0.0), Matrix::translation(0.0, 3.0, 0.0));
+        let b = s.bounds();
+        assert_eq!(b.min, Tuple::new_point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, Tuple::new_point(1.0, 4.0, 1.0));
+    }
+
+    #[test]
+    fn bounds_of_a_transformed_shape_are_in_world_space() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(1.0, -3.0, 5.0) * Matrix::scaling(0.5, 2.0, 4.0));
+        let b = s.bounds();
+        assert_eq!(b.min, Tuple::new_point(0.5, -5.0, 1.0));
+        assert_eq!(b.max, Tuple::new_point(1.5, -1.0, 9.0));
+    }
+
+    #[test]
+    fn computing_the_normal_on_a_transformed_shape() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation(Axis::Z, PI / 5.0));
+        let n = s.normal_at(&Tuple::new_point(0.0, 2f32.sqrt() / 2.0, -(2f32.sqrt()) / 2.0), &Intersection::new(0.0, &s));
+        assert_eq!(n, Tuple::new_vector(0.0, 0.97014, -0.24254));
+    }
+}
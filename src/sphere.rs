@@ -0,0 +1,183 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::Intersection, 
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox};
+
+/// A unit sphere centered at the origin of its object space
+#[derive(Debug, Default)]
+pub struct Sphere {
+    transformation: CachedTransform,
+    material: MaterialSlot
+}
+
+impl Sphere {
+    pub fn new() -> Sphere {
+        Sphere { 
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default()
+        }
+    }
+}
+
+impl Shape for Sphere {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let sphere_to_ray = &ray.origin - &Tuple::new_point(0.0, 0.0, 0.0);
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * ray.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+        let discriminant = b.powi(2) - 4.0 * a * c;
+
+        if discriminant < 0.0 {
+            return vec![];
+        }
+
+        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+        vec![Intersection::new(t1.min(t2), self), Intersection::new(t1.max(t2), self)]
+    }
+
+    fn local_normal_at(&self, point: &Tuple, _hit: &Intersection) -> Tuple {
+        point - &Tuple::new_point(0.0, 0.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::new_point(-1.0, -1.0, -1.0), Tuple::new_point(1.0, 1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{helper::Axis, matrix::Matrix};
+
+    use super::*;
+
+    #[test]
+    fn ray_intersects_a_sphere_at_two_points() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[1].t, 6.0);
+    }
+
+    #[test]
+    fn ray_intersects_a_sphere_at_a_tangent() {
+        let r = Ray::new(Tuple::new_point(0.0, 1.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 5.0);
+        assert_eq!(xs[1].t, 5.0);
+    }
+
+    #[test]
+    fn ray_misses_a_sphere() {
+        let r = Ray::new(Tuple::new_point(0.0, 2.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_originates_inside_a_sphere() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs[0].t, -1.0);
+        assert_eq!(xs[1].t, 1.0);
+    }
+
+    #[test]
+    fn sphere_is_behind_a_ray() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs[0].t, -6.0);
+        assert_eq!(xs[1].t, -4.0);
+    }
+
+    #[test]
+    fn intersect_sets_the_object_on_the_intersection() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert!(std::ptr::addr_eq(xs[0].object, &s));
+        assert!(std::ptr::addr_eq(xs[1].object, &s));
+    }
+
+    #[test]
+    fn intersecting_a_scaled_sphere_with_a_ray() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 3.0);
+        assert_eq!(xs[1].t, 7.0);
+    }
+
+    #[test]
+    fn intersecting_a_translated_sphere_with_a_ray() {
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normal_on_a_sphere_at_points_on_the_axes() {
+        let s = Sphere::new();
+        let i = Intersection::new(0.0, &s);
+        assert_eq!(s.normal_at(&Tuple::new_point(1.0, 0.0, 0.0), &i), Tuple::new_vector(1.0, 0.0, 0.0));
+        assert_eq!(s.normal_at(&Tuple::new_point(0.0, 1.0, 0.0), &i), Tuple::new_vector(0.0, 1.0, 0.0));
+        assert_eq!(s.normal_at(&Tuple::new_point(0.0, 0.0, 1.0), &i), Tuple::new_vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_on_a_sphere_at_a_nonaxial_point() {
+        let s = Sphere::new();
+        let value = 3f32.sqrt() / 3.0;
+        let n = s.normal_at(&Tuple::new_point(value, value, value), &Intersection::new(0.0, &s));
+        assert_eq!(n, Tuple::new_vector(value, value, value));
+        assert_eq!(n, n.normalize());
+    }
+
+    #[test]
+    fn normal_on_a_transformed_sphere() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation(Axis::Z, PI / 5.0));
+        let n = s.normal_at(&Tuple::new_point(0.0, 2f32.sqrt() / 2.0, -(2f32.sqrt()) / 2.0), &Intersection::new(0.0, &s));
+        assert_eq!(n, Tuple::new_vector(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn a_sphere_has_a_bounding_box() {
+        let mut s = Sphere::new();
+        let b = s.local_bounds();
+        assert_eq!(b.min, Tuple::new_point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, Tuple::new_point(1.0, 1.0, 1.0));
+        s.set_transform(Matrix::translation(1.0, 0.0, 0.0) * Matrix::scaling(2.0, 3.0, 4.0));
+        assert_eq!(s.bounds().min, Tuple::new_point(-1.0, -3.0, -4.0));
+        assert_eq!(s.bounds().max, Tuple::new_point(3.0, 3.0, 4.0));
+    }
+}
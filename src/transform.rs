@@ -0,0 +1,107 @@
+use crate::{matrix::Matrix, tuple::Tuple, helper::Axis};
+
+/// Builds a transformation from operations listed in the order they happen,
+/// so `Transform::identity().scale(2.0, 2.0, 2.0).translate(0.0, 1.0, 0.0)` scales first.
+/// Each step multiplies on the left, which is the reverse of how the matrix product reads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: Matrix
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl From<Transform> for Matrix {
+    fn from(transform: Transform) -> Self {
+        transform.matrix
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform { matrix: Matrix::new_identity_matrix() }
+    }
+
+    /// Applies any matrix after the operations so far
+    pub fn then(self, matrix: &Matrix) -> Transform {
+        Transform { matrix: *matrix * self.matrix }
+    }
+
+    pub fn translate(self, x: f32, y: f32, z: f32) -> Transform {
+        self.then(&Matrix::translation(x, y, z))
+    }
+
+    pub fn scale(self, x: f32, y: f32, z: f32) -> Transform {
+        self.then(&Matrix::scaling(x, y, z))
+    }
+
+    pub fn rotate(self, axis: Axis, angle: f32) -> Transform {
+        self.then(&Matrix::rotation(axis, angle))
+    }
+
+    /// Rotates around an axis through the origin with any direction
+    pub fn rotate_around(self, axis: &Tuple, angle: f32) -> Transform {
+        self.then(&Matrix::rotation_around(axis, angle))
+    }
+
+    pub fn shear(self, xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Transform {
+        self.then(&Matrix::shearing(xy, xz, yx, yz, zx, zy))
+    }
+
+    /// Moves what was built so far to `from`, facing `to`, see `Matrix::look_at`
+    pub fn look_at(self, from: &Tuple, to: &Tuple, up: &Tuple) -> Transform {
+        self.then(&Matrix::look_at(from, to, up))
+    }
+
+    /// Goes into the space of an eye at `from` looking at `to`, for cameras
+    pub fn view_transform(self, from: &Tuple, to: &Tuple, up: &Tuple) -> Transform {
+        self.then(&Matrix::view_transform(from, to, up))
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        self.matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn operations_apply_in_the_order_they_are_listed() {
+        let p = Tuple::new_point(1.0, 0.0, 1.0);
+        let transform = Transform::identity()
+            .rotate(Axis::X, PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+        assert_eq!(transform.matrix() * p, Tuple::new_point(15.0, 0.0, 7.0));
+        let by_hand = Matrix::translation(10.0, 5.0, 7.0) * Matrix::scaling(5.0, 5.0, 5.0) * Matrix::rotation(Axis::X, PI / 2.0);
+        assert_eq!(Matrix::from(transform), by_hand);
+    }
+
+    #[test]
+    fn the_default_transform_is_the_identity() {
+        assert_eq!(Transform::default().matrix(), Matrix::new_identity_matrix());
+        let shear = Transform::default().shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        assert_eq!(shear.matrix() * Tuple::new_point(2.0, 3.0, 4.0), Tuple::new_point(5.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn turning_and_placing_an_object() {
+        let from = Tuple::new_point(0.0, 2.0, 0.0);
+        let to = Tuple::new_point(5.0, 2.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        let transform = Transform::identity()
+            .rotate_around(&Tuple::new_vector(0.0, 0.0, 1.0), PI / 2.0)
+            .look_at(&from, &to, &up);
+        // the x axis turns into the y axis, which stays up
+        assert_eq!(transform.matrix() * Tuple::new_point(1.0, 0.0, -1.0), Tuple::new_point(1.0, 3.0, 0.0));
+        let camera = Transform::identity().look_at(&from, &to, &up).view_transform(&from, &to, &up);
+        assert_eq!(camera.matrix() * Tuple::new_point(1.0, 2.0, 3.0), Tuple::new_point(1.0, 2.0, 3.0));
+    }
+}
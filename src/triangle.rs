@@ -0,0 +1,288 @@
+use crate::{tuple::Tuple, ray::Ray, intersection::Intersection, helper::EPSILON,
+            shape::{Shape, CachedTransform, MaterialSlot}, material::Material, bounds::BoundingBox};
+
+/// A flat triangle, the normal is the same at every point of its surface
+#[derive(Debug)]
+pub struct Triangle {
+    transformation: CachedTransform,
+    material: MaterialSlot,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Triangle {
+        let e1 = &p2 - &p1;
+        let e2 = &p3 - &p1;
+        let normal = e2.cross(&e1).normalize();
+        Triangle {
+            transformation: CachedTransform::default(),
+            material: MaterialSlot::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal
+        }
+    }
+}
+
+/// A triangle that interpolates the normals given at its vertices,
+/// which makes a coarse mesh look smooth
+#[derive(Debug)]
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> SmoothTriangle {
+        SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3
+        }
+    }
+
+    pub fn p1(&self) -> &Tuple {
+        &self.triangle.p1
+    }
+
+    pub fn p2(&self) -> &Tuple {
+        &self.triangle.p2
+    }
+
+    pub fn p3(&self) -> &Tuple {
+        &self.triangle.p3
+    }
+}
+
+/// Möller–Trumbore intersection, returns t with the barycentric u and v of the hit
+fn intersect_triangle(triangle: &Triangle, ray: &Ray) -> Option<(f32, f32, f32)> {
+    let dir_cross_e2 = ray.direction.cross(&triangle.e2);
+    let determinant = triangle.e1.dot(&dir_cross_e2);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / determinant;
+    let p1_to_origin = &ray.origin - &triangle.p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&triangle.e1);
+    let v = f * ray.direction.dot(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * triangle.e2.dot(&origin_cross_e1);
+    Some((t, u, v))
+}
+
+impl Shape for Triangle {
+    fn transformation(&self) -> &CachedTransform {
+        &self.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.transformation = transformation;
+    }
+
+    fn material(&self) -> &Material {
+        self.material.get()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material.set(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.material.inherit(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match intersect_triangle(self, ray) {
+            Some((t, u, v)) => vec![Intersection::new_with_uv(t, self, u, v)],
+            None => vec![]
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Tuple, _hit: &Intersection) -> Tuple {
+        self.normal
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::from_points([&self.p1, &self.p2, &self.p3])
+    }
+
+    /// Transforms the corners rather than the box, which stays tight under rotations
+    fn bounds(&self) -> BoundingBox {
+        if self.transformation.motion.is_some() {
+            return self.transformation.bounds(&self.local_bounds());
+        }
+        let transform = self.transform();
+        BoundingBox::from_points(&[transform * &self.p1, transform * &self.p2, transform * &self.p3])
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn transformation(&self) -> &CachedTransform {
+        &self.triangle.transformation
+    }
+
+    fn set_transformation(&mut self, transformation: CachedTransform) {
+        self.triangle.set_transformation(transformation);
+    }
+
+    fn material(&self) -> &Material {
+        self.triangle.material()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.triangle.set_material(material);
+    }
+
+    fn inherit_material(&mut self, material: Material) {
+        self.triangle.inherit_material(material);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match intersect_triangle(&self.triangle, ray) {
+            Some((t, u, v)) => vec![Intersection::new_with_uv(t, self, u, v)],
+            None => vec![]
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Tuple, hit: &Intersection) -> Tuple {
+        let n2 = self.n2.multiply(hit.u);
+        let n3 = self.n3.multiply(hit.v);
+        let n1 = self.n1.multiply(1.0 - hit.u - hit.v);
+        &(&n2 + &n3) + &n1
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.triangle.local_bounds()
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.triangle.bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{helper::equal, matrix::Matrix};
+
+    use super::*;
+
+    fn default_triangle() -> Triangle {
+        Triangle::new(Tuple::new_point(0.0, 1.0, 0.0),
+                      Tuple::new_point(-1.0, 0.0, 0.0),
+                      Tuple::new_point(1.0, 0.0, 0.0))
+    }
+
+    fn default_smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(Tuple::new_point(0.0, 1.0, 0.0),
+                            Tuple::new_point(-1.0, 0.0, 0.0),
+                            Tuple::new_point(1.0, 0.0, 0.0),
+                            Tuple::new_vector(0.0, 1.0, 0.0),
+                            Tuple::new_vector(-1.0, 0.0, 0.0),
+                            Tuple::new_vector(1.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = default_triangle();
+        assert_eq!(t.e1, Tuple::new_vector(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, Tuple::new_vector(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = default_triangle();
+        let i = Intersection::new(0.0, &t);
+        assert_eq!(t.local_normal_at(&Tuple::new_point(0.0, 0.5, 0.0), &i), t.normal);
+        assert_eq!(t.local_normal_at(&Tuple::new_point(-0.5, 0.75, 0.0), &i), t.normal);
+        assert_eq!(t.local_normal_at(&Tuple::new_point(0.5, 0.25, 0.0), &i), t.normal);
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Tuple::new_point(0.0, -1.0, -2.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        assert!(t.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_misses_the_edges() {
+        let t = default_triangle();
+        let origins = [
+            Tuple::new_point(1.0, 1.0, -2.0),
+            Tuple::new_point(-1.0, 1.0, -2.0),
+            Tuple::new_point(0.0, -1.0, -2.0)
+        ];
+        for origin in origins {
+            let r = Ray::new(origin, Tuple::new_vector(0.0, 0.0, 1.0));
+            assert!(t.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Tuple::new_point(0.0, 0.5, -2.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.0);
+    }
+
+    #[test]
+    fn intersection_with_a_smooth_triangle_stores_u_and_v() {
+        let tri = default_smooth_triangle();
+        let r = Ray::new(Tuple::new_point(-0.2, 0.3, -2.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = tri.local_intersect(&r);
+        assert!(equal(xs[0].u, 0.45));
+        assert!(equal(xs[0].v, 0.25));
+    }
+
+    #[test]
+    fn smooth_triangle_uses_u_and_v_to_interpolate_the_normal() {
+        let tri = default_smooth_triangle();
+        let i = Intersection::new_with_uv(1.0, &tri, 0.45, 0.25);
+        let n = tri.normal_at(&Tuple::new_point(0.0, 0.0, 0.0), &i);
+        assert_eq!(n, Tuple::new_vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn preparing_the_normal_on_a_smooth_triangle() {
+        let tri = default_smooth_triangle();
+        let i = Intersection::new_with_uv(1.0, &tri, 0.45, 0.25);
+        let r = Ray::new(Tuple::new_point(-0.2, 0.3, -2.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.normalv, Tuple::new_vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn a_triangle_has_a_bounding_box() {
+        let mut t = Triangle::new(Tuple::new_point(-3.0, 7.0, 2.0), Tuple::new_point(6.0, 2.0, -4.0),
+                                  Tuple::new_point(2.0, -1.0, -1.0));
+        let b = t.local_bounds();
+        assert_eq!(b.min, Tuple::new_point(-3.0, -1.0, -4.0));
+        assert_eq!(b.max, Tuple::new_point(6.0, 7.0, 2.0));
+        t.set_transform(Matrix::translation(1.0, 1.0, 1.0));
+        assert_eq!(t.bounds().min, Tuple::new_point(-2.0, 0.0, -3.0));
+        assert_eq!(default_smooth_triangle().bounds().max, Tuple::new_point(1.0, 1.0, 0.0));
+    }
+}
@@ -1,8 +1,8 @@
-use std::ops::{Add, Sub};
+use std::{fmt, ops::{Add, Sub}};
 
 use crate::helper::equal;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Tuple {
     pub x: f32,
     pub y: f32,
@@ -10,6 +10,24 @@ pub struct Tuple {
     pub w: f32
 }
 
+/// Returned when converting a tuple to a `Point3` or `Vector3` whose `w` says it is the other kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TupleKindError {
+    NotAPoint(f32),
+    NotAVector(f32)
+}
+
+impl fmt::Display for TupleKindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TupleKindError::NotAPoint(w) => write!(f, "expected a point with w = 1, found w = {}", w),
+            TupleKindError::NotAVector(w) => write!(f, "expected a vector with w = 0, found w = {}", w)
+        }
+    }
+}
+
+impl std::error::Error for TupleKindError {}
+
 #[derive(Debug)]
 pub struct Environment {
     pub gravity: Tuple,
@@ -51,12 +69,13 @@ impl Tuple {
         }
     }
 
+    /// Compares `w` within `EPSILON`, transformed points pick up rounding errors
     pub fn is_a_point(&self) -> bool {
-        self.w == 1.0
+        equal(self.w, 1.0)
     }
 
     pub fn is_a_vector(&self) -> bool {
-        self.w == 0.0
+        equal(self.w, 0.0)
     }
 
     pub fn negate(&self) -> Tuple {
@@ -103,6 +122,11 @@ impl Tuple {
                             self.z * other.x - self.x * other.z, 
                             self.x * other.y - self.y * other.x)
     }
+
+    /// Reflects the vector around the given normal
+    pub fn reflect(&self, normal: &Tuple) -> Tuple {
+        self - &normal.multiply(2.0 * self.dot(normal))
+    }
 }
 
 impl PartialEq for Tuple {
@@ -114,7 +138,7 @@ impl PartialEq for Tuple {
     }
 }
 
-impl<'a, 'b> Add<&'b Tuple> for &'a Tuple {
+impl<'b> Add<&'b Tuple> for &Tuple {
     type Output = Tuple;
 
     fn add(self, rhs:&'b Tuple) -> Self::Output {
@@ -124,7 +148,7 @@ impl<'a, 'b> Add<&'b Tuple> for &'a Tuple {
 }
 
 
-impl<'a, 'b> Sub<&'b Tuple> for &'a Tuple {
+impl<'b> Sub<&'b Tuple> for &Tuple {
     type Output = Tuple;
 
     fn sub(self, rhs:&'b Tuple) -> Self::Output {
@@ -261,4 +285,19 @@ mod tests {
         assert_eq!(a.cross(&b), Tuple::new_vector(-1.0, 2.0, -1.0));
         assert_eq!(b.cross(&a), Tuple::new_vector(1.0, -2.0, 1.0));
     }
+
+    #[test]
+    fn reflecting_a_vector_approaching_at_45_degrees() {
+        let v = Tuple::new_vector(1.0, -1.0, 0.0);
+        let n = Tuple::new_vector(0.0, 1.0, 0.0);
+        assert_eq!(v.reflect(&n), Tuple::new_vector(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn reflecting_a_vector_off_a_slanted_surface() {
+        let v = Tuple::new_vector(0.0, -1.0, 0.0);
+        let value = 2f32.sqrt() / 2.0;
+        let n = Tuple::new_vector(value, value, 0.0);
+        assert_eq!(v.reflect(&n), Tuple::new_vector(1.0, 0.0, 0.0));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,151 @@
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+use crate::{helper::equal, tuple::{Tuple, TupleKindError}};
+
+/// A direction in space. Unlike a `Tuple` it can only be combined in ways that
+/// make sense for directions, adding it to a point is done on `Point3`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32
+}
+
+impl Vector3 {
+    pub fn new(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    pub fn new_zero() -> Vector3 {
+        Vector3::default()
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Vector3 {
+        *self / self.magnitude()
+    }
+
+    pub fn dot(&self, other: &Vector3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vector3) -> Vector3 {
+        Vector3::new(self.y * other.z - self.z * other.y,
+                     self.z * other.x - self.x * other.z,
+                     self.x * other.y - self.y * other.x)
+    }
+
+    /// Reflects the vector around the given normal
+    pub fn reflect(&self, normal: &Vector3) -> Vector3 {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+}
+
+impl PartialEq for Vector3 {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self.x, other.x) && equal(self.y, other.y) && equal(self.z, other.z)
+    }
+}
+
+impl From<Vector3> for Tuple {
+    fn from(vector: Vector3) -> Self {
+        Tuple::new_vector(vector.x, vector.y, vector.z)
+    }
+}
+
+impl TryFrom<Tuple> for Vector3 {
+    type Error = TupleKindError;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Self::Error> {
+        if tuple.is_a_vector() {
+            Ok(Vector3::new(tuple.x, tuple.y, tuple.z))
+        } else {
+            Err(TupleKindError::NotAVector(tuple.w))
+        }
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+
+    fn add(self, rhs: Vector3) -> Self::Output {
+        Vector3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, rhs: Vector3) -> Self::Output {
+        Vector3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Self::Output {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f32> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<f32> for Vector3 {
+    type Output = Vector3;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector3::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vector_arithmetic() {
+        let a = Vector3::new(3.0, 2.0, 1.0);
+        let b = Vector3::new(5.0, 6.0, 7.0);
+        assert_eq!(a + b, Vector3::new(8.0, 8.0, 8.0));
+        assert_eq!(a - b, Vector3::new(-2.0, -4.0, -6.0));
+        assert_eq!(-a, Vector3::new(-3.0, -2.0, -1.0));
+        assert_eq!(a * 3.5, Vector3::new(10.5, 7.0, 3.5));
+        assert_eq!(a / 2.0, Vector3::new(1.5, 1.0, 0.5));
+    }
+
+    #[test]
+    fn products_and_normalizing() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(2.0, 3.0, 4.0);
+        assert_eq!(a.dot(&b), 20.0);
+        assert_eq!(a.cross(&b), Vector3::new(-1.0, 2.0, -1.0));
+        assert_eq!(b.cross(&a), Vector3::new(1.0, -2.0, 1.0));
+        assert_eq!(a.normalize(), Vector3::new(0.26726, 0.53452, 0.80178));
+        assert!(equal(a.normalize().magnitude(), 1.0));
+    }
+
+    #[test]
+    fn reflecting_a_vector_off_a_slanted_surface() {
+        let value = 2f32.sqrt() / 2.0;
+        let v = Vector3::new(0.0, -1.0, 0.0);
+        assert_eq!(v.reflect(&Vector3::new(value, value, 0.0)), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn converting_between_vectors_and_tuples() {
+        let v = Vector3::new(1.0, -2.0, 3.0);
+        assert_eq!(Tuple::from(v), Tuple::new_vector(1.0, -2.0, 3.0));
+        assert_eq!(Vector3::try_from(Tuple::new_vector(1.0, -2.0, 3.0)), Ok(v));
+        assert_eq!(Vector3::try_from(Tuple::new_point(1.0, -2.0, 3.0)), Err(TupleKindError::NotAVector(1.0)));
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{shape::Shape, light::{Light, LightSample, lighting}, ray::Ray, color::Color, tuple::Tuple,
+            intersection::{Intersection, Computations, intersections, hit}};
+
+/// How many times a ray may bounce between reflective or transparent surfaces by default
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// A collection of objects and the lights that illuminate them
+#[derive(Debug)]
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<Box<dyn Light>>,
+    /// Limit of recursive reflected and refracted rays, stops mirrors facing each other
+    pub max_depth: usize
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl World {
+    pub fn new() -> World {
+        World { objects: vec![], lights: vec![], max_depth: DEFAULT_MAX_DEPTH }
+    }
+
+    pub fn add_object(&mut self, object: Box<dyn Shape>) {
+        self.objects.push(object);
+    }
+
+    pub fn add_light(&mut self, light: impl Light + 'static) {
+        self.lights.push(Box::new(light));
+    }
+
+    /// Intersects the ray with every object in the world, sorted by t
+    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let xs = self.objects.iter()
+            .flat_map(|object| object.intersect(ray))
+            .collect();
+        intersections(xs)
+    }
+
+    /// Returns the color at the intersection, summing the contribution of every light
+    /// and of the reflected and refracted rays. `remaining` is the number of bounces left.
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        let material = comps.object.material();
+        let hit = Intersection { time: comps.time, ..Intersection::new(comps.t, comps.object) };
+        let surface = self.lights.iter()
+            .map(|light| {
+                // the same samples decide what is in shadow and how the visible part is shaded
+                let samples = self.light_samples(light.as_ref(), comps);
+                lighting(material, &hit, light.as_ref(), &comps.point,
+                        &comps.eyev, &comps.normalv, &samples)
+            })
+            .fold(Color::new(0.0, 0.0, 0.0), |acc, color| acc + color);
+
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// Returns the color seen in the mirror direction, scaled by how reflective the surface is
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let reflect_ray = Ray::new_with_time(comps.over_point, comps.reflectv, comps.time);
+        self.color_at_depth(&reflect_ray, remaining - 1) * reflective
+    }
+
+    /// Returns the color seen through the surface, bent by Snell's law
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let transparency = comps.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        // total internal reflection, no light passes through
+        if sin2_t > 1.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = &comps.normalv.multiply(n_ratio * cos_i - cos_t) - &comps.eyev.multiply(n_ratio);
+        let refract_ray = Ray::new_with_time(comps.under_point, direction, comps.time);
+        self.color_at_depth(&refract_ray, remaining - 1) * transparency
+    }
+
+    /// Returns the light's samples as seen from the hit, with the ones an object blocks turned
+    /// black. Shadow rays leave from the over point along the directions of the samples.
+    pub fn light_samples(&self, light: &dyn Light, comps: &Computations) -> Vec<LightSample> {
+        light.samples(&comps.point).into_iter()
+            .map(|sample| if self.is_blocked(&comps.over_point, &sample.direction, sample.distance, comps.time) {
+                LightSample { intensity: Color::new(0.0, 0.0, 0.0), ..sample }
+            } else {
+                sample
+            })
+            .collect()
+    }
+
+    /// Returns the fraction of the light's samples that the point can see at the given time, between 0 and 1
+    pub fn intensity_at(&self, light: &dyn Light, point: &Tuple, time: f32) -> f32 {
+        let samples = light.samples(point);
+        let visible = samples.iter().filter(|sample| !self.is_blocked(point, &sample.direction, sample.distance, time)).count();
+        visible as f32 / samples.len() as f32
+    }
+
+    /// Returns if any object lies between the point and a position on a light at the given time
+    pub fn is_shadowed(&self, light_position: &Tuple, point: &Tuple, time: f32) -> bool {
+        let v = light_position - point;
+        self.is_blocked(point, &v.normalize(), v.magnitude(), time)
+    }
+
+    /// Returns if a ray from the point along the direction hits anything closer than
+    /// `distance`, which is infinite for lights without a position. Moving objects
+    /// are where they are at the given time.
+    pub fn is_blocked(&self, point: &Tuple, direction: &Tuple, distance: f32, time: f32) -> bool {
+        let ray = Ray::new_with_time(*point, *direction, time);
+        let xs = self.intersect(&ray);
+        match hit(&xs) {
+            Some(i) => i.t < distance,
+            None => false
+        }
+    }
+
+    /// Returns the color seen along the ray, black if nothing is hit
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_at_depth(ray, self.max_depth)
+    }
+
+    /// Same as `color_at`, with an explicit number of bounces left for secondary rays
+    pub fn color_at_depth(&self, ray: &Ray, remaining: usize) -> Color {
+        let xs = self.intersect(ray);
+        match hit(&xs) {
+            Some(i) => self.shade_hit(&i.prepare_computations(ray, &xs), remaining),
+            None => Color::new(0.0, 0.0, 0.0)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{sync::Arc, f32::consts::PI};
+
+    use crate::{sphere::Sphere, plane::Plane, material::Material, matrix::Matrix, pattern::tests::TestPattern,
+                light::{PointLight, AreaLight}, helper::Axis};
+
+    use super::*;
+
+    /// The world most of the shading tests are written against
+    pub(crate) fn default_world() -> World {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Tuple::new_point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+
+        let mut s1 = Sphere::new();
+        s1.set_material(Material { color: Color::new(0.8, 1.0, 0.6), diffuse: 0.7, specular: 0.2, ..Material::default() });
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        world.add_object(Box::new(s1));
+        world.add_object(Box::new(s2));
+        world
+    }
+
+    pub(crate) fn assert_color_near(result: Color, expected: Color) {
+        assert!((result.red() - expected.red()).abs() < 0.0001, "{:?} != {:?}", result, expected);
+        assert!((result.green() - expected.green()).abs() < 0.0001, "{:?} != {:?}", result, expected);
+        assert!((result.blue() - expected.blue()).abs() < 0.0001, "{:?} != {:?}", result, expected);
+    }
+
+    /// Secondary rays start SURFACE_EPSILON off the surface, which moves the shaded point
+    /// a little further than the f64 reference values account for
+    pub(crate) fn assert_color_near_loose(result: Color, expected: Color) {
+        assert!((result.red() - expected.red()).abs() < 0.001, "{:?} != {:?}", result, expected);
+        assert!((result.green() - expected.green()).abs() < 0.001, "{:?} != {:?}", result, expected);
+        assert!((result.blue() - expected.blue()).abs() < 0.001, "{:?} != {:?}", result, expected);
+    }
+
+    #[test]
+    fn creating_a_world() {
+        let w = World::new();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+    }
+
+    #[test]
+    fn intersect_a_world_with_a_ray() {
+        let w = default_world();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[1].t, 4.5);
+        assert_eq!(xs[2].t, 5.5);
+        assert_eq!(xs[3].t, 6.0);
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_color_near(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let mut w = default_world();
+        w.lights = vec![Box::new(PointLight::new(Tuple::new_point(0.0, 0.25, 0.0), Color::new(1.0, 1.0, 1.0)))];
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_color_near(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn color_when_a_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn color_when_a_ray_hits() {
+        let w = default_world();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_color_near(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn color_with_an_intersection_behind_the_ray() {
+        let mut w = default_world();
+        let outer_material = Material { ambient: 1.0, ..w.objects[0].material().clone() };
+        w.objects[0].set_material(outer_material);
+        let inner_material = Material { ambient: 1.0, ..w.objects[1].material().clone() };
+        w.objects[1].set_material(inner_material);
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.75), Tuple::new_vector(0.0, 0.0, -1.0));
+        assert_eq!(w.color_at(&r), w.objects[1].material().color);
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let w = default_world();
+        let p = Tuple::new_point(0.0, 10.0, 0.0);
+        assert!(!w.is_shadowed(&Tuple::new_point(-10.0, 10.0, -10.0), &p, 0.0));
+    }
+
+    #[test]
+    fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
+        let w = default_world();
+        let p = Tuple::new_point(10.0, -10.0, 10.0);
+        assert!(w.is_shadowed(&Tuple::new_point(-10.0, 10.0, -10.0), &p, 0.0));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_light() {
+        let w = default_world();
+        let p = Tuple::new_point(-20.0, 20.0, -20.0);
+        assert!(!w.is_shadowed(&Tuple::new_point(-10.0, 10.0, -10.0), &p, 0.0));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_point() {
+        let w = default_world();
+        let p = Tuple::new_point(-2.0, 2.0, -2.0);
+        assert!(!w.is_shadowed(&Tuple::new_point(-10.0, 10.0, -10.0), &p, 0.0));
+    }
+
+    #[test]
+    fn a_moving_object_casts_its_shadow_where_it_is_at_the_time() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Tuple::new_point(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+        let mut s = Sphere::new();
+        s.set_motion(Matrix::translation(0.0, 5.0, 0.0), Matrix::translation(6.0, 5.0, 0.0));
+        w.add_object(Box::new(s));
+        let p = Tuple::new_point(0.0, 0.0, 0.0);
+        assert!(w.is_shadowed(&Tuple::new_point(0.0, 10.0, 0.0), &p, 0.0));
+        assert!(!w.is_shadowed(&Tuple::new_point(0.0, 10.0, 0.0), &p, 0.5));
+        assert_eq!(w.intensity_at(w.lights[0].as_ref(), &p, 0.1), 0.0);
+        assert_eq!(w.intensity_at(w.lights[0].as_ref(), &p, 1.0), 1.0);
+    }
+
+    #[test]
+    fn secondary_rays_are_cast_at_the_time_of_the_hit() {
+        let mut w = default_world();
+        let mut mirror = Plane::new();
+        mirror.set_material(Material { reflective: 1.0, ..Material::default() });
+        mirror.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        w.add_object(Box::new(mirror));
+        let mut ball = Sphere::new();
+        ball.set_motion(Matrix::translation(0.0, 0.0, 10.0), Matrix::translation(0.0, 5.0, 10.0));
+        w.add_object(Box::new(ball));
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new_with_time(Tuple::new_point(0.0, 2.0, 5.0), Tuple::new_vector(0.0, -value, value), 1.0);
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(comps.time, 1.0);
+        let reflected = w.reflected_color(&comps, 1);
+        let missed = w.reflected_color(&xs[0].prepare_computations(&Ray { time: 0.0, ..r }, &xs), 1);
+        assert_ne!(reflected, missed);
+    }
+
+    #[test]
+    fn a_point_light_is_either_fully_visible_or_hidden() {
+        let w = default_world();
+        let cases = [((0.0, 1.0001, 0.0), 1.0), ((-1.0001, 0.0, 0.0), 1.0), ((0.0, 0.0, -1.0001), 1.0),
+                     ((0.0, 0.0, 1.0001), 0.0), ((1.0001, 0.0, 0.0), 0.0), ((0.0, -1.0001, 0.0), 0.0), ((0.0, 0.0, 0.0), 0.0)];
+        for ((x, y, z), expected) in cases {
+            assert_eq!(w.intensity_at(w.lights[0].as_ref(), &Tuple::new_point(x, y, z), 0.0), expected, "{} {} {}", x, y, z);
+        }
+    }
+
+    #[test]
+    fn an_area_light_is_partly_visible_from_the_edge_of_a_shadow() {
+        let w = default_world();
+        let mut light = AreaLight::new_rectangle(Tuple::new_point(-0.5, -0.5, -5.0), Tuple::new_vector(1.0, 0.0, 0.0), 2,
+                                                 Tuple::new_vector(0.0, 1.0, 0.0), 2, Color::new(1.0, 1.0, 1.0));
+        light.jitter = false;
+        let cases = [((0.0, 0.0, 2.0), 0.0), ((1.0, -1.0, 2.0), 0.25), ((1.5, 0.0, 2.0), 0.5),
+                     ((1.25, 1.25, 3.0), 0.75), ((0.0, 0.0, -2.0), 1.0)];
+        for ((x, y, z), expected) in cases {
+            assert_eq!(w.intensity_at(&light, &Tuple::new_point(x, y, z), 0.0), expected, "{} {} {}", x, y, z);
+        }
+    }
+
+    #[test]
+    fn shadows_and_shading_use_the_same_samples_of_a_light() {
+        let mut w = default_world();
+        let light = AreaLight::new_rectangle(Tuple::new_point(-0.5, -0.5, -5.0), Tuple::new_vector(1.0, 0.0, 0.0), 4,
+                                             Tuple::new_vector(0.0, 1.0, 0.0), 4, Color::new(1.0, 1.0, 1.0));
+        let mut wall = Plane::new();
+        wall.set_transform(Matrix::translation(0.0, 0.0, 2.0) * Matrix::rotation(Axis::X, PI / 2.0));
+        w.add_object(Box::new(wall));
+        let r = Ray::new(Tuple::new_point(1.5, 0.0, -10.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let drawn = light.samples(&comps.point);
+        let seen = w.light_samples(&light, &comps);
+        assert_eq!(seen.len(), drawn.len());
+        let mut blocked = 0;
+        for (seen, drawn) in seen.iter().zip(&drawn) {
+            assert_eq!((seen.direction, seen.distance), (drawn.direction, drawn.distance));
+            if w.is_blocked(&comps.over_point, &drawn.direction, drawn.distance, 0.0) {
+                assert_eq!(seen.intensity, Color::new(0.0, 0.0, 0.0));
+                blocked += 1;
+            } else {
+                assert_eq!(seen.intensity, drawn.intensity);
+            }
+        }
+        // the point is in the penumbra, so only some of the jittered positions are hidden
+        assert!(0 < blocked && blocked < drawn.len(), "{} of {} blocked", blocked, drawn.len());
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        w.add_object(Box::new(Sphere::new()));
+        let mut s2 = Sphere::new();
+        s2.set_transform(Matrix::translation(0.0, 0.0, 10.0));
+        w.add_object(Box::new(s2));
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.1, 0.1, 0.1));
+    }
+
+    fn reflective_plane(reflective: f32) -> Plane {
+        let mut shape = Plane::new();
+        shape.set_material(Material { reflective, ..Material::default() });
+        shape.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        shape
+    }
+
+    #[test]
+    fn reflected_color_for_a_nonreflective_material() {
+        let mut w = default_world();
+        let inner_material = Material { ambient: 1.0, ..w.objects[1].material().clone() };
+        w.objects[1].set_material(inner_material);
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reflected_color_for_a_reflective_material() {
+        let mut w = default_world();
+        w.add_object(Box::new(reflective_plane(0.5)));
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -3.0), Tuple::new_vector(0.0, -value, value));
+        let i = Intersection::new(2f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_color_near_loose(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.19032, 0.2379, 0.14274));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let mut w = default_world();
+        w.add_object(Box::new(reflective_plane(0.5)));
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -3.0), Tuple::new_vector(0.0, -value, value));
+        let i = Intersection::new(2f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_color_near_loose(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.87677, 0.92436, 0.82918));
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Tuple::new_point(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+        let mut lower = reflective_plane(1.0);
+        lower.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        let mut upper = reflective_plane(1.0);
+        upper.set_transform(Matrix::translation(0.0, 1.0, 0.0));
+        w.add_object(Box::new(lower));
+        w.add_object(Box::new(upper));
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.0), Tuple::new_vector(0.0, 1.0, 0.0));
+        // terminates instead of recursing forever
+        w.color_at(&r);
+    }
+
+    #[test]
+    fn reflected_color_at_the_maximum_recursive_depth() {
+        let mut w = default_world();
+        w.add_object(Box::new(reflective_plane(0.5)));
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -3.0), Tuple::new_vector(0.0, -value, value));
+        let i = Intersection::new(2f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(w.reflected_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_with_an_opaque_surface() {
+        let w = default_world();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(4.0, w.objects[0].as_ref()), Intersection::new(6.0, w.objects[0].as_ref())];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_at_the_maximum_recursive_depth() {
+        let mut w = default_world();
+        let glass = Material { transparency: 1.0, refractive_index: 1.5, ..w.objects[0].material().clone() };
+        w.objects[0].set_material(glass);
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -5.0), Tuple::new_vector(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(4.0, w.objects[0].as_ref()), Intersection::new(6.0, w.objects[0].as_ref())];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        let glass = Material { transparency: 1.0, refractive_index: 1.5, ..w.objects[0].material().clone() };
+        w.objects[0].set_material(glass);
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, value), Tuple::new_vector(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-value, w.objects[0].as_ref()), Intersection::new(value, w.objects[0].as_ref())];
+        // the ray is inside the sphere, so the second intersection is the one to look at
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_with_a_refracted_ray() {
+        let mut w = default_world();
+        let a = Material {
+            ambient: 1.0,
+            ..Material::new_with_pattern(Arc::new(TestPattern::default()))
+        };
+        w.objects[0].set_material(a);
+        let b = Material { transparency: 1.0, refractive_index: 1.5, ..w.objects[1].material().clone() };
+        w.objects[1].set_material(b);
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, 0.1), Tuple::new_vector(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-0.9899, w.objects[0].as_ref()), Intersection::new(-0.4899, w.objects[1].as_ref()),
+                      Intersection::new(0.4899, w.objects[1].as_ref()), Intersection::new(0.9899, w.objects[0].as_ref())];
+        let comps = xs[2].prepare_computations(&r, &xs);
+        assert_color_near_loose(w.refracted_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.99888, 0.04725));
+    }
+
+    #[test]
+    fn shade_hit_with_a_transparent_material() {
+        let mut w = default_world();
+        let mut floor = Plane::new();
+        floor.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        floor.set_material(Material { transparency: 0.5, refractive_index: 1.5, ..Material::default() });
+        let mut ball = Sphere::new();
+        ball.set_material(Material { color: Color::new(1.0, 0.0, 0.0), ambient: 0.5, ..Material::default() });
+        ball.set_transform(Matrix::translation(0.0, -3.5, -0.5));
+        w.add_object(Box::new(floor));
+        w.add_object(Box::new(ball));
+
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -3.0), Tuple::new_vector(0.0, -value, value));
+        let xs = vec![Intersection::new(2f32.sqrt(), w.objects[2].as_ref())];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_color_near_loose(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_transparent_material() {
+        let mut w = default_world();
+        let mut floor = Plane::new();
+        floor.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        floor.set_material(Material { reflective: 0.5, transparency: 0.5, refractive_index: 1.5, ..Material::default() });
+        let mut ball = Sphere::new();
+        ball.set_material(Material { color: Color::new(1.0, 0.0, 0.0), ambient: 0.5, ..Material::default() });
+        ball.set_transform(Matrix::translation(0.0, -3.5, -0.5));
+        w.add_object(Box::new(floor));
+        w.add_object(Box::new(ball));
+
+        let value = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -3.0), Tuple::new_vector(0.0, -value, value));
+        let xs = vec![Intersection::new(2f32.sqrt(), w.objects[2].as_ref())];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_color_near_loose(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.93391, 0.69643, 0.69243));
+    }
+}
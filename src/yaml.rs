@@ -0,0 +1,570 @@
+use std::fmt;
+
+/// A parsed value of a YAML document. Only the subset needed by scene files is
+/// supported: block mappings and sequences, flow `[...]` and `{...}` collections,
+/// plain and quoted scalars and `#` comments. JSON is a subset of flow YAML,
+/// so JSON documents are read by the same parser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f32),
+    String(String),
+    Sequence(Vec<Node>),
+    /// Entries keep the order of the document
+    Mapping(Vec<(String, Node)>)
+}
+
+/// A value together with the line it starts on, for error messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub value: Value,
+    /// 1-based
+    pub line: usize
+}
+
+impl Node {
+    pub fn new(value: Value, line: usize) -> Node {
+        Node { value, line }
+    }
+
+    /// Returns the value stored under the key when this node is a mapping
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        match &self.value {
+            Value::Mapping(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, node)| node),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError { line, message: message.into() })
+}
+
+/// A non-blank line with its comment removed. A flow collection spread over
+/// several lines is joined into a single one, keeping the newlines.
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String
+}
+
+/// Parses a YAML or JSON document. An empty document is null.
+pub fn parse(source: &str) -> Result<Node, ParseError> {
+    let lines = logical_lines(source)?;
+    if lines.is_empty() {
+        return Ok(Node::new(Value::Null, 1));
+    }
+
+    let mut parser = BlockParser { lines, index: 0 };
+    let indent = parser.lines[0].indent;
+    let node = parser.parse_block(indent)?;
+    match parser.lines.get(parser.index) {
+        Some(line) => error(line.number, "unexpected content after the end of the document"),
+        None => Ok(node)
+    }
+}
+
+fn logical_lines(source: &str) -> Result<Vec<Line>, ParseError> {
+    let mut lines = vec![];
+    let mut raw_lines = source.lines().enumerate();
+
+    while let Some((index, raw)) = raw_lines.next() {
+        let number = index + 1;
+        let mut text = strip_comment(raw).trim_end().to_string();
+        if text.trim().is_empty() || text.trim() == "---" {
+            continue;
+        }
+
+        let content = text.trim_start();
+        let indent = text.len() - content.len();
+        if text[..indent].contains('\t') {
+            return error(number, "tabs are not allowed for indentation");
+        }
+        text = content.to_string();
+
+        while bracket_depth(&text) > 0 {
+            match raw_lines.next() {
+                Some((_, next)) => {
+                    text.push('\n');
+                    text.push_str(strip_comment(next).trim_end());
+                }
+                None => return error(number, "unclosed bracket"),
+            }
+        }
+        lines.push(Line { number, indent, text });
+    }
+    Ok(lines)
+}
+
+/// Calls the closure for every character that is not inside a quoted string
+fn for_each_unquoted(text: &str, mut f: impl FnMut(usize, char) -> bool) {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                } else if !f(index, c) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut end = line.len();
+    let mut previous = ' ';
+    for_each_unquoted(line, |index, c| {
+        if c == '#' && previous.is_whitespace() {
+            end = index;
+            return false;
+        }
+        previous = c;
+        true
+    });
+    &line[..end]
+}
+
+fn bracket_depth(text: &str) -> i32 {
+    let mut depth = 0;
+    for_each_unquoted(text, |_, c| {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            _ => ()
+        }
+        true
+    });
+    depth
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits `key: value` into the key and the rest, which may be empty
+fn split_key(text: &str) -> Option<(String, &str)> {
+    if text.starts_with('[') || text.starts_with('{') {
+        return None;
+    }
+    let mut colon = None;
+    let mut depth = 0;
+    for_each_unquoted(text, |index, c| {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            ':' if depth == 0 => {
+                let rest = &text[index + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    colon = Some(index);
+                    return false;
+                }
+            }
+            _ => ()
+        }
+        true
+    });
+
+    let colon = colon?;
+    let key = text[..colon].trim();
+    let key = if key.len() >= 2 && (key.starts_with('"') && key.ends_with('"') || key.starts_with('\'') && key.ends_with('\'')) {
+        &key[1..key.len() - 1]
+    } else {
+        key
+    };
+    Some((key.to_string(), text[colon + 1..].trim()))
+}
+
+struct BlockParser {
+    lines: Vec<Line>,
+    index: usize
+}
+
+impl BlockParser {
+    fn current(&self) -> Option<&Line> {
+        self.lines.get(self.index)
+    }
+
+    /// Parses the block starting at the current line, which is indented by `indent`
+    fn parse_block(&mut self, indent: usize) -> Result<Node, ParseError> {
+        let line = self.lines[self.index].clone();
+        if is_sequence_item(&line.text) {
+            self.parse_sequence(indent)
+        } else if split_key(&line.text).is_some() {
+            self.parse_mapping(indent)
+        } else {
+            self.index += 1;
+            parse_inline(&line.text, line.number)
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<Node, ParseError> {
+        let start = self.lines[self.index].number;
+        let mut items = vec![];
+
+        while let Some(line) = self.current().cloned() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation");
+            }
+            if !is_sequence_item(&line.text) {
+                break;
+            }
+
+            let content = line.text[1..].trim_start();
+            if content.is_empty() {
+                self.index += 1;
+                items.push(self.parse_nested(indent, line.number)?);
+            } else {
+                // the content after the dash is parsed as if it started its own, deeper indented line
+                let offset = indent + line.text.len() - content.len();
+                self.lines[self.index] = Line { number: line.number, indent: offset, text: content.to_string() };
+                items.push(self.parse_block(offset)?);
+            }
+        }
+        Ok(Node::new(Value::Sequence(items), start))
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> Result<Node, ParseError> {
+        let start = self.lines[self.index].number;
+        let mut entries: Vec<(String, Node)> = vec![];
+
+        while let Some(line) = self.current().cloned() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation");
+            }
+            if is_sequence_item(&line.text) {
+                break;
+            }
+
+            let (key, rest) = match split_key(&line.text) {
+                Some(entry) => entry,
+                None => return error(line.number, format!("expected 'key: value', found '{}'", line.text))
+            };
+            if entries.iter().any(|(k, _)| *k == key) {
+                return error(line.number, format!("duplicate key '{}'", key));
+            }
+
+            self.index += 1;
+            let value = if rest.is_empty() {
+                // a sequence under a key may be indented at the same level as the key
+                match self.current() {
+                    Some(next) if next.indent == indent && is_sequence_item(&next.text) => self.parse_sequence(indent)?,
+                    _ => self.parse_nested(indent, line.number)?
+                }
+            } else {
+                parse_inline(rest, line.number)?
+            };
+            entries.push((key, value));
+        }
+        Ok(Node::new(Value::Mapping(entries), start))
+    }
+
+    /// Parses the block on the following lines when they are indented deeper, null otherwise
+    fn parse_nested(&mut self, indent: usize, line: usize) -> Result<Node, ParseError> {
+        match self.current() {
+            Some(next) if next.indent > indent => {
+                let nested = next.indent;
+                self.parse_block(nested)
+            }
+            _ => Ok(Node::new(Value::Null, line))
+        }
+    }
+}
+
+/// Parses a scalar or flow collection written on one logical line
+fn parse_inline(text: &str, line: usize) -> Result<Node, ParseError> {
+    let mut parser = FlowParser { chars: text.chars().collect(), position: 0, line };
+    let node = parser.parse_value(false)?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return error(parser.line, format!("unexpected '{}'", parser.chars[parser.position]));
+    }
+    Ok(node)
+}
+
+struct FlowParser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize
+}
+
+impl FlowParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        self.position += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    /// `in_flow` is true inside brackets, where commas and closing brackets end a plain scalar
+    fn parse_value(&mut self, in_flow: bool) -> Result<Node, ParseError> {
+        self.skip_whitespace();
+        let line = self.line;
+        match self.peek() {
+            Some('[') => self.parse_flow_sequence(),
+            Some('{') => self.parse_flow_mapping(),
+            Some(quote @ ('"' | '\'')) => Ok(Node::new(Value::String(self.parse_quoted(quote)?), line)),
+            _ => {
+                let text = self.parse_plain(in_flow, false);
+                Ok(Node::new(plain_scalar(&text), line))
+            }
+        }
+    }
+
+    fn parse_plain(&mut self, in_flow: bool, is_key: bool) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if (in_flow && matches!(c, ',' | ']' | '}')) || (is_key && c == ':') {
+                break;
+            }
+            text.push(c);
+            self.advance();
+        }
+        text.trim().to_string()
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> Result<String, ParseError> {
+        let line = self.line;
+        self.advance();
+        let mut text = String::new();
+        loop {
+            match self.advance() {
+                None => return error(line, "unterminated string"),
+                Some(c) if c == quote => {
+                    // single quoted strings escape a quote by doubling it
+                    if quote == '\'' && self.peek() == Some('\'') {
+                        self.advance();
+                        text.push('\'');
+                    } else {
+                        return Ok(text);
+                    }
+                }
+                Some('\\') if quote == '"' => {
+                    let escaped = match self.advance() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        Some(c) => return error(self.line, format!("unknown escape sequence '\\{}'", c)),
+                        None => return error(line, "unterminated string")
+                    };
+                    text.push(escaped);
+                }
+                Some(c) => text.push(c)
+            }
+        }
+    }
+
+    fn parse_flow_sequence(&mut self) -> Result<Node, ParseError> {
+        let line = self.line;
+        self.advance();
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.advance();
+                return Ok(Node::new(Value::Sequence(items), line));
+            }
+            items.push(self.parse_value(true)?);
+            self.expect_separator(']')?;
+        }
+    }
+
+    fn parse_flow_mapping(&mut self) -> Result<Node, ParseError> {
+        let line = self.line;
+        self.advance();
+        let mut entries: Vec<(String, Node)> = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.advance();
+                return Ok(Node::new(Value::Mapping(entries), line));
+            }
+
+            let key_line = self.line;
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'')) => self.parse_quoted(quote)?,
+                _ => self.parse_plain(true, true)
+            };
+            self.skip_whitespace();
+            if self.advance() != Some(':') {
+                return error(self.line, format!("expected ':' after the key '{}'", key));
+            }
+            if entries.iter().any(|(k, _)| *k == key) {
+                return error(key_line, format!("duplicate key '{}'", key));
+            }
+            let value = self.parse_value(true)?;
+            entries.push((key, value));
+            self.expect_separator('}')?;
+        }
+    }
+
+    /// Consumes the comma between items, leaving the closing bracket for the caller
+    fn expect_separator(&mut self, close: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.advance();
+                Ok(())
+            }
+            Some(c) if c == close => Ok(()),
+            Some(c) => error(self.line, format!("expected ',' or '{}', found '{}'", close, c)),
+            None => error(self.line, format!("expected '{}'", close))
+        }
+    }
+}
+
+fn plain_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => {
+            let numeric = text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.');
+            match text.parse::<f32>() {
+                Ok(number) if numeric => Value::Number(number),
+                _ => Value::String(text.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: f32, line: usize) -> Node {
+        Node::new(Value::Number(value), line)
+    }
+
+    fn string(value: &str, line: usize) -> Node {
+        Node::new(Value::String(value.to_string()), line)
+    }
+
+    #[test]
+    fn parsing_scalars() {
+        assert_eq!(parse("42").unwrap().value, Value::Number(42.0));
+        assert_eq!(parse("-0.5").unwrap().value, Value::Number(-0.5));
+        assert_eq!(parse("true").unwrap().value, Value::Bool(true));
+        assert_eq!(parse("~").unwrap().value, Value::Null);
+        assert_eq!(parse("rotate-y").unwrap().value, Value::String("rotate-y".to_string()));
+        assert_eq!(parse("'it''s' # comment").unwrap().value, Value::String("it's".to_string()));
+        assert_eq!(parse("\"a # b\"").unwrap().value, Value::String("a # b".to_string()));
+    }
+
+    #[test]
+    fn parsing_a_block_mapping() {
+        let node = parse("# camera\nwidth: 100\nfrom: [ 0, 1.5, -5 ]\nname: main\n").unwrap();
+        assert_eq!(node.line, 2);
+        assert_eq!(node.get("width"), Some(&number(100.0, 2)));
+        assert_eq!(node.get("name"), Some(&string("main", 4)));
+        let from = node.get("from").unwrap();
+        assert_eq!(from.value, Value::Sequence(vec![number(0.0, 3), number(1.5, 3), number(-5.0, 3)]));
+    }
+
+    #[test]
+    fn parsing_a_sequence_of_mappings() {
+        let source = "\
+- add: light
+  at: [ -10, 10, -10 ]
+
+- add: sphere
+  transform:
+    - [ translate, 1, 0, 0 ]
+  material:
+    color: [ 1, 0, 0 ]
+";
+        let node = parse(source).unwrap();
+        let items = match node.value {
+            Value::Sequence(items) => items,
+            other => panic!("expected a sequence, found {:?}", other)
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].get("add"), Some(&string("light", 1)));
+        assert_eq!(items[1].line, 4);
+        let transform = items[1].get("transform").unwrap();
+        assert_eq!(transform.value, Value::Sequence(vec![
+            Node::new(Value::Sequence(vec![string("translate", 6), number(1.0, 6), number(0.0, 6), number(0.0, 6)]), 6)
+        ]));
+        assert_eq!(items[1].get("material").unwrap().get("color").unwrap().line, 8);
+    }
+
+    #[test]
+    fn a_sequence_may_be_indented_at_the_level_of_its_key() {
+        let node = parse("transform:\n- [ scale, 2, 2, 2 ]\n- standard\nmaterial: red\n").unwrap();
+        match &node.get("transform").unwrap().value {
+            Value::Sequence(items) => assert_eq!(items.len(), 2),
+            other => panic!("expected a sequence, found {:?}", other)
+        }
+        assert_eq!(node.get("material"), Some(&string("red", 4)));
+    }
+
+    #[test]
+    fn parsing_a_json_document_over_several_lines() {
+        let source = "[\n  {\"add\": \"camera\",\n   \"width\": 10},\n  {\"add\": \"light\", \"at\": [1, 2, 3]}\n]\n";
+        let node = parse(source).unwrap();
+        let items = match node.value {
+            Value::Sequence(items) => items,
+            other => panic!("expected a sequence, found {:?}", other)
+        };
+        assert_eq!(items[0].get("width"), Some(&number(10.0, 3)));
+        assert_eq!(items[1].line, 4);
+        assert_eq!(items[1].get("add"), Some(&string("light", 4)));
+    }
+
+    #[test]
+    fn errors_report_the_line_they_happen_on() {
+        assert_eq!(parse("a: 1\nb: [1, 2\n").unwrap_err().line, 2);
+        assert_eq!(parse("a: 1\n  b: 2\n").unwrap_err().line, 2);
+        assert_eq!(parse("a: 1\na: 2\n").unwrap_err().line, 2);
+        assert_eq!(parse("a: 1\n\tb: 2\n").unwrap_err().line, 2);
+        assert_eq!(parse("[\n1,\n2 }").unwrap_err().line, 3);
+        let error = parse("- a\nb: 1\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unexpected content after the end of the document");
+    }
+}
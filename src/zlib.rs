@@ -0,0 +1,530 @@
+/// How hard `compress` works to shrink the data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Stored deflate blocks, the data is copied as is. Fast and simple, but large.
+    Stored,
+    /// LZ77 matching with the fixed Huffman codes of deflate
+    Fixed
+}
+
+/// Adler-32 checksum that ends every zlib stream
+pub fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before b may overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULO;
+        b %= MODULO;
+    }
+    (b << 16) | a
+}
+
+/// Writes bits least significant first, the order deflate packs them in
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter { bytes: vec![], buffer: 0, count: 0 }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are defined most significant bit first, so they are reversed on the way out
+    fn write_code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.write_bits(reversed, length);
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer = 0;
+            self.count = 0;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align_to_byte();
+        self.bytes
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51,
+                                           59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4,
+                                           5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385,
+                                             513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10,
+                                             11, 11, 12, 12, 13, 13];
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried, trading speed for size
+const MAX_CHAIN: usize = 64;
+
+/// Writes a literal byte or the end of block marker (256) with the fixed Huffman code
+fn write_fixed_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8)
+    }
+}
+
+fn write_fixed_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap();
+    write_fixed_literal(writer, 257 + code as u32);
+    writer.write_bits((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+
+    let code = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+    writer.write_code(code as u32, 5);
+    writer.write_bits((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+}
+
+fn hash(data: &[u8], position: usize) -> usize {
+    let value = (data[position] as usize) << 16 | (data[position + 1] as usize) << 8 | data[position + 2] as usize;
+    value.wrapping_mul(2_654_435_761) >> 17 & 0x7FFF
+}
+
+/// Compresses the data as a single deflate block with the fixed Huffman codes.
+/// Repeated sequences are found through hash chains over the last 32 KiB.
+fn deflate_fixed(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    // BFINAL = 1, BTYPE = 01
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut head = vec![usize::MAX; 0x8000];
+    let mut previous = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |position: usize, head: &mut [usize], previous: &mut [usize]| {
+        if position + MIN_MATCH <= data.len() {
+            let h = hash(data, position);
+            previous[position % WINDOW_SIZE] = head[h];
+            head[h] = position;
+        }
+    };
+
+    let mut position = 0;
+    while position < data.len() {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if position + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(data, position)];
+            let max_length = MAX_MATCH.min(data.len() - position);
+            let mut chain = 0;
+            while candidate != usize::MAX && position - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let length = data[candidate..].iter().zip(&data[position..position + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = position - candidate;
+                    if length == max_length {
+                        break;
+                    }
+                }
+                let next = previous[candidate % WINDOW_SIZE];
+                // the slot may have been reused by a newer position, which would loop forward
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            write_fixed_match(&mut writer, best_length, best_distance);
+            for p in position..position + best_length {
+                insert(p, &mut head, &mut previous);
+            }
+            position += best_length;
+        } else {
+            write_fixed_literal(&mut writer, data[position] as u32);
+            insert(position, &mut head, &mut previous);
+            position += 1;
+        }
+    }
+
+    write_fixed_literal(&mut writer, 256);
+    writer.finish()
+}
+
+/// Wraps the data in stored deflate blocks of at most 65535 bytes
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 5);
+    let mut chunks = data.chunks(65535).peekable();
+    if chunks.peek().is_none() {
+        bytes.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(chunk) = chunks.next() {
+        // BFINAL on the last block, BTYPE = 00, then padding to the byte boundary
+        bytes.push(if chunks.peek().is_none() { 1 } else { 0 });
+        let length = chunk.len() as u16;
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&(!length).to_le_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+    bytes
+}
+
+/// Returns a zlib stream (RFC 1950) holding the data
+pub fn compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    // deflate with a 32 KiB window, no preset dictionary, header checksum making it a multiple of 31
+    let mut stream = vec![0x78, 0x01];
+    stream.extend(match compression {
+        Compression::Stored => deflate_stored(data),
+        Compression::Fixed => deflate_fixed(data)
+    });
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// Reads bits least significant first
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit: u32
+}
+
+impl BitReader<'_> {
+    fn read_bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.position).ok_or("unexpected end of the deflate data")?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.position += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.position += 1;
+        }
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&[u8], String> {
+        let end = self.position + count;
+        let bytes = self.data.get(self.position..end).ok_or("unexpected end of a stored block")?;
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time
+struct Huffman {
+    /// How many codes have each length, up to 15 bits
+    counts: [u16; 16],
+    /// Symbols ordered by code
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // reject codes that assign more codes of a length than there is room for
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err("over-subscribed Huffman code".to_string());
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.read_bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+/// The order code length code lengths are stored in by dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn fixed_codes() -> Result<(Huffman, Huffman), String> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.read_bits(5)? as usize + 257;
+    let distance_count = reader.read_bits(5)? as usize + 1;
+    let code_length_count = reader.read_bits(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err("too many codes in a dynamic block".to_string());
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = reader.read_bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => match index.checked_sub(1) {
+                Some(previous) => (lengths[previous], 3 + reader.read_bits(2)? as usize),
+                None => return Err("repeated code length without a previous one".to_string())
+            },
+            17 => (0, 3 + reader.read_bits(3)? as usize),
+            _ => (0, 11 + reader.read_bits(7)? as usize)
+        };
+        if index + repeat > lengths.len() {
+            return Err("code lengths overflow the dynamic block".to_string());
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err("the dynamic block has no end of block code".to_string());
+    }
+    Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?))
+}
+
+fn inflate_block(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize, literals: &Huffman, distances: &Huffman) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                check_limit(output.len() + 1, limit)?;
+                output.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            257..=285 => {
+                let code = symbol - 257;
+                let length = LENGTH_BASE[code] as usize + reader.read_bits(LENGTH_EXTRA[code] as u32)? as usize;
+                let code = distances.decode(reader)? as usize;
+                if code >= 30 {
+                    return Err("invalid distance code".to_string());
+                }
+                let distance = DISTANCE_BASE[code] as usize + reader.read_bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                if distance > output.len() {
+                    return Err("distance points before the start of the data".to_string());
+                }
+                check_limit(output.len() + length, limit)?;
+                // the copy may overlap the bytes it produces, so it goes one byte at a time
+                let start = output.len() - distance;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+            _ => return Err("invalid literal or length code".to_string())
+        }
+    }
+}
+
+fn check_limit(size: usize, limit: usize) -> Result<(), String> {
+    if size > limit {
+        return Err(format!("the data inflates past {} bytes", limit));
+    }
+    Ok(())
+}
+
+/// Decompresses raw deflate data of at most `limit` bytes, returning the bytes and where the deflate data ended
+fn inflate(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader { data, position: 0, bit: 0 };
+    let mut output = vec![];
+    loop {
+        let last = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = reader.read_bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let complement = u16::from_le_bytes([header[2], header[3]]);
+                if length != !complement {
+                    return Err("stored block length does not match its complement".to_string());
+                }
+                check_limit(output.len() + length as usize, limit)?;
+                output.extend_from_slice(reader.read_bytes(length as usize)?);
+            }
+            1 => {
+                let (literals, distances) = fixed_codes()?;
+                inflate_block(&mut reader, &mut output, limit, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut output, limit, &literals, &distances)?;
+            }
+            _ => return Err("invalid deflate block type".to_string())
+        }
+        if last {
+            reader.align_to_byte();
+            return Ok((output, reader.position));
+        }
+    }
+}
+
+/// Decompresses a zlib stream, checking its header and Adler-32 checksum.
+/// Fails as soon as the output grows past `limit` bytes, so a small stream cannot claim unbounded memory.
+pub fn decompress(stream: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if stream.len() < 6 {
+        return Err("the zlib stream is too short".to_string());
+    }
+    let (cmf, flg) = (stream[0], stream[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err("the zlib stream does not use deflate".to_string());
+    }
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("invalid zlib header checksum".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+
+    let (data, end) = inflate(&stream[2..], limit)?;
+    let checksum = stream.get(2 + end..2 + end + 4).ok_or("the zlib stream has no checksum")?;
+    if adler32(&data).to_be_bytes() != checksum {
+        return Err("Adler-32 checksum mismatch".to_string());
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_of_known_inputs() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        let long = vec![0xFF; 100_000];
+        let expected = {
+            let (mut a, mut b) = (1u64, 0u64);
+            for &byte in &long {
+                a = (a + byte as u64) % 65521;
+                b = (b + a) % 65521;
+            }
+            (b << 16 | a) as u32
+        };
+        assert_eq!(adler32(&long), expected);
+    }
+
+    #[test]
+    fn stored_zlib_stream_layout() {
+        let stream = compress(b"hello", Compression::Stored);
+        assert_eq!(stream, vec![0x78, 0x01, 1, 5, 0, 0xFA, 0xFF, b'h', b'e', b'l', b'l', b'o', 0x06, 0x2C, 0x02, 0x15]);
+        assert_eq!((0x78 * 256 + 0x01) % 31, 0);
+    }
+
+    #[test]
+    fn stored_blocks_are_split_at_65535_bytes() {
+        let data = vec![7; 70_000];
+        let stream = compress(&data, Compression::Stored);
+        assert_eq!(stream.len(), 2 + 5 + 65535 + 5 + (70_000 - 65535) + 4);
+        assert_eq!(stream[2], 0);
+        assert_eq!(stream[2 + 5 + 65535], 1);
+    }
+
+    #[test]
+    fn fixed_huffman_shrinks_repetitive_data() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 7) as u8).collect();
+        let stream = compress(&data, Compression::Fixed);
+        assert!(stream.len() < 200, "{} bytes", stream.len());
+        assert_eq!(stream[stream.len() - 4..], adler32(&data).to_be_bytes());
+    }
+
+    #[test]
+    fn decompressing_what_compress_produces() {
+        let data: Vec<u8> = (0..70_000u64).map(|i| (i * i % 251) as u8).chain(b"abcabcabcabc".iter().copied()).collect();
+        for compression in [Compression::Stored, Compression::Fixed] {
+            assert_eq!(decompress(&compress(&data, compression), usize::MAX).unwrap(), data);
+        }
+        assert_eq!(decompress(&compress(b"", Compression::Fixed), usize::MAX).unwrap(), b"");
+    }
+
+    #[test]
+    fn decompressing_a_dynamic_huffman_stream() {
+        // zlib.compress(expected, 9) picks a dynamic Huffman block for this skewed input
+        let expected = b"abbaadbabbabadcaabaababcbaabcaabacdbababcaacbaacaccaabbddabcdaabcbadadaaaaaaab\
+                         acbcaabcababbabadabddacabbbabcabdbbabbabcb";
+        let stream = [
+            0x78, 0xda, 0x35, 0x8c, 0x81, 0x0d, 0xc0, 0x40, 0x08, 0x02, 0x67, 0x3d, 0x60, 0xff, 0x19, 0x8a,
+            0x7c, 0xaa, 0x46, 0x05, 0x11, 0x24, 0x88, 0xe8, 0x10, 0x31, 0xed, 0x2d, 0xf9, 0xc6, 0x90, 0xef,
+            0xb8, 0xfd, 0x38, 0xe3, 0x63, 0x95, 0x94, 0x0a, 0x13, 0xa6, 0xf9, 0xa2, 0xea, 0x3d, 0x99, 0xdf,
+            0x90, 0x53, 0x16, 0x6a, 0x16, 0x8a, 0xde, 0xc1, 0xfa, 0x00, 0xd8, 0xbb, 0x2d, 0xe7
+        ];
+        assert_eq!(stream[2] >> 1 & 3, 2);
+        assert_eq!(decompress(&stream, usize::MAX).unwrap(), &expected[..]);
+    }
+
+    #[test]
+    fn corrupted_streams_are_errors() {
+        let mut stream = compress(b"some data to check", Compression::Fixed);
+        let last = stream.len() - 1;
+        stream[last] ^= 1;
+        assert_eq!(decompress(&stream, usize::MAX).unwrap_err(), "Adler-32 checksum mismatch");
+        assert!(decompress(&[0x78, 0x02, 0, 0, 0, 0], usize::MAX).is_err());
+        assert!(decompress(&compress(b"truncated", Compression::Fixed)[..5], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn decompressing_stops_at_the_limit() {
+        let data = vec![0; 100_000];
+        for compression in [Compression::Stored, Compression::Fixed] {
+            let stream = compress(&data, compression);
+            assert_eq!(decompress(&stream, data.len()).unwrap(), data);
+            assert_eq!(decompress(&stream, 1000).unwrap_err(), "the data inflates past 1000 bytes");
+        }
+    }
+}